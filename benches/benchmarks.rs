@@ -1,5 +1,6 @@
 use criterion::*;
 use nalgebra_glm::{Mat4, Vec3};
+use turbo_ecs::archetypes::ArchetypeGrowth;
 use turbo_ecs::create_archetype;
 use turbo_ecs::prelude::*;
 
@@ -8,7 +9,7 @@ const COUNT: usize = 10000;
 #[derive(Default, Component)]
 struct Transform(Mat4);
 
-#[derive(Default, Component)]
+#[derive(Default, Clone, Component)]
 struct Translation(Vec3);
 
 #[derive(Default, Component)]
@@ -34,6 +35,29 @@ fn create_entities(c: &mut Criterion) {
     });
 }
 
+fn create_entities_incrementally(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Create entities incrementally");
+    for (name, growth) in [("Exact", ArchetypeGrowth::Exact), ("Double", ArchetypeGrowth::Double)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let mut ecs = EcsContext::new();
+                    ecs.set_archetype_growth(growth);
+                    let archetype =
+                        create_archetype!(ecs, [Transform, Translation, Rotation, Velocity]);
+                    (ecs, archetype)
+                },
+                |(mut ecs, archetype)| {
+                    for _ in 0..COUNT {
+                        ecs.create_entity_from_archetype(archetype);
+                    }
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+}
+
 fn destroy_entities(c: &mut Criterion) {
     c.bench_function("Destroy entities", |b| {
         b.iter_batched(
@@ -69,6 +93,21 @@ fn iterate_entities(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("Prefetched", |b| {
+        let mut ecs = EcsContext::new();
+        let archetype = create_archetype!(ecs, [Transform, Translation, Rotation, Velocity]);
+        let _ = ecs.create_entities_from_archetype(archetype, COUNT);
+
+        b.iter(|| {
+            ecs.filter()
+                .include::<(&mut Transform, &mut Translation, &Velocity, &Rotation)>()
+                .for_each_prefetched(|(m, t, v, r)| {
+                    t.0 += v.0;
+                    m.0 = Mat4::new_translation(&t.0) * Mat4::new_rotation(r.0);
+                })
+        });
+    });
+
     group.bench_function("Multi-threaded", |b| {
         let mut ecs = EcsContext::new();
         let archetype = create_archetype!(ecs, [Transform, Translation, Rotation, Velocity]);
@@ -85,10 +124,41 @@ fn iterate_entities(c: &mut Criterion) {
     });
 }
 
+fn write_component_column(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Write component column");
+    let values: Vec<_> = (0..COUNT)
+        .map(|i| Translation(Vec3::new(i as f32, 0.0, 0.0)))
+        .collect();
+
+    group.bench_function("Per entity", |b| {
+        let mut ecs = EcsContext::new();
+        let archetype = create_archetype!(ecs, [Transform, Translation, Rotation, Velocity]);
+        let entities: Vec<_> = ecs
+            .create_entities_from_archetype(archetype, COUNT)
+            .collect();
+
+        b.iter(|| {
+            for (entity, value) in entities.iter().zip(values.iter()) {
+                *ecs.get_component_mut::<Translation>(entity).unwrap() = value.clone();
+            }
+        });
+    });
+
+    group.bench_function("Column", |b| {
+        let mut ecs = EcsContext::new();
+        let archetype = create_archetype!(ecs, [Transform, Translation, Rotation, Velocity]);
+        let _ = ecs.create_entities_from_archetype(archetype, COUNT);
+
+        b.iter(|| ecs.write_component_column(archetype, &values));
+    });
+}
+
 criterion_group!(
     benchmarks,
     create_entities,
+    create_entities_incrementally,
     destroy_entities,
     iterate_entities,
+    write_component_column,
 );
 criterion_main!(benchmarks);
@@ -0,0 +1,55 @@
+use crate::components::{Component, ComponentType};
+use crate::archetypes::{Archetype, ArchetypeStore};
+
+/// A builder for [archetypes](Archetype), allowing [components](Component) to be selected at runtime.
+///
+/// Created through [EcsContext::build_archetype](crate::context::EcsContext::build_archetype).
+pub struct ArchetypeBuilder<'l> {
+	store: &'l mut ArchetypeStore,
+	components: Vec<ComponentType>,
+	capacity: usize,
+}
+
+impl<'l> ArchetypeBuilder<'l> {
+	pub(crate) fn new(store: &'l mut ArchetypeStore) -> Self {
+		Self {
+			store,
+			components: vec![],
+			capacity: 0,
+		}
+	}
+
+	/// Adds a [component](Component) of type `T` to the [archetype](Archetype).  
+	/// Adding the same [component](Component) more than once has no effect.
+	pub fn with<T: Component>(self) -> Self {
+		self.with_type(ComponentType::of::<T>())
+	}
+
+	/// Adds a [component](Component) of type `T` to the [archetype](Archetype) if `condition` is true.
+	pub fn with_if<T: Component>(self, condition: bool) -> Self {
+		match condition {
+			true => self.with::<T>(),
+			false => self,
+		}
+	}
+
+	/// Adds a [component type](ComponentType) to the [archetype](Archetype).  
+	/// Adding the same [component type](ComponentType) more than once has no effect.
+	pub fn with_type(mut self, component: ComponentType) -> Self {
+		if !self.components.contains(&component) {
+			self.components.push(component);
+		}
+		self
+	}
+
+	/// Sets the minimum capacity of the [archetype](Archetype).
+	pub fn capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity;
+		self
+	}
+
+	/// Creates the [archetype](Archetype), or returns the existing one with the same [components](Component).
+	pub fn create(self) -> Archetype {
+		self.store.create_archetype_with_capacity(&self.components, self.capacity)
+	}
+}
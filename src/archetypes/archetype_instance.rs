@@ -1,30 +1,58 @@
-use crate::components::{Component, ComponentFrom, ComponentType, ComponentTypeInfo};
-use crate::data_structures::{AnyBuffer, BitField, RangeAllocator};
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use crate::components::{
+	Component, ComponentFrom, ComponentId, ComponentInitializer, ComponentRef, ComponentStorage, ComponentType,
+	ComponentTypeInfo, SparseStorage,
+};
+use crate::data_structures::{AnyBuffer, AnySparseSet, BitField, RangeAllocator, SparseSet};
+use rayon::prelude::{IntoParallelIterator, ParallelExtend, ParallelIterator};
 use std::hash::{BuildHasherDefault, Hash};
 use nohash_hasher::NoHashHasher;
 use std::collections::HashMap;
 use std::any::TypeId;
-use std::ops::Range;
+use std::ops::{ControlFlow, Range};
 use paste::paste;
-use crate::entities::Entity;
+use crate::entities::{Entity, EntityQueryData};
 
 type Hasher = BuildHasherDefault<NoHashHasher<u64>>;
 
 /// An [EcsContext](crate::context::EcsContext) relative handle to a set of [Component](crate::components::Component)s.
-#[derive(Default, Hash, Eq, PartialEq, Copy, Clone)]
+///
+/// The default [Archetype] contains no [components](crate::components::Component) and is valid in every context.
+#[derive(Default, Hash, Eq, PartialEq, Copy, Clone, Debug)]
 pub struct Archetype {
 	pub(crate) index: usize,
+	pub(crate) registry_id: u32,
+}
+
+/// How an [archetype](Archetype)'s storage grows when it runs out of free slots.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ArchetypeGrowth {
+	/// Grow by exactly the number of missing slots.
+	Exact,
+	/// Grow by at least the current capacity, amortizing reallocations when spawning one entity at a time.
+	#[default]
+	Double,
+}
+
+impl ArchetypeGrowth {
+	/// Get the capacity to grow to when `needed` more slots are required.
+	pub fn grow(self, capacity: usize, needed: usize) -> usize {
+		match self {
+			ArchetypeGrowth::Exact => capacity + needed,
+			ArchetypeGrowth::Double => capacity + usize::max(needed, capacity),
+		}
+	}
 }
 
 pub struct ArchetypeInstance {
 	id: Archetype,
+	growth: ArchetypeGrowth,
 	bitfield: BitField,
 	entities: Vec<Entity>,
 	allocator: RangeAllocator,
 	component_bitfield: BitField,
 	components: Vec<ComponentType>,
 	buffers: HashMap<TypeId, AnyBuffer, Hasher>,
+	needs_drop: bool,
 }
 
 impl ArchetypeInstance {
@@ -34,7 +62,7 @@ impl ArchetypeInstance {
 
 	pub fn with_capacity(id: Archetype, components: &[ComponentType], capacity: usize) -> Self {
 		let mut component_bitfield = BitField::new();
-		let entities = Vec::with_capacity(capacity);
+		let entities = vec![Entity::default(); capacity];
 		let bitfield = BitField::with_capacity(capacity);
 		let allocator = RangeAllocator::with_capacity(capacity);
 
@@ -51,18 +79,33 @@ impl ArchetypeInstance {
 			}
 		}));
 
+		// Components are sorted by id, so that the order doesn't depend on how the archetype was reached.
+		let mut components = components.to_vec();
+		components.sort_unstable_by_key(|t| t.id().value());
+		components.dedup();
+
 		Self {
 			id,
+			growth: ArchetypeGrowth::default(),
+			needs_drop: buffers.values().any(|b| b.needs_drop()),
 			buffers,
 			bitfield,
 			entities,
 			allocator,
 			component_bitfield,
-			components: components.into(),
+			components,
 		}
 	}
 
-	/// Allocate `count` slots, setting all components to their default value.
+	/// Replaces the function used to initialize the values of one of the archetype's [components](Component).
+	pub fn set_initializer(&mut self, initializer: ComponentInitializer) {
+		let id = initializer.id();
+		let component = self.components.iter().find(|t| t.id() == id);
+		let component = component.expect("Archetype does not contain the initialized component");
+		initializer.apply(self.buffers.get_mut(&component.type_id()).unwrap());
+	}
+
+	/// Allocate `count` slots, setting all components to their default (or initializer provided) value.
 	/// The returned slot chunks might be fragmented.
 	pub fn take_slots(&mut self, count: usize, ranges: &mut Vec<Range<usize>>) {
 		self.take_slots_no_init(count, ranges);
@@ -81,16 +124,9 @@ impl ArchetypeInstance {
 		ranges.clear();
 		match self.allocator.try_allocate_fragmented(count, ranges) {
 			Ok(_) => {},
-			Err(needed) => unsafe {
-				for buffer in self.buffers.values_mut() {
-					buffer.ensure_capacity(self.allocator.capacity() + needed);
-				}
-
-				self.entities.reserve(needed);
-				self.entities.set_len(needed);
-
+			Err(needed) => {
+				self.ensure_capacity(self.growth.grow(self.allocator.capacity(), needed));
 				self.allocator.allocate_fragmented(count, ranges);
-				self.bitfield.ensure_capacity(self.allocator.capacity());
 			},
 		};
 	}
@@ -104,8 +140,10 @@ impl ArchetypeInstance {
 		self.bitfield.clear();
 		self.bitfield.set_batch_unchecked::<true>(slots);
 		for range in self.bitfield.iter_ranges() {
-			for buffer in self.buffers.values_mut() {
-				buffer.drop_values(range.clone());
+			if self.needs_drop {
+				for buffer in self.buffers.values_mut() {
+					buffer.drop_values(range.clone());
+				}
 			}
 			self.allocator.free(range);
 		}
@@ -117,7 +155,7 @@ impl ArchetypeInstance {
 	/// # Safety
 	/// - All slots must be within range from 0 to `capacity`. Repeated values are allowed.
 	/// - All associated components' ownership must be transferred to another archetype,
-	/// failure to do so will result in memory leaks and/or other unintended behaviour.
+	///   failure to do so will result in memory leaks and/or other unintended behaviour.
 	pub unsafe fn return_slots_no_drop(&mut self, slots: &[usize]) {
 		self.bitfield.clear();
 		self.bitfield.set_batch_unchecked::<true>(slots);
@@ -132,7 +170,7 @@ impl ArchetypeInstance {
 	/// # Safety
 	/// - `slot` must be within range from 0 to `capacity`
 	/// - All associated components' ownership must be transferred to another archetype,
-	/// failure to do so will result in memory leaks and/or other unintended behaviour.
+	///   failure to do so will result in memory leaks and/or other unintended behaviour.
 	pub unsafe fn return_slot_no_drop(&mut self, slot: usize) {
 		self.allocator.free(slot..slot + 1);
 	}
@@ -141,16 +179,95 @@ impl ArchetypeInstance {
 		set.is_subset_of(&self.component_bitfield)
 	}
 
+	pub(crate) fn matches(&self, query: &EntityQueryData) -> bool {
+		let (include, exclude) = (query.table_include(), query.table_exclude());
+		self.matches_query(include) && !exclude.intersects(&self.component_bitfield)
+	}
+
 	pub fn ensure_capacity(&mut self, capacity: usize) {
 		if self.allocator.capacity() < capacity {
 			self.bitfield.ensure_capacity(capacity);
 			self.allocator.ensure_capacity(capacity);
+			self.entities.resize_with(capacity, Entity::default);
 			for buffer in self.buffers.values_mut() {
 				buffer.ensure_capacity(capacity);
 			}
 		}
 	}
 
+	/// Converts the values of the archetype's `Old` [component](Component) into a new column of `New` values,
+	/// without modifying the archetype.
+	///
+	/// If `conv` panics, the values converted so far are dropped by the partially filled column.
+	pub fn convert_column<Old: Component, New: Component>(&self, conv: impl Fn(&Old) -> New) -> MigratedColumn {
+		let old = ComponentType::of::<Old>();
+		let src = self.buffers.get(&old.type_id());
+		let src = src.expect("Archetype does not contain the migrated component").as_slice::<Old>();
+
+		let mut column = MigratedColumn {
+			buffer: ComponentType::of::<New>().create_buffer(),
+			ranges: Vec::new(),
+		};
+		column.buffer.ensure_capacity(self.capacity());
+
+		for range in self.allocator.used_ranges() {
+			column.ranges.push(range.start..range.start);
+			for slot in range {
+				// SAFETY: Every slot in a used range holds an initialized value.
+				let value = conv(unsafe { src[slot].assume_init_ref() });
+				column.buffer.as_mut_slice::<New>()[slot].write(value);
+				column.ranges.last_mut().unwrap().end += 1;
+			}
+		}
+
+		column
+	}
+
+	/// Replaces the archetype's `Old` [component](Component) with `New`,
+	/// whose values are taken from a column created by [ArchetypeInstance::convert_column].
+	///
+	/// Returns the column of `Old` values, which are dropped along with it.
+	pub fn replace_column<Old: Component, New: Component>(&mut self, mut column: MigratedColumn) -> MigratedColumn {
+		let (old, new) = (ComponentType::of::<Old>(), ComponentType::of::<New>());
+		assert!(
+			!self.component_bitfield.get(new.id().value()),
+			"Archetype already contains the migrated component"
+		);
+
+		let position = self.components.iter().position(|t| *t == old);
+		let position = position.expect("Archetype does not contain the migrated component");
+		let buffer = std::mem::replace(&mut column.buffer, self.buffers.remove(&old.type_id()).unwrap());
+		column.ranges = self.allocator.used_ranges().collect();
+
+		self.components[position] = new.clone();
+		self.components.sort_unstable_by_key(|t| t.id().value());
+		self.component_bitfield.set(old.id().value(), false);
+		self.component_bitfield.set(new.id().value(), true);
+		self.buffers.insert(new.type_id(), buffer);
+		self.needs_drop = self.buffers.values().any(|b| b.needs_drop());
+		column
+	}
+
+	pub fn set_growth(&mut self, growth: ArchetypeGrowth) {
+		self.growth = growth;
+	}
+
+	pub fn capacity(&self) -> usize {
+		self.allocator.capacity()
+	}
+
+	pub fn len(&self) -> usize {
+		self.allocator.used()
+	}
+
+	pub fn free_slots(&self) -> usize {
+		self.allocator.available()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.allocator.used() == 0
+	}
+
 	pub fn get_component<T: Component>(&self, slot: usize) -> Option<&T> {
 		unsafe {
 			let buffer = self.buffers.get(&TypeId::of::<T>())?;
@@ -171,6 +288,59 @@ impl ArchetypeInstance {
 		}
 	}
 
+	/// Invokes `func` with the type and a pointer to every component stored in `slot`.
+	///
+	/// # Safety
+	/// `slot` must be occupied.
+	pub unsafe fn for_each_component(&self, slot: usize, mut func: impl FnMut(&ComponentType, *const u8)) {
+		for component in self.components.iter() {
+			if let Some(buffer) = self.buffers.get(&component.type_id()) {
+				func(component, buffer.value_ptr(slot));
+			}
+		}
+	}
+
+	/// Gets the values of a [component](Component) of type `T` over the archetype's occupied slots.  
+	/// Returns [None] if the archetype does not contain `T`.
+	///
+	/// # Panics
+	/// Panics if the occupied slots are not contiguous.
+	pub fn column_mut<T: Component>(&mut self) -> Option<&mut [T]> {
+		let buffer = self.buffers.get_mut(&TypeId::of::<T>())?;
+		let mut ranges = self.allocator.used_ranges();
+		let range = ranges.next().unwrap_or(0..0);
+		assert!(ranges.next().is_none(), "Archetype slots are fragmented");
+
+		unsafe { Some(&mut buffer.as_mut_slice_unchecked::<T>()[range]) }
+	}
+
+	/// Overwrites the values of a [component](Component) of type `T` over the archetype's occupied slots, in slot order.  
+	/// Returns `false` if the archetype does not contain `T`.
+	///
+	/// # Panics
+	/// Panics if the number of values does not match the number of occupied slots.
+	pub fn write_column<T: Component + Clone>(&mut self, values: &[T]) -> bool {
+		let buffer = match self.buffers.get_mut(&TypeId::of::<T>()) {
+			Some(buffer) => buffer,
+			None => return false,
+		};
+		assert_eq!(
+			values.len(),
+			self.allocator.used(),
+			"Values do not match the number of entities"
+		);
+
+		let column = unsafe { buffer.as_mut_slice_unchecked::<T>() };
+		let mut values = values;
+		for range in self.allocator.used_ranges() {
+			let (chunk, rest) = values.split_at(range.len());
+			column[range].clone_from_slice(chunk);
+			values = rest;
+		}
+
+		true
+	}
+
 	pub fn id(&self) -> Archetype {
 		self.id
 	}
@@ -183,6 +353,22 @@ impl ArchetypeInstance {
 		&self.component_bitfield
 	}
 
+	/// Iterate over the number of bytes allocated for each component's values.
+	pub fn component_bytes(&self) -> impl Iterator<Item = (ComponentId, usize)> + '_ {
+		let buffers = self.components.iter().filter_map(|t| Some((t.id(), self.buffers.get(&t.type_id())?)));
+		buffers.map(|(id, buffer)| (id, buffer.size_in_bytes()))
+	}
+
+	/// Iterate over the occupied slot ranges in ascending slot order.
+	pub fn used_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+		self.allocator.used_ranges()
+	}
+
+	/// Gets a pointer to the [entities](Entity) stored in the archetype's slots.
+	pub fn entities_ptr(&self) -> *const Entity {
+		self.entities.as_ptr()
+	}
+
 	pub fn entities_mut(&mut self) -> &mut [Entity] {
 		&mut self.entities
 	}
@@ -195,6 +381,49 @@ impl ArchetypeInstance {
 		}
 	}
 
+	/// Checks if all components of the archetype can be cloned.
+	pub fn can_clone(&self) -> bool {
+		self.buffers.values().all(AnyBuffer::can_clone)
+	}
+
+	/// Clones the components at `src_idx` into `dst_idx`.
+	///
+	/// # Safety
+	/// - The components at `src_idx` must be initialized.
+	/// - The components at `dst_idx` must be uninitialized.
+	/// - `src_idx` and `dst_idx` must be distinct and within range from 0 to `capacity`.
+	pub unsafe fn clone_components(&mut self, src_idx: usize, dst_idx: usize) {
+		for buffer in self.buffers.values_mut() {
+			buffer.clone_within(src_idx, dst_idx);
+		}
+	}
+
+	/// Default-initializes the components at `dst_idx` that are not present in `other`.
+	///
+	/// # Safety
+	/// - `dst_idx` must be within range from 0 to `capacity`.
+	/// - The components at `dst_idx` must be uninitialized.
+	pub unsafe fn default_components_not_in(&mut self, other: &ArchetypeInstance, dst_idx: usize) {
+		for (key, buffer) in self.buffers.iter_mut() {
+			if !other.buffers.contains_key(key) {
+				buffer.default_values(dst_idx..dst_idx + 1);
+			}
+		}
+	}
+
+	/// Drops the components at `src_idx` that are not present in `other`.
+	///
+	/// # Safety
+	/// - `src_idx` must be within range from 0 to `capacity`.
+	/// - The components at `src_idx` must be initialized.
+	pub unsafe fn drop_components_not_in(&mut self, other: &ArchetypeInstance, src_idx: usize) {
+		for (key, buffer) in self.buffers.iter_mut() {
+			if !other.buffers.contains_key(key) {
+				buffer.drop_values(src_idx..src_idx + 1);
+			}
+		}
+	}
+
 	pub unsafe fn copy_component_range(&self, dst: &mut ArchetypeInstance, src_range: Range<usize>, dst_idx: usize) {
 		for (key, src) in self.buffers.iter() {
 			if let Some(dst) = dst.buffers.get_mut(key) {
@@ -206,8 +435,12 @@ impl ArchetypeInstance {
 
 impl Drop for ArchetypeInstance {
 	fn drop(&mut self) {
+		// SAFETY:
+		// Every slot in a used range holds initialized components.
+		// Slots are either default-initialized or fully written by a transition before use,
+		// and they are only returned to the allocator after their components are dropped or moved.
 		unsafe {
-			for buffer in self.buffers.values_mut() {
+			for buffer in self.buffers.values_mut().filter(|b| b.needs_drop()) {
 				for range in self.allocator.used_ranges() {
 					buffer.drop_values(range)
 				}
@@ -216,23 +449,338 @@ impl Drop for ArchetypeInstance {
 	}
 }
 
+/// Per-entity filter for the [sparse](ComponentStorage::Sparse) components of a query.
+#[doc(hidden)]
+pub struct SparseFilter {
+	never: bool,
+	include: Vec<*const dyn AnySparseSet>,
+	exclude: Vec<*const dyn AnySparseSet>,
+}
+
+// SAFETY: The filter only reads the sparse sets, which cannot be modified while it exists.
+unsafe impl Send for SparseFilter {}
+unsafe impl Sync for SparseFilter {}
+
+impl SparseFilter {
+	pub(crate) fn new(storage: &SparseStorage, query: &EntityQueryData) -> Self {
+		let sets = |set: &BitField| {
+			let ids = set.iter_ranges().flatten().map(ComponentId::from_value);
+			ids.map(|id| storage.get_erased_ptr(id)).collect::<Vec<_>>()
+		};
+
+		let include = sets(query.sparse_include());
+		let exclude = sets(query.sparse_exclude());
+
+		Self {
+			never: include.iter().any(|s| s.is_none()),
+			include: include.into_iter().flatten().collect(),
+			exclude: exclude.into_iter().flatten().collect(),
+		}
+	}
+
+	#[inline(always)]
+	pub(crate) fn is_empty(&self) -> bool {
+		!self.never && self.include.is_empty() && self.exclude.is_empty()
+	}
+
+	/// # Safety
+	/// `slot` must contain a valid [Entity].
+	pub(crate) unsafe fn matches(&self, entities: *const Entity, slot: usize) -> bool {
+		if self.never {
+			return false;
+		}
+
+		let key = (*entities.add(slot)).instance as usize;
+		self.include.iter().all(|s| (**s).contains_key(key)) && !self.exclude.iter().any(|s| (**s).contains_key(key))
+	}
+}
+
+/// The number of slots processed by [IterArchetype::for_each_prefetched] before moving on to the next, prefetched, tile.
+pub const PREFETCH_TILE: usize = 256;
+
+#[cfg(target_arch = "x86_64")]
+const CACHE_LINE: usize = 64;
+
+/// Prefetches the cache lines covering `len` bytes starting from `ptr`.  
+/// This is a no-op on architectures without stable prefetch intrinsics.
+#[inline(always)]
+#[allow(unused_variables)]
+fn prefetch(ptr: *const u8, len: usize) {
+	#[cfg(target_arch = "x86_64")]
+	for offset in (0..len).step_by(CACHE_LINE) {
+		// SAFETY: Prefetching is only a hint and never faults, even on invalid addresses.
+		unsafe {
+			std::arch::x86_64::_mm_prefetch::<{ std::arch::x86_64::_MM_HINT_T0 }>(ptr.wrapping_add(offset) as *const i8)
+		};
+	}
+}
+
+/// A pointer to the values of a [component](Component), stored either in an archetype column or in a sparse set.
+#[doc(hidden)]
+pub struct ColumnPtr<T: ComponentTypeInfo> {
+	values: *mut T::ComponentType,
+	set: *const SparseSet<T::ComponentType>,
+}
+
+// SAFETY: IterArchetypeParallel's implementation guarantees every value is only accessed by one thread.
+unsafe impl<T: ComponentTypeInfo> Send for ColumnPtr<T> {}
+unsafe impl<T: ComponentTypeInfo> Sync for ColumnPtr<T> {}
+
+impl<T: ComponentTypeInfo> ColumnPtr<T>
+where
+	T::ComponentType: 'static,
+{
+	#[inline(always)]
+	unsafe fn new(archetype: &mut ArchetypeInstance, sparse: &mut SparseStorage) -> Self {
+		match T::STORAGE {
+			ComponentStorage::Table => {
+				let buffer = archetype.buffers.get_mut(&TypeId::of::<T::ComponentType>()).unwrap();
+				Self {
+					values: buffer.as_mut_slice_unchecked::<T::ComponentType>().as_mut_ptr(),
+					set: std::ptr::null(),
+				}
+			},
+			ComponentStorage::Sparse => match sparse.get_mut::<T::ComponentType>(T::component_id()) {
+				None => Self {
+					values: std::ptr::null_mut(),
+					set: std::ptr::null(),
+				},
+				Some(set) => Self {
+					values: set.as_mut_ptr(),
+					set,
+				},
+			},
+		}
+	}
+
+	/// Read-only variant of [ColumnPtr::new], the returned pointers must not be written to.
+	#[inline(always)]
+	unsafe fn new_shared(archetype: &ArchetypeInstance, sparse: &SparseStorage) -> Self {
+		match T::STORAGE {
+			ComponentStorage::Table => {
+				let buffer = archetype.buffers.get(&TypeId::of::<T::ComponentType>()).unwrap();
+				Self {
+					values: buffer.as_slice_unchecked::<T::ComponentType>().as_ptr() as *mut _,
+					set: std::ptr::null(),
+				}
+			},
+			ComponentStorage::Sparse => match sparse.get::<T::ComponentType>(T::component_id()) {
+				None => Self {
+					values: std::ptr::null_mut(),
+					set: std::ptr::null(),
+				},
+				Some(set) => Self {
+					values: set.as_ptr() as *mut _,
+					set,
+				},
+			},
+		}
+	}
+
+	/// Variant of [ColumnPtr::new] for iterators sharing the same archetype and [SparseStorage].
+	///
+	/// The archetype is never mutably borrowed as a whole,
+	/// and the values of `T` are only mutably borrowed if `T` is mutable.
+	///
+	/// # Safety
+	/// `archetype` and `sparse` must be valid, and the values of `T` must not be accessed
+	/// by anything else if `T` is mutable.
+	#[inline(always)]
+	unsafe fn from_raw(archetype: *mut ArchetypeInstance, sparse: *mut SparseStorage) -> Self {
+		if !T::MUTABLE {
+			return Self::new_shared(&*archetype, &*sparse);
+		}
+
+		match T::STORAGE {
+			ComponentStorage::Table => {
+				let buffers = &mut *std::ptr::addr_of_mut!((*archetype).buffers);
+				let buffer = buffers.get_mut(&TypeId::of::<T::ComponentType>()).unwrap();
+				Self {
+					values: buffer.as_mut_slice_unchecked::<T::ComponentType>().as_mut_ptr(),
+					set: std::ptr::null(),
+				}
+			},
+			ComponentStorage::Sparse => match (*sparse).get_mut::<T::ComponentType>(T::component_id()) {
+				None => Self {
+					values: std::ptr::null_mut(),
+					set: std::ptr::null(),
+				},
+				Some(set) => Self {
+					values: set.as_mut_ptr(),
+					set,
+				},
+			},
+		}
+	}
+
+	/// Like [ColumnPtr::new], but the archetype may not contain the table component,
+	/// in which case [ColumnPtr::get_optional] never returns a value.
+	#[inline(always)]
+	unsafe fn new_optional(archetype: &mut ArchetypeInstance, sparse: &mut SparseStorage) -> Self {
+		match T::STORAGE {
+			ComponentStorage::Table => match archetype.buffers.get_mut(&TypeId::of::<T::ComponentType>()) {
+				None => Self {
+					values: std::ptr::null_mut(),
+					set: std::ptr::null(),
+				},
+				Some(buffer) => Self {
+					values: buffer.as_mut_slice_unchecked::<T::ComponentType>().as_mut_ptr(),
+					set: std::ptr::null(),
+				},
+			},
+			ComponentStorage::Sparse => Self::new(archetype, sparse),
+		}
+	}
+
+	/// Hints the CPU to load the values of the slots in `range` into the cache.  
+	/// Only table components are prefetched, as sparse ones are not stored in slot order.
+	///
+	/// # Safety
+	/// `range` must be within the archetype's capacity.
+	#[inline(always)]
+	unsafe fn prefetch(&self, range: Range<usize>) {
+		if T::STORAGE == ComponentStorage::Table {
+			let size = std::mem::size_of::<T::ComponentType>();
+			prefetch(self.values.add(range.start) as *const u8, range.len() * size);
+		}
+	}
+
+	/// # Safety
+	/// `slot` must contain a valid [Entity].
+	#[inline(always)]
+	unsafe fn get(&self, entities: *const Entity, slot: usize) -> Option<*mut T::ComponentType> {
+		match T::STORAGE {
+			ComponentStorage::Table => Some(self.values.add(slot)),
+			ComponentStorage::Sparse => {
+				if self.set.is_null() {
+					return None;
+				}
+				let key = (*entities.add(slot)).instance as usize;
+				let index = (*self.set).index_of(key)?;
+				Some(self.values.add(index))
+			},
+		}
+	}
+
+	/// Variant of [ColumnPtr::get] for columns created by [ColumnPtr::new_optional].
+	///
+	/// # Safety
+	/// `slot` must contain a valid [Entity].
+	#[inline(always)]
+	unsafe fn get_optional(&self, entities: *const Entity, slot: usize) -> Option<*mut T::ComponentType> {
+		match self.values.is_null() {
+			true => None,
+			false => self.get(entities, slot),
+		}
+	}
+}
+
 pub trait IterArchetype<T> {
-	fn for_each(&mut self, func: &mut impl FnMut(T));
-	fn entities_for_each(&mut self, func: &mut impl FnMut(Entity, T));
+	fn for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(T));
+	/// Like [IterArchetype::for_each], but the slots are visited in tiles of [PREFETCH_TILE] entities,
+	/// prefetching the table components of the next tile before processing the current one.
+	fn for_each_prefetched(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(T));
+	fn entities_for_each(
+		&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(Entity, T),
+	);
+	fn try_entities_for_each(
+		&mut self, sparse: &mut SparseStorage, filter: &SparseFilter,
+		func: &mut impl FnMut(Entity, T) -> ControlFlow<()>,
+	) -> ControlFlow<()>;
 }
 
 pub trait IterArchetypeParallel<T> {
-	fn for_each(&mut self, func: &(impl Fn(T) + Send + Sync));
-	fn entities_for_each(&mut self, func: &(impl Fn(Entity, T) + Send + Sync));
+	fn for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &(impl Fn(T) + Send + Sync));
+	fn entities_for_each(
+		&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &(impl Fn(Entity, T) + Send + Sync),
+	);
+	fn fold<A: Send>(
+		&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, identity: &(impl Fn() -> A + Send + Sync),
+		fold: &(impl Fn(A, T) -> A + Send + Sync), reduce: &(impl Fn(A, A) -> A + Send + Sync),
+	) -> A;
+	/// Appends the results of `func` to `results`, in the same order as [IterArchetype::entities_for_each].
+	fn map_collect<R: Send>(
+		&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &(impl Fn(Entity, T) -> R + Send + Sync),
+		results: &mut Vec<R>,
+	);
+}
+
+/// Per-slot access to the components of an archetype, used by external iterators.
+pub trait ArchetypeFetch<'l, T> {
+	type Item;
+	type Columns;
+
+	/// Gets the columns of `T` without borrowing the whole archetype or [SparseStorage],
+	/// so multiple iterators can fetch disjoint components from the same archetype at once.
+	///
+	/// # Safety
+	/// `archetype` and `sparse` must be valid, the archetype must contain all table components of `T`,
+	/// and the components of `T` that are mutable must not be accessed by anything else while the columns are in use.
+	unsafe fn columns(archetype: *mut Self, sparse: *mut SparseStorage) -> Self::Columns;
+
+	/// # Safety
+	/// `slot` must contain a valid [Entity] and every slot must only be fetched once per borrow.
+	unsafe fn fetch(columns: &Self::Columns, entities: *const Entity, slot: usize) -> Option<Self::Item>;
+}
+
+/// Per-slot access to the components of an archetype, where each component of `T` may be missing.
+pub trait ArchetypeFetchAny<'l, T> {
+	type Item;
+	type Columns;
+
+	/// # Safety
+	/// Always safe if called by an iterator over an archetype, the archetype may lack any component of `T`.
+	unsafe fn columns_any(&mut self, sparse: &mut SparseStorage) -> Self::Columns;
+
+	/// Returns [None] if the [entity](Entity) in `slot` has none of the components of `T`.
+	///
+	/// # Safety
+	/// `slot` must contain a valid [Entity] and every slot must only be fetched once per borrow.
+	unsafe fn fetch_any(columns: &Self::Columns, entities: *const Entity, slot: usize) -> Option<Self::Item>;
+}
+
+/// Read-only iteration, only implemented for sets of components that can be built from a `*const` pointer.
+pub trait IterArchetypeShared<T> {
+	fn for_each(&self, sparse: &SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(T));
+	fn par_for_each(&self, sparse: &SparseStorage, filter: &SparseFilter, func: &(impl Fn(T) + Send + Sync));
 }
 
 impl IterArchetype<()> for ArchetypeInstance {
-	fn for_each(&mut self, _: &mut impl FnMut(())) {}
+	fn for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(())) {
+		IterArchetype::entities_for_each(self, sparse, filter, &mut |_, ()| func(()));
+	}
+
+	fn for_each_prefetched(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(())) {
+		IterArchetype::for_each(self, sparse, filter, func);
+	}
+
+	fn entities_for_each(&mut self, _: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(Entity, ())) {
+		let entities = self.entities.as_ptr();
+		for range in self.allocator.used_ranges() {
+			for i in range {
+				unsafe {
+					if filter.is_empty() || filter.matches(entities, i) {
+						func((*entities.add(i)).clone(), ())
+					}
+				}
+			}
+		}
+	}
 
-	fn entities_for_each(&mut self, func: &mut impl FnMut(Entity, ())) {
-		for entity in self.entities.iter().cloned() {
-			func(entity, ())
+	fn try_entities_for_each(
+		&mut self, _: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(Entity, ()) -> ControlFlow<()>,
+	) -> ControlFlow<()> {
+		let entities = self.entities.as_ptr();
+		for range in self.allocator.used_ranges() {
+			for i in range {
+				unsafe {
+					if filter.is_empty() || filter.matches(entities, i) {
+						func((*entities.add(i)).clone(), ())?;
+					}
+				}
+			}
 		}
+		ControlFlow::Continue(())
 	}
 }
 
@@ -243,77 +791,271 @@ macro_rules! impl_archetype_iter {
             impl <$($t: ComponentTypeInfo + ComponentFrom<*mut $t::ComponentType>),*> IterArchetype<($($t),*)> for ArchetypeInstance
 				where $($t::ComponentType: 'static),*
 			{
-                fn for_each(&mut self, func: &mut impl FnMut(($($t),*))) {
+                fn for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(($($t),*))) {
                     unsafe {
-                        $(
-                            let [<$t:lower>] = self.buffers.get_mut(&TypeId::of::<$t::ComponentType>()).unwrap();
-                            let [<$t:lower>] = [<$t:lower>].as_mut_slice_unchecked::<$t::ComponentType>().as_mut_ptr();
-                        )*
+                        $(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr();
+
                         for range in self.allocator.used_ranges() {
                             for i in range {
-                                $(let [<$t:lower>] = [<$t:lower>].add(i);)*
+								if filtered && !filter.matches(entities, i) {
+									continue;
+								}
+                                $(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { continue };)*
                                 func(($($t::convert([<$t:lower>])),*));
                             }
                         }
                     }
                 }
 
-				fn entities_for_each(&mut self, func: &mut impl FnMut(Entity, ($($t),*))) {
+				fn for_each_prefetched(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(($($t),*))) {
                     unsafe {
-                        $(
-                            let [<$t:lower>] = self.buffers.get_mut(&TypeId::of::<$t::ComponentType>()).unwrap();
-                            let [<$t:lower>] = [<$t:lower>].as_mut_slice_unchecked::<$t::ComponentType>().as_mut_ptr();
-                        )*
+                        $(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
 
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr();
+
+                        for range in self.allocator.used_ranges() {
+							let mut tile = range.start..usize::min(range.start + PREFETCH_TILE, range.end);
+							while !tile.is_empty() {
+								let next = tile.end..usize::min(tile.end + PREFETCH_TILE, range.end);
+								$([<$t:lower>].prefetch(next.clone());)*
+
+								for i in tile {
+									if filtered && !filter.matches(entities, i) {
+										continue;
+									}
+									$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { continue };)*
+									func(($($t::convert([<$t:lower>])),*));
+								}
+								tile = next;
+							}
+                        }
+                    }
+                }
+
+				fn entities_for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(Entity, ($($t),*))) {
+                    unsafe {
+                        $(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
 						let entities = self.entities.as_ptr();
 
                         for range in self.allocator.used_ranges() {
                             for i in range {
-                                $(let [<$t:lower>] = [<$t:lower>].add(i);)*
+								if filtered && !filter.matches(entities, i) {
+									continue;
+								}
+                                $(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { continue };)*
 								let entity = (*entities.add(i)).clone();
                                 func(entity, ($($t::convert([<$t:lower>])),*));
                             }
                         }
                     }
+                }
+
+				fn try_entities_for_each(
+					&mut self, sparse: &mut SparseStorage, filter: &SparseFilter,
+					func: &mut impl FnMut(Entity, ($($t),*)) -> ControlFlow<()>,
+				) -> ControlFlow<()> {
+                    unsafe {
+                        $(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr();
+
+                        for range in self.allocator.used_ranges() {
+                            for i in range {
+								if filtered && !filter.matches(entities, i) {
+									continue;
+								}
+                                $(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { continue };)*
+								let entity = (*entities.add(i)).clone();
+                                func(entity, ($($t::convert([<$t:lower>])),*))?;
+                            }
+                        }
+                    }
+					ControlFlow::Continue(())
                 }
             }
 
+			#[allow(unused_parens)]
+			impl<'l, $($t: ComponentRef<'l>),*> ArchetypeFetch<'l, ($($t),*)> for ArchetypeInstance
+				where $($t::ComponentType: 'static),*
+			{
+				type Item = ($($t::Ref),*);
+				type Columns = ($(ColumnPtr<$t>),*,);
+
+				#[inline(always)]
+				unsafe fn columns(archetype: *mut Self, sparse: *mut SparseStorage) -> Self::Columns {
+					($(ColumnPtr::<$t>::from_raw(archetype, sparse)),*,)
+				}
+
+				#[inline(always)]
+				unsafe fn fetch(columns: &Self::Columns, entities: *const Entity, slot: usize) -> Option<Self::Item> {
+					let ($([<$t:lower>]),*,) = columns;
+					$(let [<$t:lower>] = [<$t:lower>].get(entities, slot)?;)*
+					Some(($($t::from_ptr([<$t:lower>])),*))
+				}
+			}
+
+			#[allow(unused_parens)]
+			impl<'l, $($t: ComponentRef<'l>),*> ArchetypeFetchAny<'l, ($($t),*)> for ArchetypeInstance
+				where $($t::ComponentType: 'static),*
+			{
+				type Item = ($(Option<$t::Ref>),*);
+				type Columns = ($(ColumnPtr<$t>),*,);
+
+				#[inline(always)]
+				unsafe fn columns_any(&mut self, sparse: &mut SparseStorage) -> Self::Columns {
+					($(ColumnPtr::<$t>::new_optional(self, sparse)),*,)
+				}
+
+				#[inline(always)]
+				#[allow(clippy::question_mark)]
+				unsafe fn fetch_any(columns: &Self::Columns, entities: *const Entity, slot: usize) -> Option<Self::Item> {
+					let ($([<$t:lower>]),*,) = columns;
+					$(let [<$t:lower>] = [<$t:lower>].get_optional(entities, slot);)*
+					if $([<$t:lower>].is_none())&&* {
+						return None;
+					}
+					Some(($([<$t:lower>].map(|ptr| $t::from_ptr(ptr))),*))
+				}
+			}
+
+			#[allow(unused_parens)]
+			impl<$($t: ComponentTypeInfo + ComponentFrom<*const $t::ComponentType> + Send + Sync),*> IterArchetypeShared<($($t),*)> for ArchetypeInstance
+				where $($t::ComponentType: 'static),*
+			{
+				fn for_each(&self, sparse: &SparseStorage, filter: &SparseFilter, func: &mut impl FnMut(($($t),*))) {
+					unsafe {
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new_shared(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr();
+
+						for range in self.allocator.used_ranges() {
+							for i in range {
+								if filtered && !filter.matches(entities, i) {
+									continue;
+								}
+								$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { continue };)*
+								func(($($t::convert([<$t:lower>] as *const _)),*));
+							}
+						}
+					}
+				}
+
+				fn par_for_each(&self, sparse: &SparseStorage, filter: &SparseFilter, func: &(impl Fn(($($t),*)) + Sync + Send)) {
+					unsafe {
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new_shared(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr() as usize;
+
+						let ranges: Vec<_> = self.allocator.used_ranges().collect();
+						ranges.into_par_iter().flatten().for_each(|i| {
+							let entities = entities as *const Entity;
+							if filtered && !filter.matches(entities, i) {
+								return;
+							}
+							$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { return };)*
+							func(($($t::convert([<$t:lower>] as *const _)),*));
+						});
+					}
+				}
+			}
+
 			#[allow(unused_parens)]
 			impl<$($t: ComponentTypeInfo + ComponentFrom<*mut $t::ComponentType> + Send + Sync),*> IterArchetypeParallel<($($t),*)> for ArchetypeInstance
 				where $($t::ComponentType: 'static),*
 			{
-				fn for_each(&mut self, func: &(impl Fn(($($t),*)) + Sync + Send)) {
+				fn for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &(impl Fn(($($t),*)) + Sync + Send)) {
 					unsafe {
-						$(
-                            let [<$t:lower>] = self.buffers.get_mut(&TypeId::of::<$t::ComponentType>()).unwrap();
-                            let [<$t:lower>] = [<$t:lower>].as_mut_slice_unchecked::<$t::ComponentType>().as_mut_ptr() as usize;
-                        )*
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr() as usize;
 
 						let ranges: Vec<_> = self.allocator.used_ranges().collect();
 						ranges.into_par_iter().flatten().for_each(|i| {
-							$(let [<$t:lower>] = ([<$t:lower>] as *mut $t::ComponentType).add(i);)*
+							let entities = entities as *const Entity;
+							if filtered && !filter.matches(entities, i) {
+								return;
+							}
+							$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { return };)*
 							func(($($t::convert([<$t:lower>])),*));
 						});
 					}
 				}
 
-				fn entities_for_each(&mut self, func: &(impl Fn(Entity, ($($t),*)) + Sync + Send)) {
+				fn entities_for_each(&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, func: &(impl Fn(Entity, ($($t),*)) + Sync + Send)) {
 					unsafe {
-						$(
-                            let [<$t:lower>] = self.buffers.get_mut(&TypeId::of::<$t::ComponentType>()).unwrap();
-                            let [<$t:lower>] = [<$t:lower>].as_mut_slice_unchecked::<$t::ComponentType>().as_mut_ptr() as usize;
-                        )*
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
 
+						let filtered = !filter.is_empty();
 						let entities = self.entities.as_ptr() as usize;
 
 						let ranges: Vec<_> = self.allocator.used_ranges().collect();
 						ranges.into_par_iter().flatten().for_each(|i| {
-							$(let [<$t:lower>] = ([<$t:lower>] as *mut $t::ComponentType).add(i);)*
-							let entity = (*(entities as *const Entity).add(i)).clone();
+							let entities = entities as *const Entity;
+							if filtered && !filter.matches(entities, i) {
+								return;
+							}
+							$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { return };)*
+							let entity = (*entities.add(i)).clone();
 							func(entity, ($($t::convert([<$t:lower>])),*));
 						});
 					}
 				}
+
+				fn fold<A: Send>(
+					&mut self, sparse: &mut SparseStorage, filter: &SparseFilter, identity: &(impl Fn() -> A + Send + Sync),
+					fold: &(impl Fn(A, ($($t),*)) -> A + Send + Sync), reduce: &(impl Fn(A, A) -> A + Send + Sync),
+				) -> A {
+					unsafe {
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr() as usize;
+
+						let ranges: Vec<_> = self.allocator.used_ranges().collect();
+						ranges.into_par_iter().flatten().fold(identity, |acc, i| {
+							let entities = entities as *const Entity;
+							if filtered && !filter.matches(entities, i) {
+								return acc;
+							}
+							$(let Some([<$t:lower>]) = [<$t:lower>].get(entities, i) else { return acc };)*
+							fold(acc, ($($t::convert([<$t:lower>])),*))
+						}).reduce(identity, reduce)
+					}
+				}
+
+				fn map_collect<R: Send>(
+					&mut self, sparse: &mut SparseStorage, filter: &SparseFilter,
+					func: &(impl Fn(Entity, ($($t),*)) -> R + Send + Sync), results: &mut Vec<R>,
+				) {
+					unsafe {
+						$(let [<$t:lower>] = ColumnPtr::<$t>::new(self, sparse);)*
+
+						let filtered = !filter.is_empty();
+						let entities = self.entities.as_ptr() as usize;
+
+						// Parallel extension preserves the order of the slots, regardless of how they are split.
+						let ranges: Vec<_> = self.allocator.used_ranges().collect();
+						results.par_extend(ranges.into_par_iter().flatten().filter_map(|i| {
+							let entities = entities as *const Entity;
+							if filtered && !filter.matches(entities, i) {
+								return None;
+							}
+							$(let [<$t:lower>] = [<$t:lower>].get(entities, i)?;)*
+							let entity = (*entities.add(i)).clone();
+							Some(func(entity, ($($t::convert([<$t:lower>])),*)))
+						}));
+					}
+				}
 			}
 
         }
@@ -332,3 +1074,24 @@ impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8);
 impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9);
 impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
 impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_archetype_iter!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+
+/// A column of [component](Component) values detached from its archetype, created while migrating a [component](Component).
+///
+/// The values in `ranges` are dropped along with the column.
+pub struct MigratedColumn {
+	buffer: AnyBuffer,
+	ranges: Vec<Range<usize>>,
+}
+
+impl Drop for MigratedColumn {
+	fn drop(&mut self) {
+		for range in self.ranges.drain(..).filter(|r| !r.is_empty()) {
+			// SAFETY: The values in `ranges` are initialized and owned by the column.
+			unsafe { self.buffer.drop_values(range) };
+		}
+	}
+}
@@ -7,3 +7,41 @@ macro_rules! create_archetype {
 		])
 	};
 }
+
+/// Create a new [Entity](crate::entities::Entity) in the specified [EcsContext](crate::context::EcsContext)
+/// with the provided [Component](crate::components::Component) values.  
+/// The [Archetype](crate::archetypes::Archetype) is derived from the values' types.
+/// Each [Component](crate::components::Component) type may only appear once, repeating one panics.
+#[macro_export]
+macro_rules! spawn {
+	(@bind $ecs: expr; [$($bound: ident)*]; $value: expr $(, $rest: expr)*) => {{
+		let value = $value;
+		$crate::spawn!(@bind $ecs; [$($bound)* value]; $($rest),*)
+	}};
+
+	(@bind $ecs: expr; [$($bound: ident)*];) => {{
+		let ecs = &mut $ecs;
+		let components = [$($crate::components::ComponentType::of_val(&$bound)),*];
+		assert!(
+			(1..components.len()).all(|i| !components[..i].contains(&components[i])),
+			"An entity cannot be spawned with the same component type multiple times"
+		);
+		let archetype = ecs.create_archetype(&components);
+
+		// SAFETY: All components of the archetype are written right after creation.
+		unsafe {
+			let entity = ecs.create_entity_uninit(archetype);
+			$(ecs.write_component(&entity, $bound);)*
+			ecs.finish_entity(&entity);
+			entity
+		}
+	}};
+
+	($ecs: expr $(,)?) => {
+		$ecs.create_entity()
+	};
+
+	($ecs: expr, $($value: expr),+ $(,)?) => {
+		$crate::spawn!(@bind $ecs; []; $($value),+)
+	};
+}
@@ -1,21 +1,61 @@
-use crate::archetypes::{Archetype, ArchetypeInstance};
+use crate::archetypes::{Archetype, ArchetypeGrowth, ArchetypeInstance};
 use std::hash::{BuildHasherDefault, Hash};
 use crate::data_structures::BitField;
-use crate::components::ComponentType;
+use crate::components::{Component, ComponentInitializer, ComponentStorage, ComponentType};
 use crate::entities::EntityQuery;
 use nohash_hasher::NoHashHasher;
 use std::collections::HashMap;
+use parking_lot::RwLock;
+use std::time::{Duration, Instant};
+use std::sync::Arc;
 
 type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
 
 pub(crate) struct ArchetypeStore {
+	registry_id: u32,
+	growth: ArchetypeGrowth,
 	bf: BitField,
 	vec: Vec<ArchetypeInstance>,
 	map: HashMap<BitField, Archetype>,
-	queries: HashMap<EntityQuery, Vec<usize>, Hasher>,
-	transitions: HashMap<ArchetypeTransition, Archetype, Hasher>,
+	/// Indices of the archetypes matched by each query.
+	/// Lookups through `&self` populate it under a write lock on miss, while `&mut self` accesses bypass the lock.
+	queries: RwLock<HashMap<EntityQuery, Arc<Vec<usize>>, Hasher>>,
+	transitions: HashMap<ArchetypeTransition, Archetype>,
+	/// Indices of the archetypes removed by [ArchetypeStore::collect_empty].
+	/// Their slots are never reused, so stale [Archetype] handles cannot alias newer archetypes.
+	collected: BitField,
+	profiler: Option<ArchetypeProfiler>,
 }
 
+/// A callback notified of every [ArchetypeEvent] along with the time it took.
+pub type ArchetypeProfiler = Box<dyn Fn(ArchetypeEvent, Duration) + Send + Sync>;
+
+/// A structural operation whose cost grows with the number of [archetypes](Archetype) or queries.
+///
+/// These operations only happen the first time a combination of [components](crate::components::Component)
+/// or a query is encountered, so they may cause spikes when that happens mid-frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ArchetypeEvent {
+	/// A new [archetype](Archetype) was created and matched against all existing queries.
+	ArchetypeCreated(Archetype),
+	/// A query was matched against all existing [archetypes](Archetype).
+	QueryInitialized(EntityQuery),
+	/// The destination of a transition was looked up or created, and the edge was cached.
+	TransitionResolved {
+		/// The [archetype](Archetype) the transition starts from.
+		src: Archetype,
+		/// The [archetype](Archetype) the transition leads to.
+		dst: Archetype,
+		/// Whether the transition adds or removes a [component](crate::components::Component).
+		kind: ArchetypeTransitionKind,
+	},
+}
+
+/// A cached edge of the archetype graph.
+///
+/// Transitions are hashed field by field, so they do not depend on archetype indices
+/// or [component ids](crate::components::ComponentId) fitting in a packed integer.
+/// Archetype indices are bound by `usize`, while component ids are bound by `u32`.
 #[derive(Clone)]
 pub(crate) struct ArchetypeTransition {
 	pub archetype: Archetype,
@@ -23,21 +63,28 @@ pub(crate) struct ArchetypeTransition {
 	pub kind: ArchetypeTransitionKind,
 }
 
+/// The kind of structural change moving an [entity](crate::entities::Entity) between [archetypes](Archetype).
 #[repr(usize)]
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
-pub(crate) enum ArchetypeTransitionKind {
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ArchetypeTransitionKind {
+	/// A [component](crate::components::Component) is added.
 	Add = 0,
+	/// A [component](crate::components::Component) is removed.
 	Remove = 1,
 }
 
 impl ArchetypeStore {
-	pub fn new() -> Self {
+	pub fn new(registry_id: u32) -> Self {
 		Self {
+			registry_id,
+			growth: ArchetypeGrowth::default(),
 			bf: BitField::new(),
-			queries: HashMap::default(),
+			queries: RwLock::default(),
 			map: HashMap::from([(BitField::new(), Archetype::default())]),
-			vec: vec![ArchetypeInstance::new(Archetype { index: 0 }, &[])],
+			vec: vec![ArchetypeInstance::new(Archetype::default(), &[])],
 			transitions: HashMap::default(),
+			collected: BitField::new(),
+			profiler: None,
 		}
 	}
 
@@ -49,6 +96,13 @@ impl ArchetypeStore {
 	/// Creates an [archetype](crate::archetypes::Archetype) containing the specified [components](crate::components::Component) with the specified capacity.
 	#[inline(never)]
 	pub fn create_archetype_with_capacity(&mut self, components: &[ComponentType], min_capacity: usize) -> Archetype {
+		// Sparse components are not stored in archetypes
+		if components.iter().any(|t| t.storage() == ComponentStorage::Sparse) {
+			let components: Vec<_> =
+				components.iter().filter(|t| t.storage() == ComponentStorage::Table).cloned().collect();
+			return self.create_archetype_with_capacity(&components, min_capacity);
+		}
+
 		let bitfield = &mut self.bf;
 		bitfield.clear();
 
@@ -57,27 +111,42 @@ impl ArchetypeStore {
 		}
 
 		if let Some(archetype) = self.map.get(bitfield) {
-			self.vec[archetype.index as usize].ensure_capacity(min_capacity);
+			self.vec[archetype.index].ensure_capacity(min_capacity);
 			return *archetype;
 		}
 
-		let archetype = Archetype { index: self.vec.len() };
-		let instance = ArchetypeInstance::with_capacity(archetype, components, min_capacity);
+		let archetype = self.push_archetype(components, min_capacity);
+		self.map.insert(self.bf.clone(), archetype);
+		archetype
+	}
 
-		// Match archetype against all queries
-		for (query, results) in self.queries.iter_mut() {
-			let data = crate::entities::get_query_data(*query);
-			if !instance.matches_query(data.include()) {
-				continue;
-			}
-			if instance.matches_query(data.exclude()) {
-				continue;
-			}
-			results.push(self.vec.len());
+	/// Creates a new [archetype](crate::archetypes::Archetype) containing the specified [components](crate::components::Component),
+	/// even if one with the same [components](crate::components::Component) already exists.
+	///
+	/// Pinned archetypes are never returned by [ArchetypeStore::create_archetype] nor picked as the destination of a transition,
+	/// so they only contain the [entities](crate::entities::Entity) explicitly created in or moved to them.
+	#[inline(never)]
+	pub fn create_pinned_archetype(&mut self, components: &[ComponentType], min_capacity: usize) -> Archetype {
+		// Sparse components are not stored in archetypes
+		if components.iter().any(|t| t.storage() == ComponentStorage::Sparse) {
+			let components: Vec<_> =
+				components.iter().filter(|t| t.storage() == ComponentStorage::Table).cloned().collect();
+			return self.create_pinned_archetype(&components, min_capacity);
 		}
 
-		self.map.insert(bitfield.clone(), archetype);
-		self.vec.push(instance);
+		self.push_archetype(components, min_capacity)
+	}
+
+	/// Creates an [archetype](crate::archetypes::Archetype) containing the specified [components](crate::components::Component),
+	/// replacing the initializers of the specified [components](crate::components::Component).
+	pub fn create_archetype_with_initializers(
+		&mut self, components: &[ComponentType], initializers: Vec<ComponentInitializer>,
+	) -> Archetype {
+		let archetype = self.create_archetype(components);
+		let instance = &mut self.vec[archetype.index];
+		for initializer in initializers {
+			instance.set_initializer(initializer);
+		}
 		archetype
 	}
 
@@ -89,17 +158,75 @@ impl ArchetypeStore {
 		&mut self.vec[index]
 	}
 
+	/// Gets mutable references to two distinct archetypes.
+	pub fn get_pair_mut(&mut self, a: usize, b: usize) -> (&mut ArchetypeInstance, &mut ArchetypeInstance) {
+		assert_ne!(a, b, "Archetypes must be distinct");
+		let [a, b] = self.vec.get_disjoint_mut([a, b]).unwrap();
+		(a, b)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &ArchetypeInstance> {
+		let collected = &self.collected;
+		self.vec.iter().enumerate().filter(|(i, _)| !collected.get(*i)).map(|(_, a)| a)
+	}
+
+	/// Checks if the archetype at `index` has been removed by [ArchetypeStore::collect_empty].
+	pub fn is_collected(&self, index: usize) -> bool {
+		self.collected.get(index)
+	}
+
 	pub fn query(&mut self, query: EntityQuery) -> impl Iterator<Item = &mut ArchetypeInstance> {
-		if !self.queries.contains_key(&query) {
-			self.init_query(query);
-		}
+		self.warm_query(query);
 
 		unsafe {
 			let instances = self.vec.as_mut_ptr();
-			self.queries.get(&query).unwrap().iter().map(move |i| &mut *instances.add(*i))
+			self.queries.get_mut().get(&query).unwrap().iter().map(move |i| &mut *instances.add(*i))
+		}
+	}
+
+	/// Read-only counterpart of [ArchetypeStore::query].
+	///
+	/// The query cache is initialized through a lock if needed, so shared iterations can run concurrently.
+	pub fn query_shared(&self, query: EntityQuery) -> impl Iterator<Item = &ArchetypeInstance> {
+		let indices = self.query_indices(query);
+		let instances = &self.vec;
+		(0..indices.len()).map(move |i| &instances[indices[i]])
+	}
+
+	/// Gets the indices of the archetypes matching the query, initializing its cache through a lock if needed.
+	pub fn query_indices(&self, query: EntityQuery) -> Arc<Vec<usize>> {
+		let cached = self.queries.read().get(&query).cloned();
+		cached.unwrap_or_else(|| self.init_query(query))
+	}
+
+	/// Gets a pointer to the archetype at index 0, through which the others can be accessed without borrowing the store.
+	pub fn as_mut_ptr(&mut self) -> *mut ArchetypeInstance {
+		self.vec.as_mut_ptr()
+	}
+
+	/// Like [ArchetypeStore::query], but skips archetypes that currently contain no entities.  
+	/// Archetypes are only removed when explicitly collected, so this avoids per-archetype setup work on transiently empty ones when iterating.
+	pub fn query_populated(&mut self, query: EntityQuery) -> impl Iterator<Item = &mut ArchetypeInstance> {
+		self.query(query).filter(|archetype| !archetype.is_empty())
+	}
+
+	/// Read-only counterpart of [ArchetypeStore::query_populated].
+	pub fn query_shared_populated(&self, query: EntityQuery) -> impl Iterator<Item = &ArchetypeInstance> {
+		self.query_shared(query).filter(|archetype| !archetype.is_empty())
+	}
+
+	/// Matches the query against all existing archetypes, unless it has already been done.  
+	/// Archetypes created afterwards are matched against it as they are created.
+	pub fn warm_query(&mut self, query: EntityQuery) {
+		if !self.queries.get_mut().contains_key(&query) {
+			self.init_query(query);
 		}
 	}
 
+	pub fn is_query_initialized(&self, query: EntityQuery) -> bool {
+		self.queries.read().contains_key(&query)
+	}
+
 	pub fn get_archetype_transition(
 		&mut self, transition: ArchetypeTransition,
 	) -> Option<(&mut ArchetypeInstance, &mut ArchetypeInstance)> {
@@ -113,81 +240,206 @@ impl ArchetypeStore {
 			}
 		}
 
-		match self.transitions.get(&transition) {
-			Some(archetype) => Some(get_refs(&mut self.vec, transition.archetype, *archetype)),
+		let dst = match self.transitions.get(&transition) {
+			Some(archetype) => *archetype,
+			None => {
+				let start = self.profiler.is_some().then(Instant::now);
+				let dst = self.resolve_transition(&transition)?;
+				self.transitions.insert(transition.clone(), dst);
+				self.report(start, || ArchetypeEvent::TransitionResolved {
+					src: transition.archetype,
+					dst,
+					kind: transition.kind,
+				});
+				dst
+			},
+		};
+
+		Some(get_refs(&mut self.vec, transition.archetype, dst))
+	}
+
+	/// Finds or creates the destination of an uncached transition,
+	/// returning [None] if it does not change the [archetype](Archetype).
+	fn resolve_transition(&mut self, transition: &ArchetypeTransition) -> Option<Archetype> {
+		let src = &self.vec[transition.archetype.index];
+		let id = transition.component.id().value();
+		let add = transition.kind == ArchetypeTransitionKind::Add;
+		if src.component_bitfield().get(id) == add {
+			return None;
+		}
+
+		let bitfield = &mut self.bf;
+		bitfield.copy_from(src.component_bitfield());
+		bitfield.set(id, add);
 
-			None => match transition.kind {
-				ArchetypeTransitionKind::Add => {
-					let src = &self.vec[transition.archetype.index];
-					if src.component_bitfield().get(transition.component.id().value()) {
-						return None;
-					}
+		if let Some(archetype) = self.map.get(bitfield) {
+			return Some(*archetype);
+		}
 
-					let bitfield = &mut self.bf;
-					bitfield.copy_from(src.component_bitfield());
-					bitfield.set(transition.component.id().value(), true);
+		let mut components = Vec::with_capacity(src.components().len() + 1);
+		components.extend(src.components().iter().filter(|t| t.id().value() != id).cloned());
+		if add {
+			components.push(transition.component.clone());
+		}
+		Some(self.create_archetype(&components))
+	}
 
-					match self.map.get(bitfield) {
-						Some(archetype) => Some(get_refs(&mut self.vec, transition.archetype, *archetype)),
+	/// Creates the destination [archetype](Archetype) of a transition and caches the edge,
+	/// returning the destination, or the source if the transition does not change the [archetype](Archetype).
+	pub fn prebuild_transition(&mut self, transition: ArchetypeTransition) -> Archetype {
+		let src = transition.archetype;
+		match self.get_archetype_transition(transition) {
+			Some((_, dst)) => dst.id(),
+			None => src,
+		}
+	}
 
-						None => {
-							let mut components = Vec::with_capacity(src.components().len() + 1);
-							components.extend_from_slice(src.components());
-							components.push(transition.component.clone());
+	/// Sets how all current and future [archetypes](Archetype) grow when they run out of free slots.
+	pub fn set_growth(&mut self, growth: ArchetypeGrowth) {
+		self.growth = growth;
+		for instance in self.vec.iter_mut() {
+			instance.set_growth(growth);
+		}
+	}
 
-							let archetype = self.create_archetype(&components);
-							self.transitions.insert(transition.clone(), archetype);
+	/// Replaces the `Old` [component](crate::components::Component) with `New` in every archetype containing it.
+	///
+	/// Migrated archetypes keep their index, so existing [Archetype] handles stay valid,
+	/// but all cached transitions and queries are rebuilt to account for their new [components](crate::components::Component).
+	///
+	/// # Panics
+	/// Panics before migrating anything if an archetype already contains `New`,
+	/// or if migrating one would produce the signature of another existing archetype.  
+	/// All values are converted before any archetype is modified, so if `conv` panics nothing is migrated.
+	pub fn migrate_component<Old: Component, New: Component>(&mut self, conv: impl Fn(&Old) -> New) {
+		let (old, new) = (Old::component_id().value(), New::component_id().value());
+		let migrated: Vec<_> = self.vec.iter().filter(|a| a.component_bitfield().get(old)).map(|a| a.id()).collect();
+		if migrated.is_empty() {
+			return;
+		}
 
-							Some(get_refs(&mut self.vec, transition.archetype, archetype))
-						},
-					}
-				},
+		for archetype in migrated.iter() {
+			let mut bitfield = self.vec[archetype.index].component_bitfield().clone();
+			assert!(!bitfield.get(new), "Archetype already contains the migrated component");
+			bitfield.set(old, false);
+			bitfield.set(new, true);
+			assert!(
+				!self.map.contains_key(&bitfield),
+				"An archetype with the migrated components already exists"
+			);
+		}
 
-				ArchetypeTransitionKind::Remove => {
-					let src = &self.vec[transition.archetype.index];
-					if !src.component_bitfield().get(transition.component.id().value()) {
-						return None;
-					}
+		// If `conv` panics, the columns converted so far are dropped along with their values
+		let columns: Vec<_> = migrated.iter().map(|a| self.vec[a.index].convert_column::<Old, New>(&conv)).collect();
 
-					let bitfield = &mut self.bf;
-					bitfield.copy_from(src.component_bitfield());
-					bitfield.set(transition.component.id().value(), false);
+		let mut old_columns = Vec::with_capacity(columns.len());
+		for (archetype, column) in migrated.into_iter().zip(columns) {
+			old_columns.push(self.vec[archetype.index].replace_column::<Old, New>(column));
 
-					match self.map.get(bitfield) {
-						Some(archetype) => Some(get_refs(&mut self.vec, transition.archetype, *archetype)),
+			// Pinned archetypes are not in the map, and must not be added to it
+			let key = self.map.iter().find(|(_, a)| **a == archetype).map(|(k, _)| k.clone());
+			if let Some(key) = key {
+				self.map.remove(&key);
+				let bitfield = self.vec[archetype.index].component_bitfield().clone();
+				self.map.insert(bitfield, archetype);
+			}
+		}
 
-						None => {
-							let mut components = Vec::from(src.components());
-							components
-								.remove(components.iter().position(|t| t.id() == transition.component.id()).unwrap());
+		self.transitions.clear();
+		let queries: Vec<_> = self.queries.get_mut().drain().map(|(query, _)| query).collect();
+		for query in queries {
+			self.init_query(query);
+		}
 
-							let archetype = self.create_archetype(&components);
-							self.transitions.insert(transition.clone(), archetype);
+		// The old values are only dropped once the migration is complete, as their destructors may panic
+		drop(old_columns);
+	}
 
-							Some(get_refs(&mut self.vec, transition.archetype, archetype))
-						},
-					}
-				},
-			},
+	/// Removes all archetypes that contain no [entities](crate::entities::Entity), except the default one,
+	/// returning how many were removed.
+	///
+	/// Removed archetypes release their storage and are dropped from all cached queries and transitions.
+	/// Their indices are tombstoned rather than compacted, so the handles of the remaining archetypes stay valid.
+	#[inline(never)]
+	pub fn collect_empty(&mut self) -> usize {
+		let mut count = 0;
+		for (i, instance) in self.vec.iter_mut().enumerate().skip(1) {
+			if self.collected.get(i) || !instance.is_empty() {
+				continue;
+			}
+
+			*instance = ArchetypeInstance::new(instance.id(), &[]);
+			self.collected.set(i, true);
+			count += 1;
 		}
+
+		if count == 0 {
+			return 0;
+		}
+
+		let collected = &self.collected;
+		self.map.retain(|_, archetype| !collected.get(archetype.index));
+		self.transitions.retain(|t, dst| !collected.get(t.archetype.index) && !collected.get(dst.index));
+		for indices in self.queries.get_mut().values_mut() {
+			if indices.iter().any(|i| collected.get(*i)) {
+				Arc::make_mut(indices).retain(|i| !collected.get(*i));
+			}
+		}
+
+		count
 	}
 
 	#[inline(never)]
-	fn init_query(&mut self, query: EntityQuery) {
+	fn push_archetype(&mut self, components: &[ComponentType], min_capacity: usize) -> Archetype {
+		let archetype = Archetype {
+			index: self.vec.len(),
+			registry_id: self.registry_id,
+		};
+		let start = self.profiler.is_some().then(Instant::now);
+		let mut instance = ArchetypeInstance::with_capacity(archetype, components, min_capacity);
+		instance.set_growth(self.growth);
+
+		// Match archetype against all queries
+		for (query, results) in self.queries.get_mut().iter_mut() {
+			let data = crate::entities::get_query_data(*query);
+			if instance.matches(&data) {
+				Arc::make_mut(results).push(self.vec.len());
+			}
+		}
+
+		self.vec.push(instance);
+		self.report(start, || ArchetypeEvent::ArchetypeCreated(archetype));
+		archetype
+	}
+
+	fn init_query(&self, query: EntityQuery) -> Arc<Vec<usize>> {
+		let start = self.profiler.is_some().then(Instant::now);
 		let data = crate::entities::get_query_data(query);
 
 		// Match query against all archetypes
-		let indices = self.vec.iter().enumerate().filter_map(|(i, a)| {
-			if !a.matches_query(data.include()) {
-				return None;
-			}
-			if a.matches_query(data.exclude()) {
-				return None;
-			}
-			Some(i)
-		});
+		let collected = &self.collected;
+		let indices = self
+			.vec
+			.iter()
+			.enumerate()
+			.filter_map(|(i, a)| (!collected.get(i) && a.matches(&data)).then_some(i));
+
+		let indices = Arc::new(indices.collect());
+		let indices = self.queries.write().entry(query).or_insert(indices).clone();
+		self.report(start, || ArchetypeEvent::QueryInitialized(query));
+		indices
+	}
 
-		self.queries.insert(query, indices.collect());
+	/// Sets the callback notified of every structural operation, or removes it if [None].
+	pub fn set_profiler(&mut self, profiler: Option<ArchetypeProfiler>) {
+		self.profiler = profiler;
+	}
+
+	#[inline]
+	fn report(&self, start: Option<Instant>, event: impl FnOnce() -> ArchetypeEvent) {
+		if let (Some(profiler), Some(start)) = (&self.profiler, start) {
+			profiler(event(), start.elapsed());
+		}
 	}
 }
 
@@ -201,9 +453,8 @@ impl PartialEq<Self> for ArchetypeTransition {
 
 impl Hash for ArchetypeTransition {
 	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-		let kind = self.kind as usize;
-		let archetype = self.archetype.index << 33;
-		let component = self.component.id().value() << 1;
-		state.write_usize(kind | archetype | component);
+		self.archetype.index.hash(state);
+		self.component.id().hash(state);
+		self.kind.hash(state);
 	}
 }
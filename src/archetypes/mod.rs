@@ -4,11 +4,13 @@
 //! TODO
 
 mod archetype_macros;
+mod archetype_builder;
 mod archetype_instance;
 mod archetype_registry;
 
-pub use archetype_macros::*;
-pub use archetype_instance::Archetype;
+pub use archetype_instance::{Archetype, ArchetypeGrowth};
+pub use archetype_builder::ArchetypeBuilder;
+pub use archetype_registry::{ArchetypeEvent, ArchetypeProfiler, ArchetypeTransitionKind};
 
 pub(crate) use archetype_instance::*;
 pub(crate) use archetype_registry::*;
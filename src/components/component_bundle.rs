@@ -0,0 +1,49 @@
+use crate::components::{Component, ComponentType};
+use crate::entities::{Entity, EntityRegistry};
+
+/// A group of [components](Component) that can be added to an [entity](Entity) at once,
+/// through [EntityRegistry::add_bundle].  
+/// It is implemented for tuples of up to 16 [components](Component).
+pub trait Bundle: 'static {
+	/// Gets the [component types](ComponentType) contained in the [Bundle].
+	fn component_types() -> Vec<ComponentType>;
+
+	/// Writes all [components](Component) of the [Bundle] to the specified [entity](Entity).
+	/// **Should not be called from user code.**
+	///
+	/// # Safety
+	/// See [EntityRegistry::write_component].
+	#[doc(hidden)]
+	unsafe fn write(self, registry: &mut EntityRegistry, entity: &Entity);
+}
+
+macro_rules! impl_bundle {
+    ($($t: ident $i: tt),*) => {
+        impl<$($t: Component),*> Bundle for ($($t),*,) {
+            fn component_types() -> Vec<ComponentType> {
+                vec![$(ComponentType::of::<$t>()),*]
+            }
+
+            unsafe fn write(self, registry: &mut EntityRegistry, entity: &Entity) {
+                $(registry.write_component(entity, self.$i);)*
+            }
+        }
+    };
+}
+
+impl_bundle!(T0 0);
+impl_bundle!(T0 0, T1 1);
+impl_bundle!(T0 0, T1 1, T2 2);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14);
+impl_bundle!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14, T15 15);
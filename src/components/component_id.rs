@@ -1,6 +1,9 @@
 //! A  unique runtime identifier tied to a [Component] type.
 //!
-//! Developers shouldn't rely on [component ids](ComponentId), as they are not stable between program re-runs.
+//! Developers shouldn't rely on [component ids](ComponentId), as they are not stable between program re-runs.  
+//! Ids are process-global and assigned in the order in which [components](Component) are first used,
+//! so they are not deterministic: registering the same [components](Component) in a different order
+//! produces different ids, and there is no way to reset them.
 //! [Component ids](ComponentId) are generally used for populating the various
 //! [bitfields](crate::data_structures::BitField) used in
 //! [entity queries](crate::entities::EntityQuery).
@@ -8,10 +11,24 @@
 use std::sync::atomic::Ordering::Relaxed;
 use crate::data_structures::BitField;
 use std::sync::atomic::AtomicUsize;
-use crate::components::Component;
+use crate::components::{Component, ComponentStorage, ComponentType};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::any::TypeId;
+use std::fmt::{Display, Formatter};
+use std::error::Error;
 use std::hash::Hash;
 
-static mut NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+lazy_static! {
+	static ref ID_TO_NAME: RwLock<Vec<&'static str>> = RwLock::new(vec![""]);
+	static ref SPARSE_IDS: RwLock<BitField> = RwLock::new(BitField::new());
+	static ref GENERIC_IDS: RwLock<HashMap<TypeId, ComponentId>> = RwLock::new(HashMap::new());
+	static ref NAME_TO_TYPE: RwLock<HashMap<&'static str, Option<ComponentType>>> = RwLock::new(HashMap::new());
+	static ref ID_TO_TYPE: RwLock<Vec<Option<ComponentType>>> = RwLock::new(Vec::new());
+}
 
 /// A globally unique identifier for a type implementing the [`Component`] trait.
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
@@ -26,10 +43,28 @@ impl ComponentId {
 		T::component_id()
 	}
 
+	/// Get the type name of the [Component] the [ComponentId] belongs to.
+	pub fn name(&self) -> &'static str {
+		ID_TO_NAME.read()[self.value]
+	}
+
+	/// Get where the values of the [Component] the [ComponentId] belongs to are stored.
+	pub fn storage(&self) -> ComponentStorage {
+		match SPARSE_IDS.read().get(self.value) {
+			true => ComponentStorage::Sparse,
+			false => ComponentStorage::Table,
+		}
+	}
+
 	#[inline(always)]
 	pub(crate) const fn value(&self) -> usize {
 		self.value
 	}
+
+	#[inline(always)]
+	pub(crate) const fn from_value(value: usize) -> Self {
+		Self { value }
+	}
 }
 
 impl From<&[ComponentId]> for BitField {
@@ -48,11 +83,107 @@ impl From<&[ComponentId]> for BitField {
 /// # Safety
 /// Always safe when called from library code for newly instantiated [components](Component).  
 /// To be called from code generated from #[derive([Component])].
-pub unsafe fn get_next() -> ComponentId {
+pub unsafe fn get_next(name: &'static str, storage: ComponentStorage) -> ComponentId {
+	let mut names = ID_TO_NAME.write();
 	let value = NEXT_ID.fetch_add(1, Relaxed);
 	debug_assert!(
 		value <= u32::MAX as usize,
 		"This is an insane number of components. Please seek help."
 	);
+
+	if names.len() <= value {
+		names.resize(value + 1, "");
+	}
+
+	names[value] = name;
+	if storage == ComponentStorage::Sparse {
+		SPARSE_IDS.write().set(value, true);
+	}
+
 	ComponentId { value }
 }
+
+/// Generates a new [ComponentId] for `T` and registers its [ComponentType] by name.
+/// **Should not be called from user code.**
+///
+/// # Safety
+/// Always safe when called from library code for newly instantiated [components](Component).  
+/// To be called from code generated from #[derive([Component])].
+#[doc(hidden)]
+pub unsafe fn get_next_of<T: Component>() -> ComponentId {
+	let name = std::any::type_name::<T>();
+	let id = get_next(name, T::STORAGE);
+	let component = ComponentType::with_id::<T>(id);
+
+	let mut by_id = ID_TO_TYPE.write();
+	if by_id.len() <= id.value {
+		by_id.resize(id.value + 1, None);
+	}
+	by_id[id.value] = Some(component.clone());
+	drop(by_id);
+
+	let mut types = NAME_TO_TYPE.write();
+	types.insert(name, Some(component.clone()));
+
+	// Unqualified names are only registered as long as they are unambiguous.
+	if !name.contains('<') {
+		let short = name.rsplit("::").next().unwrap();
+		types.entry(short).and_modify(|t| *t = None).or_insert(Some(component));
+	}
+
+	id
+}
+
+/// Gets the [ComponentType] registered with the specified name.
+pub(crate) fn type_by_name(name: &str) -> Option<ComponentType> {
+	NAME_TO_TYPE.read().get(name).cloned().flatten()
+}
+
+/// Gets the [ComponentType] with the specified [ComponentId].
+pub(crate) fn type_by_id(id: ComponentId) -> Result<ComponentType, UnknownComponentError> {
+	let types = ID_TO_TYPE.read();
+	types.get(id.value).cloned().flatten().ok_or(UnknownComponentError(id))
+}
+
+/// A [ComponentId] that does not belong to any registered [Component].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnknownComponentError(pub ComponentId);
+
+impl Display for UnknownComponentError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "No component is registered with id {}", self.0.value)
+	}
+}
+
+impl Error for UnknownComponentError {}
+
+/// Gets the [ComponentId] of a generic [Component] type, generating it on first use.
+/// **Should not be called from user code.**
+///
+/// Generic types cannot hold a static per instantiation, so their ids are looked up by [TypeId].
+#[doc(hidden)]
+pub fn get_generic<T: Component>() -> ComponentId {
+	let key = TypeId::of::<T>();
+	if let Some(id) = GENERIC_IDS.read().get(&key) {
+		return *id;
+	}
+
+	let mut ids = GENERIC_IDS.write();
+	*ids.entry(key).or_insert_with(|| unsafe { get_next_of::<T>() })
+}
+
+/// Gets the [ComponentId] and name of every [Component] registered so far, in registration order.
+///
+/// Ids are process-global and cannot be reset, since every [Component] type caches its own.
+/// Tests should therefore only rely on the identity of ids, never on their values.
+#[cfg(test)]
+pub(crate) fn registered() -> Vec<(ComponentId, &'static str)> {
+	let names = ID_TO_NAME.read();
+	let end = NEXT_ID.load(Relaxed).min(names.len());
+	(1..end).map(|value| (ComponentId { value }, names[value])).collect()
+}
+
+/// Get the [BitField] of all [sparse](ComponentStorage::Sparse) [component ids](ComponentId) created so far.
+pub(crate) fn sparse_ids() -> BitField {
+	SPARSE_IDS.read().clone()
+}
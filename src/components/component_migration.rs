@@ -0,0 +1,91 @@
+use crate::components::Component;
+use std::fmt::{Display, Formatter};
+use std::error::Error;
+
+type Decoder<T> = Box<dyn Fn(&[u8]) -> T>;
+type Migration<T> = Box<dyn Fn(u32, &[u8]) -> T>;
+
+/// Loads serialized values of a [Component] type,
+/// migrating values stored by older versions of its layout.
+///
+/// The byte format is up to the user: `decode` reads values stored with the current [Component::VERSION],
+/// while `migrate` receives the stored version and bytes of any older value.
+pub struct ComponentLoader<T: Component> {
+	decode: Decoder<T>,
+	migrate: Option<Migration<T>>,
+}
+
+/// The reason a serialized [component](Component) could not be loaded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ComponentLoadError {
+	/// The value was stored by an older version and no migration was provided.
+	MissingMigration {
+		/// The version the value was stored with.
+		stored: u32,
+		/// The current [Component::VERSION].
+		current: u32,
+	},
+	/// The value was stored by a newer version than the current one.
+	NewerVersion {
+		/// The version the value was stored with.
+		stored: u32,
+		/// The current [Component::VERSION].
+		current: u32,
+	},
+}
+
+impl<T: Component> ComponentLoader<T> {
+	/// Creates a [ComponentLoader] decoding values of the current version with `decode`.
+	pub fn new(decode: impl Fn(&[u8]) -> T + 'static) -> Self {
+		Self {
+			decode: Box::new(decode),
+			migrate: None,
+		}
+	}
+
+	/// Sets the function used to load values stored by older versions.
+	pub fn with_migration(mut self, migrate: impl Fn(u32, &[u8]) -> T + 'static) -> Self {
+		self.migrate = Some(Box::new(migrate));
+		self
+	}
+
+	/// Loads a value stored with the specified `version`.
+	pub fn load(&self, version: u32, bytes: &[u8]) -> Result<T, ComponentLoadError> {
+		let current = T::VERSION;
+		if version == current {
+			return Ok((self.decode)(bytes));
+		}
+		if version > current {
+			return Err(ComponentLoadError::NewerVersion {
+				stored: version,
+				current,
+			});
+		}
+
+		match &self.migrate {
+			Some(migrate) => Ok(migrate(version, bytes)),
+			None => Err(ComponentLoadError::MissingMigration {
+				stored: version,
+				current,
+			}),
+		}
+	}
+}
+
+impl Display for ComponentLoadError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingMigration { stored, current } => {
+				write!(f, "No migration from component version {stored} to {current}")
+			},
+			Self::NewerVersion { stored, current } => {
+				write!(
+					f,
+					"Component version {stored} is newer than the current version {current}"
+				)
+			},
+		}
+	}
+}
+
+impl Error for ComponentLoadError {}
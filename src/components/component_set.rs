@@ -18,19 +18,37 @@ lazy_static! {
 	static ref EMPTY_BITFIELD: Arc<BitField> = Arc::new(BitField::new());
 	static ref VEC_TO_BITFIELD: Mutex<VBFMap> = Mutex::new(HashMap::default());
 	static ref TYPE_TO_BITFIELD: Mutex<TBFMap> = Mutex::new(HashMap::default());
+	static ref TYPE_TO_WRITTEN: Mutex<HashMap<TypeId, Arc<BitField>, Hasher>> = Mutex::new(HashMap::default());
 }
 
 /// It provides a unified way to create a [BitField](crate::data_structures::BitField) from a set of
 /// [Component](crate::components::Component) types through their base type and all derived ref types.
+///
+/// [ComponentSet] is implemented for single [components](crate::components::Component) and for tuples of up to 16 of them.  
+/// Larger or runtime-defined sets of [components](crate::components::Component) can be queried through
+/// [DynQueryBuilder](crate::entities::DynQueryBuilder) instead.
+#[diagnostic::on_unimplemented(
+	message = "`{Self}` is not a valid set of components",
+	label = "not a component, or a tuple of more than 16 components",
+	note = "component sets are limited to tuples of up to 16 components; use `DynQueryBuilder` for larger sets"
+)]
 pub trait ComponentSet {
 	/// Extract a bitfield from a set of [ComponentIds](crate::components::ComponentId)
 	fn get_bitfield() -> (Arc<BitField>, bool);
+
+	/// Extract a bitfield from the [ComponentIds](crate::components::ComponentId) of the
+	/// [components](crate::components::Component) the set accesses mutably.
+	fn get_written_bitfield() -> Arc<BitField>;
 }
 
 impl ComponentSet for () {
 	fn get_bitfield() -> (Arc<BitField>, bool) {
 		(EMPTY_BITFIELD.clone(), false)
 	}
+
+	fn get_written_bitfield() -> Arc<BitField> {
+		EMPTY_BITFIELD.clone()
+	}
 }
 
 impl<T: 'static + ComponentTypeInfo> ComponentSet for T
@@ -41,6 +59,14 @@ where
 	fn get_bitfield() -> (Arc<BitField>, bool) {
 		<(T::ComponentType,) as ComponentSet>::get_bitfield()
 	}
+
+	#[inline]
+	fn get_written_bitfield() -> Arc<BitField> {
+		match T::MUTABLE {
+			true => <(T::ComponentType,) as ComponentSet>::get_bitfield().0,
+			false => EMPTY_BITFIELD.clone(),
+		}
+	}
 }
 
 fn make_bitfield(components: &[ComponentId]) -> (Arc<BitField>, bool) {
@@ -80,6 +106,19 @@ macro_rules! impl_component_bitfield {
                 ttb.insert(key, (bitfield.clone(), repeats));
                 (bitfield, repeats)
             }
+
+            fn get_written_bitfield() -> Arc<BitField> {
+                let key = TypeId::of::<Self>();
+                let mut ttw = TYPE_TO_WRITTEN.lock();
+                let written = ttw.entry(key).or_insert_with(|| {
+                    let mut bitfield = BitField::new();
+                    $(if <$t>::MUTABLE {
+                        bitfield.set(<$t>::component_id().value(), true);
+                    })*
+                    Arc::new(bitfield)
+                });
+                written.clone()
+            }
         }
     };
 }
@@ -96,3 +135,7 @@ impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
 impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
 impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
 impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12);
+impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13);
+impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14);
+impl_component_bitfield!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14, T15 15);
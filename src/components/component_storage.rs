@@ -0,0 +1,105 @@
+use crate::data_structures::{AnySparseSet, SparseSet};
+use crate::components::{Component, ComponentId};
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use std::collections::HashMap;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+
+/// Determines where the values of a [Component](crate::components::Component) type are stored.
+///
+/// It can be selected through #\[component(storage = "...")].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ComponentStorage {
+	/// Values are stored in the columns of an [archetype](crate::archetypes::Archetype).  
+	/// This is the default, and the fastest to iterate.
+	Table,
+	/// Values are stored in a per-component sparse set.  
+	/// Adding or removing them does not move an [entity](crate::entities::Entity) to another
+	/// [archetype](crate::archetypes::Archetype), which suits rare or frequently toggled components.
+	Sparse,
+}
+
+/// The sparse sets of all [sparse](ComponentStorage::Sparse) components in a registry,
+/// keyed by [ComponentId].
+#[doc(hidden)]
+#[derive(Default)]
+pub struct SparseStorage {
+	sets: HashMap<usize, Box<dyn AnySparseSet>, Hasher>,
+}
+
+impl SparseStorage {
+	pub(crate) fn is_empty(&self) -> bool {
+		self.sets.is_empty()
+	}
+
+	pub(crate) fn get<T: 'static>(&self, id: ComponentId) -> Option<&SparseSet<T>> {
+		let set = self.sets.get(&id.value())?;
+		set.as_any().downcast_ref()
+	}
+
+	pub(crate) fn get_mut<T: 'static>(&mut self, id: ComponentId) -> Option<&mut SparseSet<T>> {
+		let set = self.sets.get_mut(&id.value())?;
+		set.as_any_mut().downcast_mut()
+	}
+
+	pub(crate) fn get_or_insert<T: Component>(&mut self, id: ComponentId) -> &mut SparseSet<T> {
+		let set = self
+			.sets
+			.entry(id.value())
+			.or_insert_with(|| Box::new(SparseSet::<T>::with_clone_fn(T::clone_fn())));
+		set.as_any_mut().downcast_mut().unwrap()
+	}
+
+	pub(crate) fn get_erased(&self, id: ComponentId) -> Option<&dyn AnySparseSet> {
+		self.sets.get(&id.value()).map(|s| s.as_ref())
+	}
+
+	/// Like [SparseStorage::get_erased], but the returned pointer is taken without borrowing the set,
+	/// so it stays valid while the set is mutably borrowed to access its values.
+	pub(crate) fn get_erased_ptr(&self, id: ComponentId) -> Option<*const dyn AnySparseSet> {
+		self.sets.get(&id.value()).map(|s| std::ptr::addr_of!(**s))
+	}
+
+	/// Checks if a value of the [component](crate::components::Component) with the specified id
+	/// is associated with `key`.
+	pub(crate) fn contains(&self, id: ComponentId, key: usize) -> bool {
+		self.sets.get(&id.value()).is_some_and(|s| s.contains_key(key))
+	}
+
+	/// Invokes `func` with the id and a pointer to every value associated with `key`.
+	pub(crate) fn for_each_value(&self, key: usize, mut func: impl FnMut(ComponentId, *const u8)) {
+		for (id, set) in self.sets.iter() {
+			if let Some(value) = set.value_ptr(key) {
+				func(ComponentId::from_value(*id), value);
+			}
+		}
+	}
+
+	/// Checks if all values associated with `key` can be cloned.
+	pub(crate) fn can_clone(&self, key: usize) -> bool {
+		self.sets.values().all(|s| !s.contains_key(key) || s.can_clone())
+	}
+
+	/// Clone all values associated with `src`, associating them with `dst`.
+	pub(crate) fn clone_all(&mut self, src: usize, dst: usize) {
+		for set in self.sets.values_mut() {
+			set.clone_key(src, dst);
+		}
+	}
+
+	/// Move all values associated with `src` to another storage, associating them with `dst_key`.
+	pub(crate) fn move_all(&mut self, src: usize, dst: &mut SparseStorage, dst_key: usize) {
+		for (id, set) in self.sets.iter_mut().filter(|(_, s)| s.contains_key(src)) {
+			let target = dst.sets.entry(*id).or_insert_with(|| set.new_empty());
+			set.move_key(src, target.as_mut(), dst_key);
+		}
+	}
+
+	/// Remove all values associated with `key`.
+	pub(crate) fn remove_all(&mut self, key: usize) {
+		for set in self.sets.values_mut() {
+			set.remove_key(key);
+		}
+	}
+}
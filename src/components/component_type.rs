@@ -1,15 +1,172 @@
 use crate::data_structures::{AnyBuffer, BitField};
-use crate::components::ComponentId;
+use crate::components::component_id::{self, ComponentId, UnknownComponentError};
+use crate::components::ComponentStorage;
 use std::hash::{Hash, Hasher};
+use crate::entities::{Entity, EntityRegistry};
+use std::marker::PhantomData;
 use std::any::TypeId;
 
+/// Clones `count` values of a [Component] type from `src` into the uninitialized memory at `dst`.
+#[doc(hidden)]
+pub type CloneFn = unsafe fn(src: *const u8, dst: *mut u8, count: usize);
+
+/// Writes `count` default values of a [Component] type into the uninitialized memory at `dst`.
+#[doc(hidden)]
+pub type DefaultFn = unsafe fn(dst: *mut u8, count: usize);
+
+/// Registers the [Link](crate::entities::Link) of a [Component] type attached to an [entity](Entity) with its registry.
+#[doc(hidden)]
+pub type LinkFn = fn(registry: &mut EntityRegistry, holder: &Entity);
+
 /// A piece of data associated with an Entity.
+///
+/// [Components](Component) are not required to implement [Default].  
+/// Those that don't must always be given an explicit value, either through [spawn](crate::spawn),
+/// [add_component](crate::entities::EntityRegistry::add_component) or a
+/// [ComponentInitializer]; creating an [entity](Entity) that would default-initialize them panics.
+///
+/// Every instantiation of a generic [Component] is a distinct [Component].  
+/// Generic [components](Component) are only considered [Default] or [Clone] when their
+/// type parameters are bounded accordingly, e.g. `struct Position<T: Default + Clone>`.
+///
+/// #\[derive(Component)] locates this crate even when it's renamed in `Cargo.toml`.  
+/// When it's only reachable through another path, e.g. a re-export,
+/// that path can be specified through #\[component(crate = "...")].
 pub trait Component
 where
-	Self: 'static + Default,
+	Self: 'static,
 {
+	/// Where the values of the [Component] type are stored.
+	const STORAGE: ComponentStorage = ComponentStorage::Table;
+
+	/// The version of the [Component] type's layout, used to migrate serialized values.
+	///
+	/// It can be selected through #\[component(version = ...)].
+	const VERSION: u32 = 1;
+
+	/// Whether the [Component] type references another [entity](Entity) through [Component::link_target].
+	#[doc(hidden)]
+	const LINK: bool = false;
+
 	/// Retrieves the [Component] type's unique runtime identifier.
 	fn component_id() -> ComponentId;
+
+	/// Retrieves the [entity](Entity) referenced by a [Link](crate::entities::Link) component.
+	#[doc(hidden)]
+	#[inline(always)]
+	fn link_target(&self) -> Option<&Entity> {
+		None
+	}
+
+	/// Retrieves the function used to clone values of the [Component] type, if it implements [Clone].
+	#[doc(hidden)]
+	#[inline(always)]
+	fn clone_fn() -> Option<CloneFn> {
+		None
+	}
+
+	/// Retrieves the function used to default-initialize values of the [Component] type, if it implements [Default].
+	#[doc(hidden)]
+	#[inline(always)]
+	fn default_fn() -> Option<DefaultFn> {
+		None
+	}
+}
+
+/// A [Component] whose values can be mutated once added to an [entity](Entity).
+///
+/// It's implemented by #\[derive([`Component`])], unless the [Component] is marked as #\[component(immutable)].  
+/// Immutable [components](Component) can only be read, added and removed,
+/// which makes accidental mutations of invariants, such as stable identifiers, a compile-time error.
+#[diagnostic::on_unimplemented(
+	message = "`{Self}` is an immutable component",
+	label = "cannot be accessed mutably",
+	note = "remove #[component(immutable)] from `{Self}` to allow mutable access"
+)]
+pub trait MutableComponent: Component {}
+
+/// Detects whether a type implements [Clone] without requiring it as a bound.
+/// **Should not be used from user code.**
+///
+/// Calling `(&CloneProbe::<T>::new()).clone_fn()` resolves to [CloneProbeClone] if `T: Clone`,
+/// and falls back to [CloneProbeFallback] otherwise.
+#[doc(hidden)]
+pub struct CloneProbe<T>(PhantomData<T>);
+
+impl<T> CloneProbe<T> {
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+#[doc(hidden)]
+pub trait CloneProbeClone {
+	fn clone_fn(&self) -> Option<CloneFn>;
+}
+
+impl<T: Clone> CloneProbeClone for CloneProbe<T> {
+	fn clone_fn(&self) -> Option<CloneFn> {
+		Some(|src, dst, count| unsafe {
+			let (src, dst) = (src as *const T, dst as *mut T);
+			for i in 0..count {
+				std::ptr::write(dst.add(i), (*src.add(i)).clone());
+			}
+		})
+	}
+}
+
+#[doc(hidden)]
+pub trait CloneProbeFallback {
+	fn clone_fn(&self) -> Option<CloneFn>;
+}
+
+impl<T> CloneProbeFallback for &CloneProbe<T> {
+	fn clone_fn(&self) -> Option<CloneFn> {
+		None
+	}
+}
+
+/// Detects whether a type implements [Default] without requiring it as a bound.
+/// **Should not be used from user code.**
+///
+/// Calling `(&DefaultProbe::<T>::new()).default_fn()` resolves to [DefaultProbeDefault] if `T: Default`,
+/// and falls back to [DefaultProbeFallback] otherwise.
+#[doc(hidden)]
+pub struct DefaultProbe<T>(PhantomData<T>);
+
+impl<T> DefaultProbe<T> {
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+#[doc(hidden)]
+pub trait DefaultProbeDefault {
+	fn default_fn(&self) -> Option<DefaultFn>;
+}
+
+impl<T: Default> DefaultProbeDefault for DefaultProbe<T> {
+	fn default_fn(&self) -> Option<DefaultFn> {
+		Some(|dst, count| unsafe {
+			let dst = dst as *mut T;
+			for i in 0..count {
+				std::ptr::write(dst.add(i), T::default());
+			}
+		})
+	}
+}
+
+#[doc(hidden)]
+pub trait DefaultProbeFallback {
+	fn default_fn(&self) -> Option<DefaultFn>;
+}
+
+impl<T> DefaultProbeFallback for &DefaultProbe<T> {
+	fn default_fn(&self) -> Option<DefaultFn> {
+		None
+	}
 }
 
 /// A runtime representation of a type implementing the [`Component`] trait.
@@ -17,19 +174,58 @@ where
 pub struct ComponentType {
 	id: ComponentId,
 	type_id: TypeId,
+	storage: ComponentStorage,
+	size: usize,
+	align: usize,
 	make_vec: fn() -> AnyBuffer,
+	clone: Option<CloneFn>,
+	default: Option<DefaultFn>,
+	link: Option<LinkFn>,
 }
 
 impl ComponentType {
 	/// Retrieves the [ComponentType] of `T`
 	pub fn of<T: Component>() -> Self {
+		Self::with_id::<T>(ComponentId::of::<T>())
+	}
+
+	/// Retrieves the [ComponentType] of the [Component] registered with the specified name, if any.
+	///
+	/// Both the full type name, as returned by [std::any::type_name] and [ComponentId::name],
+	/// and the unqualified one are accepted. Unqualified names shared by more than one [Component] are not.  
+	/// A [Component] is only registered once its [ComponentId] has been generated,
+	/// which happens the first time the type is used, e.g. through [ComponentType::of].
+	pub fn by_name(name: &str) -> Option<Self> {
+		component_id::type_by_name(name)
+	}
+
+	/// Retrieves the [ComponentType] of the [Component] with the specified [ComponentId].
+	///
+	/// A [Component] is only registered once its [ComponentId] has been generated,
+	/// which happens the first time the type is used, e.g. through [ComponentType::of].
+	pub fn by_id(id: ComponentId) -> Result<Self, UnknownComponentError> {
+		component_id::type_by_id(id)
+	}
+
+	pub(crate) fn with_id<T: Component>(id: ComponentId) -> Self {
 		Self {
-			id: ComponentId::of::<T>(),
+			id,
 			type_id: TypeId::of::<T>(),
-			make_vec: AnyBuffer::new_default::<T>,
+			storage: T::STORAGE,
+			size: std::mem::size_of::<T>(),
+			align: std::mem::align_of::<T>(),
+			make_vec: AnyBuffer::new::<T>,
+			clone: T::clone_fn(),
+			default: T::default_fn(),
+			link: T::LINK.then_some(EntityRegistry::link_attached::<T>),
 		}
 	}
 
+	/// Retrieves the [ComponentType] of `value`'s type.
+	pub fn of_val<T: Component>(_: &T) -> Self {
+		Self::of::<T>()
+	}
+
 	/// Retrieves the [ComponentType]'s unique runtime identifier.
 	pub const fn id(&self) -> ComponentId {
 		self.id
@@ -40,8 +236,41 @@ impl ComponentType {
 		self.type_id
 	}
 
+	/// Retrieves where the values of the [ComponentType] are stored.
+	pub const fn storage(&self) -> ComponentStorage {
+		self.storage
+	}
+
+	/// Retrieves the size in bytes of the values of the [ComponentType].
+	pub const fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Retrieves the alignment in bytes of the values of the [ComponentType].
+	pub const fn align(&self) -> usize {
+		self.align
+	}
+
+	/// Checks if the values of the [ComponentType] can be cloned.
+	pub const fn is_clone(&self) -> bool {
+		self.clone.is_some()
+	}
+
+	/// Checks if the values of the [ComponentType] can be default-initialized.
+	pub const fn has_default(&self) -> bool {
+		self.default.is_some()
+	}
+
+	/// Gets the function registering the [Link](crate::entities::Link) of an [entity](Entity), if the type is one.
+	pub(crate) fn link_fn(&self) -> Option<LinkFn> {
+		self.link
+	}
+
 	pub(crate) fn create_buffer(&self) -> AnyBuffer {
-		(self.make_vec)()
+		let mut buffer = (self.make_vec)();
+		buffer.set_clone_fn(self.clone);
+		buffer.set_default_fn(self.default);
+		buffer
 	}
 }
 
@@ -59,6 +288,32 @@ impl Hash for ComponentType {
 	}
 }
 
+/// A custom function used to initialize a [Component] in place of [Default::default],
+/// keyed by the [Component]'s [ComponentId].
+pub struct ComponentInitializer {
+	id: ComponentId,
+	apply: Box<dyn FnOnce(&mut AnyBuffer)>,
+}
+
+impl ComponentInitializer {
+	/// Creates an initializer for `T` that constructs its values with `init`.
+	pub fn new<T: Component>(init: impl Fn() -> T + Send + Sync + 'static) -> Self {
+		Self {
+			id: T::component_id(),
+			apply: Box::new(move |buffer| buffer.set_initializer(init)),
+		}
+	}
+
+	/// Retrieves the unique runtime identifier of the initialized [Component].
+	pub const fn id(&self) -> ComponentId {
+		self.id
+	}
+
+	pub(crate) fn apply(self, buffer: &mut AnyBuffer) {
+		(self.apply)(buffer)
+	}
+}
+
 /// It provides a unified way to access a component's [ComponentId] and type
 /// through its base type and all derived ref types.
 ///
@@ -67,21 +322,64 @@ pub trait ComponentTypeInfo {
 	/// The underlying [Component]'s type
 	type ComponentType: ComponentTypeInfo;
 
+	/// Where the values of the underlying [Component] type are stored.
+	const STORAGE: ComponentStorage;
+
+	/// Whether the type grants mutable access to the underlying [Component].
+	const MUTABLE: bool = false;
+
 	/// Retrieves the [Component] type's unique runtime identifier.
 	fn component_id() -> ComponentId;
 }
 
 impl<T: ComponentTypeInfo> ComponentTypeInfo for &T {
 	type ComponentType = T::ComponentType;
+	const STORAGE: ComponentStorage = T::STORAGE;
 	fn component_id() -> ComponentId {
 		Self::ComponentType::component_id()
 	}
 }
 
-impl<T: ComponentTypeInfo> ComponentTypeInfo for &mut T {
+impl<T: ComponentTypeInfo> ComponentTypeInfo for &mut T
+where
+	T::ComponentType: MutableComponent,
+{
 	type ComponentType = T::ComponentType;
+	const STORAGE: ComponentStorage = T::STORAGE;
+	const MUTABLE: bool = true;
 	fn component_id() -> ComponentId {
-		Self::ComponentType::component_id()
+		<Self::ComponentType as ComponentTypeInfo>::component_id()
+	}
+}
+
+/// It binds a `'static` [Component] reference type, as used in [component sets](crate::components::ComponentSet),
+/// to the lifetime `'l` of the borrow it was obtained from.
+pub trait ComponentRef<'l>: ComponentTypeInfo {
+	/// The bound reference type.
+	type Ref;
+
+	/// # Safety
+	/// Always safe if called by an iterator over an archetype.
+	/// The iterator guarantees Rust's aliasing rules are maintained.
+	#[doc(hidden)]
+	unsafe fn from_ptr(ptr: *mut Self::ComponentType) -> Self::Ref;
+}
+
+impl<'l, T: Component + ComponentTypeInfo<ComponentType = T>> ComponentRef<'l> for &'static T {
+	type Ref = &'l T;
+
+	#[inline(always)]
+	unsafe fn from_ptr(ptr: *mut T) -> &'l T {
+		&*ptr
+	}
+}
+
+impl<'l, T: MutableComponent + ComponentTypeInfo<ComponentType = T>> ComponentRef<'l> for &'static mut T {
+	type Ref = &'l mut T;
+
+	#[inline(always)]
+	unsafe fn from_ptr(ptr: *mut T) -> &'l mut T {
+		&mut *ptr
 	}
 }
 
@@ -120,7 +418,7 @@ impl<T: Component> ComponentFrom<*mut T> for &T {
 	}
 }
 
-impl<T: Component> ComponentFrom<*mut T> for &mut T {
+impl<T: MutableComponent> ComponentFrom<*mut T> for &mut T {
 	#[inline(always)]
 	unsafe fn convert(value: *mut T) -> Self {
 		&mut *value
@@ -0,0 +1,59 @@
+use crate::components::{Component, ComponentId, ComponentType};
+use std::marker::PhantomData;
+use std::any::TypeId;
+
+/// A type-erased reference to the value of a [Component], as received by a [ComponentVisitor].
+pub struct ErasedComponent<'l> {
+	component: ComponentType,
+	value: *const u8,
+	phantom: PhantomData<&'l ()>,
+}
+
+impl<'l> ErasedComponent<'l> {
+	/// # Safety
+	/// `value` must point to an initialized value of `component`'s type, valid for `'l`.
+	pub(crate) unsafe fn new(component: ComponentType, value: *const u8) -> Self {
+		Self {
+			component,
+			value,
+			phantom: PhantomData,
+		}
+	}
+
+	/// Gets the [ComponentType] of the value.
+	pub fn component_type(&self) -> &ComponentType {
+		&self.component
+	}
+
+	/// Gets the [ComponentId] of the value's type.
+	pub fn id(&self) -> ComponentId {
+		self.component.id()
+	}
+
+	/// Gets a reference to the value if it is of type `T`.
+	pub fn downcast_ref<T: Component>(&self) -> Option<&'l T> {
+		match self.component.type_id() == TypeId::of::<T>() {
+			true => Some(unsafe { &*(self.value as *const T) }),
+			false => None,
+		}
+	}
+
+	/// Gets a pointer to the value.
+	pub fn as_ptr(&self) -> *const u8 {
+		self.value
+	}
+}
+
+/// Receives the [components](Component) of an [entity](crate::entities::Entity), one at a time.
+///
+/// See [EntityRegistry::visit_components](crate::entities::EntityRegistry::visit_components).
+pub trait ComponentVisitor {
+	/// Visits a single [component](Component).
+	fn visit(&mut self, component: ErasedComponent<'_>);
+}
+
+impl<F: FnMut(ErasedComponent<'_>)> ComponentVisitor for F {
+	fn visit(&mut self, component: ErasedComponent<'_>) {
+		self(component)
+	}
+}
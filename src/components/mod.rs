@@ -16,8 +16,16 @@
 pub mod component_id;
 mod component_type;
 mod component_set;
+mod component_storage;
+mod component_migration;
+mod component_bundle;
+mod component_visitor;
 
 pub use component_set::*;
 pub use component_type::*;
+pub use component_migration::*;
+pub use component_bundle::*;
+pub use component_visitor::*;
+pub use component_storage::{ComponentStorage, SparseStorage};
 pub use turbo_ecs_derive::Component;
 pub(crate) use component_id::{ComponentId};
@@ -1,7 +1,7 @@
 use crate::systems::{System, SystemRegistry};
-use crate::components::ComponentType;
-use crate::entities::EntityRegistry;
-use crate::archetypes::Archetype;
+use crate::components::{ComponentInitializer, ComponentSet, ComponentType};
+use crate::entities::{ComponentQuery, EntityQuery, EntityRegistry};
+use crate::archetypes::{Archetype, ArchetypeBuilder};
 use std::ops::{Deref, DerefMut};
 
 /// A container for
@@ -22,6 +22,15 @@ impl EcsContext {
 		}
 	}
 
+	/// Creates a new [EcsContext] able to hold the specified number of [entities](crate::entities::Entity)
+	/// without further allocations.
+	pub fn with_capacity(entities: usize) -> Self {
+		Self {
+			entity_store: EntityRegistry::with_capacity(entities),
+			system_store: SystemRegistry::new(),
+		}
+	}
+
 	/// Creates an [archetype](crate::archetypes::Archetype) containing the specified [`components`](crate::components::Component).
 	pub fn create_archetype(&mut self, components: &[ComponentType]) -> Archetype {
 		self.entity_store.archetype_store.create_archetype(components)
@@ -32,20 +41,87 @@ impl EcsContext {
 		self.entity_store.archetype_store.create_archetype_with_capacity(components, min_capacity)
 	}
 
+	/// Creates a new [archetype](crate::archetypes::Archetype) containing the specified [`components`](crate::components::Component),
+	/// distinct from any other [archetype](crate::archetypes::Archetype) with the same [`components`](crate::components::Component).
+	///
+	/// Pinned archetypes keep a group of entities in their own storage, so they don't interleave with unrelated ones.  
+	/// Entities are only placed in them explicitly, by creating them from the archetype or moving them to it.
+	/// Adding or removing a component moves an entity out of its pinned archetype.
+	/// Queries match pinned archetypes like any other.
+	pub fn create_pinned_archetype(&mut self, components: &[ComponentType]) -> Archetype {
+		self.entity_store.archetype_store.create_pinned_archetype(components, 0)
+	}
+
+	/// Starts building an [archetype](crate::archetypes::Archetype) one [component](crate::components::Component) at a time.
+	pub fn build_archetype(&mut self) -> ArchetypeBuilder<'_> {
+		ArchetypeBuilder::new(&mut self.entity_store.archetype_store)
+	}
+
+	/// Creates an [archetype](crate::archetypes::Archetype) containing the specified [`components`](crate::components::Component).
+	///
+	/// New entities of the archetype will have the [`components`](crate::components::Component) with an initializer
+	/// constructed by it instead of their [Default] implementation.  
+	/// Since archetypes are shared by all entities with the same set of components,
+	/// the initializers replace any previously set for the archetype.
+	pub fn create_archetype_with_initializers(
+		&mut self, components: &[ComponentType], initializers: Vec<ComponentInitializer>,
+	) -> Archetype {
+		self.entity_store.archetype_store.create_archetype_with_initializers(components, initializers)
+	}
+
+	/// Removes all [archetypes](crate::archetypes::Archetype) that currently contain no [entities](crate::entities::Entity),
+	/// returning how many were removed.
+	///
+	/// [Archetypes](crate::archetypes::Archetype) are otherwise kept forever once created,
+	/// so worlds churning through many short-lived combinations of [components](crate::components::Component)
+	/// can call this periodically to release their storage and keep query matching fast.  
+	/// Handles to the remaining [archetypes](crate::archetypes::Archetype) stay valid,
+	/// while using a handle to a removed one panics; it must be re-created instead.
+	pub fn collect_empty_archetypes(&mut self) -> usize {
+		self.entity_store.archetype_store.collect_empty()
+	}
+
+	/// Matches `query` against all existing [archetypes](crate::archetypes::Archetype) ahead of time.
+	///
+	/// Queries are otherwise initialized lazily the first time they are iterated,
+	/// which requires scanning every [archetype](crate::archetypes::Archetype).  
+	/// [Archetypes](crate::archetypes::Archetype) created afterwards are matched as they are created.
+	pub fn warm_query(&mut self, query: EntityQuery) {
+		self.entity_store.archetype_store.warm_query(query);
+	}
+
+	/// Matches the query described by the included components `I` and excluded components `E`
+	/// against all existing [archetypes](crate::archetypes::Archetype) ahead of time.  
+	/// See [EcsContext::warm_query].
+	pub fn warm_query_of<I: 'static + ComponentSet, E: 'static + ComponentSet>(&mut self) {
+		self.warm_query(<(I, E)>::get_query());
+	}
+
 	/// Add a new [system](System) to the [EcsContext].
 	pub fn register_system<T: 'static + System>(&mut self, system: T) {
 		self.system_store.add_system(system);
 	}
 
+	/// Add a new [system](System) to the [EcsContext], which only runs when `criteria` returns true.
+	///
+	/// `criteria` is evaluated every time the [systems](System) are run, right before the [system](System) would be.  
+	/// Useful for pausing [systems](System) or running them at a lower rate.
+	pub fn register_system_with<T: 'static + System>(
+		&mut self, system: T, criteria: impl FnMut(&EntityRegistry) -> bool + 'static,
+	) {
+		self.system_store.add_system_with(system, Some(Box::new(criteria)));
+	}
+
 	/// Initialize all [systems](System)
 	/// Must be called before any system can be run.
 	pub fn setup_systems(&mut self) {
-		self.system_store.setup_systems();
+		self.system_store.setup_systems(&mut self.entity_store);
 	}
 
 	/// Execute all [systems](System).
 	pub fn run_systems(&mut self) {
 		self.system_store.run_systems(&mut self.entity_store);
+		self.entity_store.advance_tick();
 	}
 }
 
@@ -1,18 +1,24 @@
+use crate::components::{CloneFn, DefaultFn};
 use std::mem::{MaybeUninit, align_of, size_of};
 use std::alloc::Layout;
 use std::any::TypeId;
 use std::ops::Range;
 
+type Initializer = Box<dyn Fn(*mut u8, Range<usize>) + Send + Sync>;
+
 /// A polymorphic container for items of the same type.
 /// The container does not keep track of which values stored within have been initialized,
 /// nor will it automatically drop them upon destruction.
 pub(crate) struct AnyBuffer {
 	buffer: Box<[u8]>,
+	capacity: usize,
 	type_id: TypeId,
 	type_size: usize,
 	type_align: usize,
-	drop: fn(&mut Self, Range<usize>),
-	default: Option<fn(&mut Self, Range<usize>)>,
+	drop: Option<fn(&mut Self, Range<usize>)>,
+	default: Option<DefaultFn>,
+	initializer: Option<Initializer>,
+	clone: Option<CloneFn>,
 }
 
 #[allow(dead_code)]
@@ -21,10 +27,6 @@ impl AnyBuffer {
 		Self::with_capacity::<T>(1)
 	}
 
-	pub fn new_default<T: 'static + Default>() -> Self {
-		Self::with_capacity_default::<T>(1)
-	}
-
 	#[allow(clippy::uninit_vec)]
 	pub fn with_capacity<T: 'static>(capacity: usize) -> Self {
 		unsafe {
@@ -34,32 +36,61 @@ impl AnyBuffer {
 
 			Self {
 				buffer,
+				capacity,
 				type_size,
 				type_align,
 				type_id: TypeId::of::<T>(),
 
-				drop: |this, range| {
-					let ptr = (this.buffer.as_mut_ptr() as *mut T).add(range.start);
-					let slice = std::slice::from_raw_parts_mut(ptr, range.len());
-					std::ptr::drop_in_place(slice);
+				// Values that don't need dropping are simply overwritten.
+				drop: match std::mem::needs_drop::<T>() {
+					false => None,
+					true => Some(|this, range| {
+						let ptr = (this.buffer.as_mut_ptr() as *mut T).add(range.start);
+						let slice = std::slice::from_raw_parts_mut(ptr, range.len());
+						std::ptr::drop_in_place(slice);
+					}),
 				},
 
 				default: None,
+				initializer: None,
+				clone: None,
 			}
 		}
 	}
 
-	pub fn with_capacity_default<T: 'static + Default>(capacity: usize) -> Self {
-		let mut this = Self::with_capacity::<T>(capacity);
-		this.default = Some(|this, range| unsafe {
-			let ptr = (this.buffer.as_mut_ptr() as *mut T).add(range.start);
-			let slice = std::slice::from_raw_parts_mut(ptr, range.len());
-			for x in slice {
-				std::ptr::write(x, T::default());
+	/// Overrides the function used by [AnyBuffer::default_values] to initialize values.
+	pub fn set_initializer<T: 'static>(&mut self, init: impl Fn() -> T + Send + Sync + 'static) {
+		assert_eq!(
+			self.type_id,
+			TypeId::of::<T>(),
+			"Buffer does not contain elements of type T"
+		);
+		self.initializer = Some(Box::new(move |buffer, range| unsafe {
+			let ptr = (buffer as *mut T).add(range.start);
+			for i in 0..range.len() {
+				std::ptr::write(ptr.add(i), init());
 			}
-		});
+		}));
+	}
 
-		this
+	/// Sets the function used by [AnyBuffer::default_values] to initialize values when no initializer is set.
+	pub fn set_default_fn(&mut self, default: Option<DefaultFn>) {
+		self.default = default;
+	}
+
+	/// Sets the function used by [AnyBuffer::clone_values] to clone values.
+	pub fn set_clone_fn(&mut self, clone: Option<CloneFn>) {
+		self.clone = clone;
+	}
+
+	/// Checks if the buffer's values can be cloned.
+	pub fn can_clone(&self) -> bool {
+		self.clone.is_some()
+	}
+
+	/// Checks if the buffer's values need to be dropped.
+	pub fn needs_drop(&self) -> bool {
+		self.drop.is_some()
 	}
 
 	pub fn ensure_capacity(&mut self, capacity: usize) {
@@ -68,7 +99,8 @@ impl AnyBuffer {
 			if current < capacity {
 				let mut buffer = make_buffer(self.type_size, self.type_align, capacity);
 				std::ptr::copy_nonoverlapping(self.buffer.as_ptr(), buffer.as_mut_ptr(), self.buffer.len());
-				self.buffer = buffer;
+				free_buffer(std::mem::replace(&mut self.buffer, buffer), self.type_align);
+				self.capacity = capacity;
 			}
 		}
 	}
@@ -80,7 +112,9 @@ impl AnyBuffer {
 		debug_assert!(range.start < self.capacity());
 		debug_assert!(range.len() <= self.capacity() - range.start);
 
-		(self.drop)(self, range);
+		if let Some(drop) = self.drop {
+			drop(self, range);
+		}
 	}
 
 	/// # Safety
@@ -90,9 +124,13 @@ impl AnyBuffer {
 		debug_assert!(range.start < self.capacity());
 		debug_assert!(range.len() <= self.capacity() - range.start);
 
+		if let Some(initializer) = &self.initializer {
+			return initializer(self.buffer.as_mut_ptr(), range);
+		}
+
 		match self.default {
-			None => panic!("Buffer does not have a default function for T"),
-			Some(default) => default(self, range),
+			None => panic!("Component does not implement Default, its values must be provided explicitly"),
+			Some(default) => default(self.buffer.as_mut_ptr().add(range.start * self.type_size), range.len()),
 		}
 	}
 
@@ -117,13 +155,47 @@ impl AnyBuffer {
 		std::ptr::copy_nonoverlapping(src, dst, range.len() * self.type_size);
 	}
 
+	/// Clones values into another buffer. Unlike [AnyBuffer::copy_values], the source values remain valid.
+	///
+	/// # Safety
+	/// - The two buffers must contain the same type.
+	/// - All values in `range` must be initialized.
+	/// - The destination values must be uninitialized or already dropped.
+	/// - `range` and `range.len() + dst_offset` must be within the bounds of their buffers.
+	pub unsafe fn clone_values(&self, dst: &mut Self, range: Range<usize>, dst_offset: usize) {
+		debug_assert!(self.type_id == dst.type_id);
+		debug_assert!(range.len() <= self.capacity() - range.start);
+		debug_assert!(range.len() <= dst.capacity() - dst_offset);
+
+		let clone = self.clone.expect("Buffer does not have a clone function for T");
+		let src = self.buffer.as_ptr().add(range.start * self.type_size);
+		let dst = dst.buffer.as_mut_ptr().add(dst_offset * self.type_size);
+		clone(src, dst, range.len());
+	}
+
+	/// Clones the value at `src` into `dst` within the same buffer.
+	///
+	/// # Safety
+	/// - The value at `src` must be initialized.
+	/// - The value at `dst` must be uninitialized or already dropped.
+	/// - `src` and `dst` must be distinct and within the bounds of the buffer.
+	pub unsafe fn clone_within(&mut self, src: usize, dst: usize) {
+		debug_assert!(src != dst);
+		debug_assert!(src < self.capacity() && dst < self.capacity());
+
+		let clone = self.clone.expect("Buffer does not have a clone function for T");
+		let ptr = self.buffer.as_mut_ptr();
+		clone(ptr.add(src * self.type_size), ptr.add(dst * self.type_size), 1);
+	}
+
 	pub fn as_slice<T: 'static>(&self) -> &[MaybeUninit<T>] {
 		assert_eq!(
 			self.type_id,
 			TypeId::of::<T>(),
 			"Buffer does not contain elements of type T"
 		);
-		unsafe { self.as_slice_unchecked() }
+		let ptr = self.buffer.as_ptr() as *const MaybeUninit<T>;
+		unsafe { std::slice::from_raw_parts(ptr, self.capacity()) }
 	}
 
 	/// # Safety
@@ -144,7 +216,8 @@ impl AnyBuffer {
 			TypeId::of::<T>(),
 			"Buffer does not contain elements of type T"
 		);
-		unsafe { self.as_mut_slice_unchecked() }
+		let ptr = self.buffer.as_mut_ptr() as *mut MaybeUninit<T>;
+		unsafe { std::slice::from_raw_parts_mut(ptr, self.capacity()) }
 	}
 
 	/// # Safety
@@ -159,13 +232,49 @@ impl AnyBuffer {
 		std::slice::from_raw_parts_mut(ptr, self.capacity())
 	}
 
+	/// Gets a pointer to the value at `index`.
+	///
+	/// # Safety
+	/// `index` must be within the bounds of the buffer.
+	pub unsafe fn value_ptr(&self, index: usize) -> *const u8 {
+		debug_assert!(index < self.capacity());
+		self.buffer.as_ptr().add(index * self.type_size)
+	}
+
 	pub fn capacity(&self) -> usize {
-		self.buffer.len() / self.type_size
+		self.capacity
+	}
+
+	/// Gets the number of bytes allocated for the buffer's values.
+	pub fn size_in_bytes(&self) -> usize {
+		self.capacity * self.type_size
+	}
+}
+
+impl Drop for AnyBuffer {
+	fn drop(&mut self) {
+		unsafe { free_buffer(std::mem::take(&mut self.buffer), self.type_align) }
 	}
 }
 
 unsafe fn make_buffer(t_size: usize, t_align: usize, count: usize) -> Box<[u8]> {
 	let bytes = t_size.checked_mul(count).unwrap();
+	if bytes == 0 {
+		return Box::from_raw(std::ptr::slice_from_raw_parts_mut(t_align as *mut u8, 0));
+	}
+
 	let layout = Layout::from_size_align(bytes, t_align).unwrap();
-	Box::from_raw(std::slice::from_raw_parts_mut(std::alloc::alloc(layout), layout.size()))
+	Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+		std::alloc::alloc(layout),
+		layout.size(),
+	))
+}
+
+/// Buffers are allocated with the alignment of their type, so they must not be freed by [Box].
+unsafe fn free_buffer(buffer: Box<[u8]>, t_align: usize) {
+	let bytes = buffer.len();
+	let ptr = Box::into_raw(buffer) as *mut u8;
+	if bytes != 0 {
+		std::alloc::dealloc(ptr, Layout::from_size_align_unchecked(bytes, t_align));
+	}
 }
@@ -1,7 +1,7 @@
 use std::sync::atomic::AtomicU32;
 use std::hash::{Hash, Hasher};
 use std::cmp::Ordering;
-use std::iter::repeat;
+use std::iter::repeat_n;
 use std::ops::Range;
 
 const BITS: usize = 32;
@@ -46,7 +46,7 @@ impl BitField {
 			Ordering::Greater => {
 				let bit_value = unsafe { self.values.get_unchecked(position) };
 				let bit = FIRST_BIT >> shift;
-				(bit_value & bit as u32) != 0
+				(bit_value & bit) != 0
 			},
 			_ => false,
 		}
@@ -61,7 +61,7 @@ impl BitField {
 		let (position, shift) = Self::pos_shift(i);
 		let bit_value = self.values.get_unchecked(position);
 		let bit = FIRST_BIT >> shift;
-		(bit_value & bit as u32) != 0
+		(bit_value & bit) != 0
 	}
 
 	/// Set the value of the bit at index `i`.
@@ -149,6 +149,32 @@ impl BitField {
 		}
 	}
 
+	/// Set the bits at the indices in `set` and clear the bits at the indices in `clear`.  
+	/// Indices present in both slices are cleared.
+	///
+	/// Both slices must be sorted in ascending order, so that they can be merged
+	/// and each affected word is only loaded and stored once.
+	///
+	/// # Safety
+	/// All indices must be in range from 0 to `capacity`.
+	pub unsafe fn set_batch_mixed_unchecked(&mut self, set: &[usize], clear: &[usize]) {
+		debug_assert!(set.is_sorted() && clear.is_sorted(), "Indices are not sorted");
+
+		let (mut set, mut clear) = (set.iter().peekable(), clear.iter().peekable());
+		while let Some(position) = set.peek().into_iter().chain(clear.peek()).map(|i| Self::pos_shift(**i).0).min() {
+			let (mut set_bits, mut clear_bits) = (0, 0);
+			while let Some(i) = set.next_if(|i| Self::pos_shift(**i).0 == position) {
+				set_bits |= FIRST_BIT >> Self::pos_shift(*i).1;
+			}
+			while let Some(i) = clear.next_if(|i| Self::pos_shift(**i).0 == position) {
+				clear_bits |= FIRST_BIT >> Self::pos_shift(*i).1;
+			}
+
+			let bit_value = self.values.get_unchecked_mut(position);
+			*bit_value = (*bit_value | set_bits) & !clear_bits;
+		}
+	}
+
 	/// Copies all bits from another [BitField]
 	pub fn copy_from(&mut self, other: &BitField) {
 		if self.values.len() > other.values.len() {
@@ -157,15 +183,51 @@ impl BitField {
 			self.ensure_capacity(other.capacity());
 		}
 
-		self.values.copy_from_slice(&other.values);
+		self.values[..other.values.len()].copy_from_slice(&other.values);
 	}
 
-	/// Check if the [BitField] is a subset of another [BitField].
+	/// Check if the [BitField] is a subset of another [BitField].  
+	/// An empty [BitField] is a subset of every [BitField].
 	pub fn is_subset_of(&self, other: &BitField) -> bool {
-		if self.values.is_empty() || other.values.is_empty() {
-			return false;
+		let others = other.values.iter().chain(repeat_n(&0, self.values.len()));
+		self.values.iter().zip(others).all(|(mask, bits)| (*bits & *mask) == *mask)
+	}
+
+	/// Check if any bit is set in both `self` and `other`.
+	pub fn intersects(&self, other: &BitField) -> bool {
+		self.values.iter().zip(other.values.iter()).any(|(a, b)| (a & b) != 0)
+	}
+
+	/// Create a new [BitField] containing the bits set in both `self` and `other`.
+	pub fn intersection(&self, other: &BitField) -> BitField {
+		let values = self.values.iter().zip(other.values.iter()).map(|(a, b)| a & b);
+		Self {
+			values: values.collect(),
+		}
+	}
+
+	/// Create a new [BitField] containing the bits set in `self` but not in `other`.
+	pub fn difference(&self, other: &BitField) -> BitField {
+		let others = other.values.iter().chain(repeat_n(&0, self.values.len()));
+		let values = self.values.iter().zip(others).map(|(a, b)| a & !b);
+		Self {
+			values: values.collect(),
 		}
-		self.values.iter().zip(other.values.iter()).any(|(mask, bits)| (*bits & *mask) == *mask)
+	}
+
+	/// Set all bits that are set in `other`.
+	pub fn union_with(&mut self, other: &BitField) {
+		if self.values.len() < other.values.len() {
+			self.values.resize(other.values.len(), 0);
+		}
+		for (a, b) in self.values.iter_mut().zip(other.values.iter()) {
+			*a |= b;
+		}
+	}
+
+	/// Check if no bits are set.
+	pub fn is_empty(&self) -> bool {
+		self.values.iter().all(|v| *v == 0)
 	}
 
 	/// Set all bits to 0.
@@ -182,7 +244,17 @@ impl BitField {
 			}
 			count -= self.values.len();
 
-			self.values.extend(repeat(0).take(count));
+			self.values.extend(repeat_n(0, count));
+		}
+	}
+
+	/// Shrink the capacity of the [BitField] down to `capacity` bits (minimum).
+	/// All bits past the new capacity are discarded.
+	pub fn shrink_to(&mut self, capacity: usize) {
+		let count = capacity.div_ceil(BITS);
+		if count < self.values.len() {
+			self.values.truncate(count);
+			self.values.shrink_to_fit();
 		}
 	}
 
@@ -192,7 +264,7 @@ impl BitField {
 		if new * BITS < count {
 			new += 1;
 		}
-		self.values.extend(repeat(0).take(new));
+		self.values.extend(repeat_n(0, new));
 	}
 
 	/// Get the [BitField]'s capacity in bits.
@@ -201,7 +273,7 @@ impl BitField {
 	}
 
 	/// Iterate over the ranges of set bits.
-	pub fn iter_ranges(&self) -> BitFieldRangeIterator {
+	pub fn iter_ranges(&self) -> BitFieldRangeIterator<'_> {
 		BitFieldRangeIterator::new(&self.values)
 	}
 
@@ -260,7 +332,7 @@ impl Hash for BitField {
 		};
 
 		if let Some(last) = last {
-			for i in &self.values[0..last] {
+			for i in &self.values[0..=last] {
 				i.hash(state);
 			}
 		}
@@ -288,53 +360,41 @@ impl Iterator for BitFieldRangeIterator<'_> {
 	type Item = Range<usize>;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		if self.index >= self.values.len() {
-			return None;
-		}
-
-		while self.values[self.index] == 0 {
-			self.index += 1;
-			self.sub_index = 0;
-
+		let first_bit = loop {
 			if self.index >= self.values.len() {
 				return None;
 			}
-		}
 
-		let value = self.values[self.index];
-		let first_bit = match find_first_bit(value, self.sub_index) {
-			None => return None,
-			Some(bit) => bit,
+			match find_first_bit(self.values[self.index], self.sub_index) {
+				Some(bit) => break bit,
+				None => {
+					self.index += 1;
+					self.sub_index = 0;
+				},
+			}
 		};
 
-		let last_bit = find_last_bit(value, first_bit as u32);
 		let start = self.index * BITS + first_bit;
 
-		return match last_bit {
-			Some(last_bit) => {
-				let end = self.index * BITS + last_bit;
-				self.sub_index = (last_bit + 1) as u32;
-				Some(start..end)
-			},
-			None => {
+		if let Some(last_bit) = find_last_bit(self.values[self.index], first_bit as u32) {
+			self.sub_index = last_bit as u32;
+			return Some(start..self.index * BITS + last_bit);
+		}
+
+		self.index += 1;
+		self.sub_index = 0;
+		while self.index < self.values.len() {
+			let value = self.values[self.index];
+			if value == ALL_BITS_SET {
 				self.index += 1;
-				let mut end = self.index * BITS;
-				while self.index < self.values.len() {
-					let value = self.values[self.index];
-					if value == ALL_BITS_SET {
-						end += BITS;
-						self.index += 1;
-					} else {
-						let last_bit = find_last_bit(value, 0).unwrap();
-						self.sub_index = (last_bit + 1) as u32;
-						end += last_bit;
-						return Some(start..end);
-					}
-				}
+			} else {
+				let last_bit = find_last_bit(value, 0).unwrap();
+				self.sub_index = last_bit as u32;
+				return Some(start..self.index * BITS + last_bit);
+			}
+		}
 
-				Some(start..end)
-			},
-		};
+		return Some(start..self.index * BITS);
 
 		#[inline]
 		fn find_first_bit(value: u32, start: u32) -> Option<usize> {
@@ -4,9 +4,13 @@ mod pool;
 mod any_buffer;
 mod bit_field;
 mod range_allocator;
+mod sparse_set;
+mod scratch_buffer;
 
 pub use pool::*;
 pub use bit_field::*;
 pub use range_allocator::*;
 
 pub(crate) use any_buffer::*;
+pub(crate) use sparse_set::*;
+pub(crate) use scratch_buffer::*;
@@ -1,6 +1,8 @@
 use std::ops::{Deref, DerefMut};
 use std::mem::MaybeUninit;
+use parking_lot::Mutex;
 use std::cell::RefCell;
+use std::sync::Arc;
 use std::rc::Rc;
 
 /// A simple object pool.
@@ -48,3 +50,52 @@ impl<T> Drop for PoolBorrow<T> {
 		}
 	}
 }
+
+/// A thread-safe variant of [Pool], whose objects can be borrowed from multiple threads at once.
+#[derive(Default)]
+pub struct SyncPool<T: Default + Send> {
+	values: Arc<Mutex<Vec<T>>>,
+}
+
+/// A handle to a borrowed object in a [SyncPool]
+pub struct SyncPoolBorrow<T: Send> {
+	value: Option<T>,
+	values: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T: Default + Send> SyncPool<T> {
+	/// Borrow one element from the pool.
+	pub fn take_one(&self) -> SyncPoolBorrow<T> {
+		let value = self.values.lock().pop().unwrap_or_default();
+		SyncPoolBorrow {
+			value: Some(value),
+			values: self.values.clone(),
+		}
+	}
+
+	/// Get the number of elements currently available in the pool.
+	pub fn available(&self) -> usize {
+		self.values.lock().len()
+	}
+}
+
+impl<T: Send> Deref for SyncPoolBorrow<T> {
+	type Target = T;
+	fn deref(&self) -> &Self::Target {
+		self.value.as_ref().unwrap()
+	}
+}
+
+impl<T: Send> DerefMut for SyncPoolBorrow<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.value.as_mut().unwrap()
+	}
+}
+
+impl<T: Send> Drop for SyncPoolBorrow<T> {
+	fn drop(&mut self) {
+		if let Some(value) = self.value.take() {
+			self.values.lock().push(value);
+		}
+	}
+}
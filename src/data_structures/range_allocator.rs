@@ -1,5 +1,7 @@
 use std::collections::btree_map::Values;
+use std::fmt::{Display, Formatter};
 use std::collections::BTreeMap;
+use std::error::Error;
 use std::iter::Cloned;
 
 type Range = std::ops::Range<usize>;
@@ -12,6 +14,14 @@ pub struct RangeAllocator {
 	ranges: BTreeMap<usize, Range>,
 }
 
+/// A snapshot of the state of a [RangeAllocator], created by [RangeAllocator::mark].
+#[derive(Clone)]
+pub struct RangeAllocatorMark {
+	used: usize,
+	capacity: usize,
+	ranges: BTreeMap<usize, Range>,
+}
+
 impl RangeAllocator {
 	/// Create a new [RangeAllocator]
 	pub fn new() -> Self {
@@ -63,6 +73,37 @@ impl RangeAllocator {
 		}
 	}
 
+	/// Conditionally allocate a continuous chunk of size `size` starting at a multiple of `align`.
+	/// The function returns the amount of additional capacity that guarantees a successful allocation
+	/// if there are no suitable chunks to allocate into.
+	///
+	/// The space skipped to align the chunk's start is left free, so aligned allocations fragment
+	/// the allocator more than [RangeAllocator::try_allocate], and may fail even when enough space is available.
+	pub fn try_allocate_aligned(&mut self, size: usize, align: usize) -> Result<Range, usize> {
+		assert_ne!(align, 0, "Alignment must be greater than 0");
+		let aligned = |start: usize| start.div_ceil(align) * align;
+		let find = self.ranges.values().find(|r| aligned(r.start) + size <= r.end);
+
+		match find.cloned() {
+			Some(free) => {
+				let start = aligned(free.start);
+				let used_range = start..start + size;
+
+				self.ranges.remove(&free.start);
+				if free.start < start {
+					self.ranges.insert(free.start, free.start..start);
+				}
+				if used_range.end < free.end {
+					self.ranges.insert(used_range.end, used_range.end..free.end);
+				}
+
+				self.used += size;
+				Ok(used_range)
+			},
+			None => Err(aligned(self.capacity) - self.capacity + size),
+		}
+	}
+
 	/// Allocate multiple chunks adding up to a size of `size`.
 	///
 	/// The resulting chunks will be placed into `ranges`.
@@ -130,13 +171,28 @@ impl RangeAllocator {
 		}
 	}
 
+	/// Return a range to the allocator, checking that it is currently allocated first.
+	/// The allocator is left untouched if the check fails.
+	pub fn try_free(&mut self, range: Range) -> Result<(), RangeFreeError> {
+		self.check_allocated(&range)?;
+		self.free(range);
+		Ok(())
+	}
+
 	/// Return a range to the allocator.
 	/// **Ranges should never be returned twice**.
+	///
+	/// Freeing ranges that are not allocated panics in debug builds,
+	/// and corrupts the allocator in release builds. Use [RangeAllocator::try_free] to always check.
 	//noinspection DuplicatedCode
 	pub fn free(&mut self, range: Range) {
 		if range.is_empty() {
 			return;
 		}
+		#[cfg(debug_assertions)]
+		if let Err(err) = self.check_allocated(&range) {
+			panic!("{err}: {range:?}");
+		}
 		let find_start = self.ranges.get(&range.end);
 		match find_start {
 			None => {},
@@ -183,6 +239,44 @@ impl RangeAllocator {
 		self.ranges.insert(range.start, range);
 	}
 
+	/// Free all allocated chunks at once, leaving a single chunk covering the whole capacity.
+	///
+	/// Much faster than freeing every chunk individually, as its cost only depends on the number of free chunks.
+	pub fn reset(&mut self) {
+		self.used = 0;
+		self.ranges.clear();
+		if self.capacity != 0 {
+			self.ranges.insert(0, 0..self.capacity);
+		}
+	}
+
+	/// Capture the current state of the allocator, so that it can be restored by [RangeAllocator::reset_to].
+	pub fn mark(&self) -> RangeAllocatorMark {
+		RangeAllocatorMark {
+			used: self.used,
+			capacity: self.capacity,
+			ranges: self.ranges.clone(),
+		}
+	}
+
+	/// Restore the allocator to the state captured by `mark`.
+	///
+	/// Chunks allocated since the mark are freed, while chunks freed since the mark are allocated again.
+	/// Capacity reserved since the mark is kept and left free.
+	pub fn reset_to(&mut self, mark: &RangeAllocatorMark) {
+		assert!(mark.capacity <= self.capacity, "Mark does not belong to this allocator");
+
+		self.used = mark.used;
+		self.ranges.clone_from(&mark.ranges);
+		if mark.capacity < self.capacity {
+			let start = match self.ranges.last_key_value() {
+				Some((start, last)) if last.end == mark.capacity => *start,
+				_ => mark.capacity,
+			};
+			self.ranges.insert(start, start..self.capacity);
+		}
+	}
+
 	/// Get the amount of available space left to the allocator.
 	#[inline]
 	pub fn available(&self) -> usize {
@@ -218,16 +312,31 @@ impl RangeAllocator {
 
 	/// Iterate over the unallocated chunks
 	#[inline]
-	pub fn free_ranges(&self) -> Cloned<Values<usize, Range>> {
+	pub fn free_ranges(&self) -> Cloned<Values<'_, usize, Range>> {
 		self.ranges.values().cloned()
 	}
 
-	/// Iterate over the allocated chunks
+	/// Iterate over the allocated chunks.
+	///
+	/// Chunks are always yielded in ascending order and adjacent chunks are merged,
+	/// regardless of the order in which they were allocated or freed.
 	#[inline]
-	pub fn used_ranges(&self) -> UsedRangeIterator {
+	pub fn used_ranges(&self) -> UsedRangeIterator<'_> {
 		UsedRangeIterator::new(self)
 	}
 
+	fn check_allocated(&self, range: &Range) -> Result<(), RangeFreeError> {
+		if range.end > self.capacity || range.start > range.end {
+			return Err(RangeFreeError::OutOfBounds);
+		}
+
+		// Free ranges never overlap, so only the last one starting before the end of `range` can intersect it.
+		match self.ranges.range(..range.end).next_back() {
+			Some((_, free)) if free.end > range.start => Err(RangeFreeError::NotAllocated),
+			_ => Ok(()),
+		}
+	}
+
 	fn allocate_new(&mut self, size: usize) -> Range {
 		let start = self.capacity;
 		self.capacity += size;
@@ -236,7 +345,29 @@ impl RangeAllocator {
 	}
 }
 
-/// Iterates over the allocated chunks of a [RangeAllocator]
+/// The reason a range could not be returned to a [RangeAllocator].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RangeFreeError {
+	/// The range extends past the allocator's capacity.
+	OutOfBounds,
+	/// Part of the range is not currently allocated, e.g. because it has already been freed.
+	NotAllocated,
+}
+
+impl Display for RangeFreeError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::OutOfBounds => write!(f, "Range is out of the allocator's bounds"),
+			Self::NotAllocated => write!(f, "Range is not allocated"),
+		}
+	}
+}
+
+impl Error for RangeFreeError {}
+
+/// Iterates over the allocated chunks of a [RangeAllocator] in ascending order.
+///
+/// The chunks are the gaps between the free ranges, which are kept sorted by their start.
 pub struct UsedRangeIterator<'l> {
 	lst: usize,
 	cap: usize,
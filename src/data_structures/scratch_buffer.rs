@@ -0,0 +1,108 @@
+use std::ops::{Deref, DerefMut};
+use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// An untyped allocation reused by short-lived buffers whose element type changes between uses.
+pub(crate) struct ScratchBuffer {
+	ptr: NonNull<u8>,
+	layout: Layout,
+}
+
+impl Default for ScratchBuffer {
+	fn default() -> Self {
+		Self {
+			ptr: NonNull::dangling(),
+			layout: Layout::new::<()>(),
+		}
+	}
+}
+
+impl ScratchBuffer {
+	/// Borrows the allocation as an empty [ScratchVec] able to hold `capacity` values,
+	/// growing it only if the current one is too small or not aligned for `T`.
+	pub fn as_vec<T>(&mut self, capacity: usize) -> ScratchVec<'_, T> {
+		let layout = Layout::array::<T>(capacity).expect("Scratch buffer is too large");
+		if layout.size() > self.layout.size() || layout.align() > self.layout.align() {
+			let layout = Layout::from_size_align(
+				usize::max(layout.size(), self.layout.size()),
+				usize::max(layout.align(), self.layout.align()),
+			)
+			.unwrap();
+
+			self.release();
+			self.ptr = NonNull::new(unsafe { std::alloc::alloc(layout) })
+				.unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+			self.layout = layout;
+		}
+
+		let capacity = match size_of::<T>() {
+			0 => usize::MAX,
+			size => self.layout.size() / size,
+		};
+
+		ScratchVec {
+			ptr: match size_of::<T>() {
+				0 => NonNull::dangling(),
+				_ => self.ptr.cast(),
+			},
+			len: 0,
+			capacity,
+			phantom: PhantomData,
+		}
+	}
+
+	#[cfg(test)]
+	pub fn as_ptr(&self) -> *const u8 {
+		self.ptr.as_ptr()
+	}
+
+	fn release(&mut self) {
+		if self.layout.size() != 0 {
+			unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+		}
+	}
+}
+
+impl Drop for ScratchBuffer {
+	fn drop(&mut self) {
+		self.release();
+	}
+}
+
+/// A fixed-capacity vector stored in a [ScratchBuffer].
+/// The values it contains are dropped along with it.
+pub(crate) struct ScratchVec<'l, T> {
+	ptr: NonNull<T>,
+	len: usize,
+	capacity: usize,
+	phantom: PhantomData<(&'l mut ScratchBuffer, T)>,
+}
+
+impl<T> ScratchVec<'_, T> {
+	/// Appends a value to the vector, panicking if it is full.
+	pub fn push(&mut self, value: T) {
+		assert!(self.len < self.capacity, "Scratch buffer capacity exceeded");
+		unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+		self.len += 1;
+	}
+}
+
+impl<T> Deref for ScratchVec<'_, T> {
+	type Target = [T];
+	fn deref(&self) -> &Self::Target {
+		unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+	}
+}
+
+impl<T> DerefMut for ScratchVec<'_, T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+	}
+}
+
+impl<T> Drop for ScratchVec<'_, T> {
+	fn drop(&mut self) {
+		unsafe { std::ptr::drop_in_place(self.deref_mut()) };
+	}
+}
@@ -0,0 +1,154 @@
+use crate::components::CloneFn;
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use std::collections::HashMap;
+use std::any::Any;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+
+/// A densely packed container of values indexed by sparse keys.
+pub(crate) struct SparseSet<T> {
+	keys: Vec<usize>,
+	values: Vec<T>,
+	indices: HashMap<usize, usize, Hasher>,
+	clone: Option<CloneFn>,
+}
+
+/// A type erased [SparseSet].
+pub(crate) trait AnySparseSet: Any {
+	fn contains_key(&self, key: usize) -> bool;
+	fn remove_key(&mut self, key: usize);
+	fn can_clone(&self) -> bool;
+	/// Clones the value associated with `src`, if any, and associates it with `dst`.
+	fn clone_key(&mut self, src: usize, dst: usize);
+	/// Moves the value associated with `src`, if any, to `dst`, associating it with `dst_key`.  
+	/// `dst` must be a set of the same type.
+	fn move_key(&mut self, src: usize, dst: &mut dyn AnySparseSet, dst_key: usize);
+	/// Gets a pointer to the value associated with `key`, if any.
+	fn value_ptr(&self, key: usize) -> Option<*const u8>;
+	/// Creates an empty set of the same type.
+	fn new_empty(&self) -> Box<dyn AnySparseSet>;
+	fn as_any(&self) -> &dyn Any;
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+#[allow(dead_code)]
+impl<T> SparseSet<T> {
+	pub fn new() -> Self {
+		Self {
+			keys: vec![],
+			values: vec![],
+			indices: HashMap::default(),
+			clone: None,
+		}
+	}
+
+	pub fn with_clone_fn(clone: Option<CloneFn>) -> Self {
+		Self { clone, ..Self::new() }
+	}
+
+	/// Insert a value, returning the previous value associated with `key`.
+	pub fn insert(&mut self, key: usize, value: T) -> Option<T> {
+		match self.indices.get(&key) {
+			Some(index) => Some(std::mem::replace(&mut self.values[*index], value)),
+			None => {
+				self.indices.insert(key, self.values.len());
+				self.keys.push(key);
+				self.values.push(value);
+				None
+			},
+		}
+	}
+
+	pub fn remove(&mut self, key: usize) -> Option<T> {
+		let index = self.indices.remove(&key)?;
+		self.keys.swap_remove(index);
+		if let Some(moved) = self.keys.get(index) {
+			self.indices.insert(*moved, index);
+		}
+		Some(self.values.swap_remove(index))
+	}
+
+	pub fn get(&self, key: usize) -> Option<&T> {
+		let index = self.indices.get(&key)?;
+		Some(&self.values[*index])
+	}
+
+	pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+		let index = self.indices.get(&key)?;
+		Some(&mut self.values[*index])
+	}
+
+	pub fn index_of(&self, key: usize) -> Option<usize> {
+		self.indices.get(&key).copied()
+	}
+
+	pub fn as_ptr(&self) -> *const T {
+		self.values.as_ptr()
+	}
+
+	pub fn as_mut_ptr(&mut self) -> *mut T {
+		self.values.as_mut_ptr()
+	}
+
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+}
+
+impl<T: 'static> AnySparseSet for SparseSet<T> {
+	fn contains_key(&self, key: usize) -> bool {
+		self.indices.contains_key(&key)
+	}
+
+	fn remove_key(&mut self, key: usize) {
+		self.remove(key);
+	}
+
+	fn can_clone(&self) -> bool {
+		self.clone.is_some()
+	}
+
+	fn clone_key(&mut self, src: usize, dst: usize) {
+		let Some(index) = self.index_of(src) else {
+			return;
+		};
+		let clone = self.clone.expect("Sparse set does not have a clone function for T");
+		let mut value = std::mem::MaybeUninit::<T>::uninit();
+		unsafe {
+			clone(
+				self.values.as_ptr().add(index) as *const u8,
+				value.as_mut_ptr() as *mut u8,
+				1,
+			);
+			self.insert(dst, value.assume_init());
+		}
+	}
+
+	fn move_key(&mut self, src: usize, dst: &mut dyn AnySparseSet, dst_key: usize) {
+		if let Some(value) = self.remove(src) {
+			let dst: &mut Self = dst.as_any_mut().downcast_mut().expect("Sparse sets are of different types");
+			dst.insert(dst_key, value);
+		}
+	}
+
+	fn value_ptr(&self, key: usize) -> Option<*const u8> {
+		self.get(key).map(|v| v as *const T as *const u8)
+	}
+
+	fn new_empty(&self) -> Box<dyn AnySparseSet> {
+		Box::new(Self::with_clone_fn(self.clone))
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
@@ -0,0 +1,25 @@
+use std::fmt::{Display, Formatter};
+use std::error::Error;
+
+/// The reason a [component](crate::components::Component) of an [entity](crate::entities::Entity) could not be accessed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ComponentAccessError {
+	/// The [entity](crate::entities::Entity) does not have the requested [component](crate::components::Component).
+	MissingComponent,
+	/// The [entity](crate::entities::Entity) has already been destroyed.
+	DeadEntity,
+	/// The [entity](crate::entities::Entity) does not belong to the registry it was used with.
+	WrongContext,
+}
+
+impl Display for ComponentAccessError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingComponent => write!(f, "Entity does not contain the requested component"),
+			Self::DeadEntity => write!(f, "Entity has already been destroyed"),
+			Self::WrongContext => write!(f, "Entity does not belong to this context"),
+		}
+	}
+}
+
+impl Error for ComponentAccessError {}
@@ -0,0 +1,75 @@
+use crate::components::ComponentId;
+use crate::data_structures::BitField;
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use std::collections::HashMap;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+
+/// The tick at which each tracked [component](crate::components::Component) was added to each
+/// [entity](crate::entities::Entity), indexed by [component id](ComponentId) and entity instance.
+#[derive(Default)]
+pub(crate) struct AddedTicks {
+	ticks: HashMap<usize, HashMap<usize, u64, Hasher>, Hasher>,
+}
+
+impl AddedTicks {
+	pub fn is_empty(&self) -> bool {
+		self.ticks.is_empty()
+	}
+
+	pub fn track(&mut self, id: ComponentId) {
+		self.ticks.entry(id.value()).or_default();
+	}
+
+	pub fn is_tracked(&self, id: ComponentId) -> bool {
+		self.ticks.contains_key(&id.value())
+	}
+
+	pub fn get(&self, id: ComponentId, instance: usize) -> Option<u64> {
+		self.ticks.get(&id.value())?.get(&instance).copied()
+	}
+
+	/// Records the addition of every tracked [component](crate::components::Component) for which `contains` returns `true`.
+	pub fn record_created(&mut self, instance: usize, tick: u64, contains: impl Fn(usize) -> bool) {
+		for (id, ticks) in self.ticks.iter_mut() {
+			if contains(*id) {
+				ticks.insert(instance, tick);
+			}
+		}
+	}
+
+	pub fn record_entered(&mut self, id: ComponentId, instance: usize, tick: u64) {
+		if let Some(ticks) = self.ticks.get_mut(&id.value()) {
+			ticks.insert(instance, tick);
+		}
+	}
+
+	pub fn record_exited(&mut self, id: ComponentId, instance: usize) {
+		if let Some(ticks) = self.ticks.get_mut(&id.value()) {
+			ticks.remove(&instance);
+		}
+	}
+
+	/// Records the changes of an [entity](crate::entities::Entity) moving between archetypes with the specified signatures.
+	pub fn record_transition(&mut self, instance: usize, tick: u64, src: &BitField, dst: &BitField) {
+		for (id, ticks) in self.ticks.iter_mut() {
+			match (src.get(*id), dst.get(*id)) {
+				(false, true) => {
+					ticks.insert(instance, tick);
+				},
+				(true, false) => {
+					ticks.remove(&instance);
+				},
+				_ => {},
+			}
+		}
+	}
+
+	/// Drops the ticks of the destroyed [entity](crate::entities::Entity) whose instance is `instance`.
+	pub fn remove(&mut self, instance: usize) {
+		for ticks in self.ticks.values_mut() {
+			ticks.remove(&instance);
+		}
+	}
+}
@@ -0,0 +1,85 @@
+use crate::archetypes::{ArchetypeFetchAny, ArchetypeInstance, SparseFilter};
+use crate::components::ComponentSet;
+use crate::entities::{Entity, EntityQuery, EntityRegistry};
+use crate::data_structures::BitField;
+use std::marker::PhantomData;
+
+/// An [EntityFilter](crate::entities::EntityFilter) matching the [entities](Entity) that include
+/// at least one of the [components](crate::components::Component) in `A`, created by
+/// [EntityFilter::include_any](crate::entities::EntityFilter::include_any).
+///
+/// Since a matched [entity](Entity) may lack some of them, each [component](crate::components::Component)
+/// of `A` is passed to the iteration functions as an [Option].
+pub struct AnyFilter<'l, A: 'static + ComponentSet> {
+	entity_store: &'l mut EntityRegistry,
+	query: EntityQuery,
+	any: BitField,
+	limit: usize,
+	phantom: PhantomData<&'l A>,
+}
+
+impl<'l, A: 'static + ComponentSet> AnyFilter<'l, A>
+where
+	ArchetypeInstance: ArchetypeFetchAny<'l, A>,
+{
+	pub(crate) fn new(entity_store: &'l mut EntityRegistry, query: EntityQuery, limit: usize) -> Self {
+		let (any, has_repeats) = A::get_bitfield();
+		if has_repeats {
+			panic!("An entity query cannot include a type multiple times")
+		}
+
+		Self {
+			entity_store,
+			query,
+			any: any.as_ref().clone(),
+			limit,
+			phantom: PhantomData,
+		}
+	}
+
+	/// Iterate all matching entities with the provided function.
+	pub fn for_each(self, mut func: impl FnMut(<ArchetypeInstance as ArchetypeFetchAny<'l, A>>::Item)) {
+		self.entities_for_each(|_, args| func(args));
+	}
+
+	/// Iterate all matching entities with the provided function, passing their [Entity] alongside their components.
+	pub fn entities_for_each(
+		self, mut func: impl FnMut(Entity, <ArchetypeInstance as ArchetypeFetchAny<'l, A>>::Item),
+	) {
+		let store = self.entity_store;
+		let sparse = crate::components::component_id::sparse_ids();
+		let table_any = self.any.difference(&sparse);
+		let sparse_any = self.any.intersects(&sparse);
+
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(self.query));
+		let mut remaining = self.limit;
+
+		for archetype in store.archetype_store.query_populated(self.query) {
+			// Without sparse components, archetypes lacking all table components of `A` cannot match
+			if !sparse_any && !archetype.component_bitfield().intersects(&table_any) {
+				continue;
+			}
+
+			unsafe {
+				let columns = archetype.columns_any(&mut store.sparse_storage);
+				let entities = archetype.entities_mut().as_ptr();
+				for range in archetype.used_ranges() {
+					for slot in range {
+						if !filter.is_empty() && !filter.matches(entities, slot) {
+							continue;
+						}
+						let Some(args) = ArchetypeInstance::fetch_any(&columns, entities, slot) else {
+							continue;
+						};
+						if remaining == 0 {
+							return;
+						}
+
+						remaining -= 1;
+						func((*entities.add(slot)).clone(), args);
+					}
+				}
+			}
+		}
+	}
+}
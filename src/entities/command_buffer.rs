@@ -0,0 +1,70 @@
+use crate::entities::{Entity, EntityRegistry};
+use crate::archetypes::Archetype;
+use crate::components::Component;
+
+type Command = Box<dyn FnOnce(&mut EntityRegistry)>;
+
+/// A queue of deferred structural changes to an [EntityRegistry].
+///
+/// Commands are executed in the order they were recorded when the buffer is [applied](CommandBuffer::apply).
+#[derive(Default)]
+pub struct CommandBuffer {
+	commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+	/// Create a new, empty [CommandBuffer].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record an arbitrary command.
+	pub fn push(&mut self, command: impl FnOnce(&mut EntityRegistry) + 'static) {
+		self.commands.push(Box::new(command));
+	}
+
+	/// Record the creation of an [entity](Entity) belonging to the specified [archetype](Archetype).
+	/// `init` is invoked with the new [entity](Entity) once it has been created.
+	pub fn spawn(&mut self, archetype: Archetype, init: impl FnOnce(&mut EntityRegistry, Entity) + 'static) {
+		self.push(move |registry| {
+			let entity = registry.create_entity_from_archetype(archetype);
+			init(registry, entity);
+		});
+	}
+
+	/// Record the destruction of an [entity](Entity).
+	pub fn destroy(&mut self, entity: Entity) {
+		self.push(move |registry| registry.destroy_entities(&[entity]));
+	}
+
+	/// Record the addition of a [component](Component) to an [entity](Entity).
+	pub fn add_component<T: Component>(&mut self, entity: Entity, value: T) {
+		self.push(move |registry| {
+			registry.add_component(&entity, value);
+		});
+	}
+
+	/// Record the removal of a [component](Component) from an [entity](Entity).
+	pub fn remove_component<T: Component>(&mut self, entity: Entity) {
+		self.push(move |registry| {
+			registry.remove_component::<T>(&entity);
+		});
+	}
+
+	/// Gets the number of recorded commands.
+	pub fn len(&self) -> usize {
+		self.commands.len()
+	}
+
+	/// Checks if no commands have been recorded.
+	pub fn is_empty(&self) -> bool {
+		self.commands.is_empty()
+	}
+
+	/// Execute and remove all recorded commands.
+	pub fn apply(&mut self, registry: &mut EntityRegistry) {
+		for command in self.commands.drain(..) {
+			command(registry);
+		}
+	}
+}
@@ -0,0 +1,66 @@
+use crate::entities::{Entity, EntityRegistry};
+use crate::components::ComponentId;
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use std::collections::HashMap;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+type Callback = Box<dyn FnMut(&EntityRegistry, &Entity)>;
+
+struct Watch {
+	entity: Entity,
+	component: ComponentId,
+	changed: bool,
+	callback: Callback,
+}
+
+/// Callbacks watching the [components](crate::components::Component) of specific [entities](Entity),
+/// indexed by their instance.
+#[derive(Default)]
+pub(crate) struct ComponentWatches {
+	changed: bool,
+	watches: HashMap<usize, Vec<Watch>, Hasher>,
+}
+
+impl ComponentWatches {
+	pub fn is_empty(&self) -> bool {
+		self.watches.is_empty()
+	}
+
+	pub fn watch(&mut self, entity: &Entity, component: ComponentId, callback: Callback) {
+		self.watches.entry(entity.instance as usize).or_default().push(Watch {
+			entity: entity.clone(),
+			component,
+			changed: false,
+			callback,
+		});
+	}
+
+	/// Flags the watches on the `component` of `entity` as changed.
+	pub fn mark(&mut self, entity: &Entity, component: ComponentId) {
+		if let Some(watches) = self.watches.get_mut(&(entity.instance as usize)) {
+			for watch in watches.iter_mut().filter(|w| w.component == component) {
+				watch.changed = true;
+				self.changed = true;
+			}
+		}
+	}
+
+	/// Drops the watches on the [entity](Entity) whose instance is `instance`.
+	pub fn remove(&mut self, instance: usize) {
+		self.watches.remove(&instance);
+	}
+
+	/// Invokes the callbacks of the watches flagged as changed, clearing the flags.
+	pub fn notify(&mut self, registry: &EntityRegistry) {
+		if !self.changed {
+			return;
+		}
+
+		self.changed = false;
+		for watch in self.watches.values_mut().flatten().filter(|w| w.changed) {
+			watch.changed = false;
+			(watch.callback)(registry, &watch.entity);
+		}
+	}
+}
@@ -0,0 +1,24 @@
+use crate::components::{Component, ComponentId};
+use crate::data_structures::BitField;
+
+/// A tag [component](Component) deactivating an [entity](crate::entities::Entity) without destroying it.
+///
+/// Queries implicitly skip disabled [entities](crate::entities::Entity),
+/// unless they explicitly include [Disabled] or opt in through `include_disabled`.  
+/// Disabling and re-enabling an [entity](crate::entities::Entity) moves it between
+/// [archetypes](crate::archetypes::Archetype), like any other structural change.
+#[derive(Default, Copy, Clone, Debug, Component)]
+pub struct Disabled;
+
+/// Adds [Disabled] to the `exclude` set of a query, unless the query already mentions it.
+pub(crate) fn exclude_disabled(include: &BitField, exclude: &mut BitField) {
+	let id = ComponentId::of::<Disabled>().value();
+	if !include.get(id) {
+		exclude.set(id, true);
+	}
+}
+
+/// Removes the implicit exclusion of [Disabled] from the `exclude` set of a query.
+pub(crate) fn include_disabled(exclude: &mut BitField) {
+	exclude.set(ComponentId::of::<Disabled>().value(), false);
+}
@@ -1,4 +1,10 @@
+use crate::entities::EntityRegistry;
+
 /// A unique handle to an `Entity`
+///
+/// Handles may safely outlive the [EntityRegistry](crate::entities::EntityRegistry) that created them:
+/// registry ids are never reused, so using a handle with any other registry
+/// is detected before its instance is accessed.
 #[derive(Clone, Debug)]
 pub struct Entity {
 	pub(crate) version: u32,
@@ -6,6 +12,15 @@ pub struct Entity {
 	pub(crate) instance: *mut EntityInstance,
 }
 
+/// A handle to an [Entity] that might have been destroyed.
+///
+/// Unlike an [Entity], it can be safely kept in long-lived data structures,
+/// and must be [upgraded](WeakEntity::upgrade) to access the [Entity] it refers to.
+#[derive(Clone, Debug, Default)]
+pub struct WeakEntity {
+	entity: Entity,
+}
+
 pub(crate) struct EntityInstance {
 	pub(crate) slot: usize,
 	pub(crate) version: u32,
@@ -14,11 +29,7 @@ pub(crate) struct EntityInstance {
 
 impl Default for Entity {
 	fn default() -> Self {
-		Self {
-			version: 0,
-			registry_id: 0,
-			instance: std::ptr::null_mut(),
-		}
+		Self::null()
 	}
 }
 
@@ -33,6 +44,47 @@ impl Default for EntityInstance {
 }
 
 impl Entity {
+	/// Creates a null [Entity], which does not belong to any [EntityRegistry](crate::entities::EntityRegistry).  
+	/// It can be used as a placeholder, but it must not be passed to registry functions.
+	pub const fn null() -> Self {
+		Self {
+			version: 0,
+			registry_id: 0,
+			instance: std::ptr::null_mut(),
+		}
+	}
+
+	/// Checks if the [Entity] is [null](Entity::null).
+	/// Only placeholders are null, destroyed [entities](Entity) are not.
+	pub fn is_null(&self) -> bool {
+		self.instance.is_null()
+	}
+
+	/// Creates a [WeakEntity] referring to the [Entity].
+	pub fn downgrade(&self) -> WeakEntity {
+		WeakEntity { entity: self.clone() }
+	}
+
+	/// Packs the [Entity] into a single integer holding the index of its instance and its version,
+	/// which can be stored in plain data or sent across FFI and network boundaries.  
+	/// [Null](Entity::null) entities are packed as 0.
+	///
+	/// The bits are only meaningful within `registry`, and can be turned back into an [Entity] with [Entity::from_bits].  
+	/// This function will panic if the [Entity] belongs to another registry,
+	/// or if its instance has been freed by [shrink_to_fit](EntityRegistry::shrink_to_fit).
+	pub fn to_bits(&self, registry: &EntityRegistry) -> u64 {
+		registry.entity_to_bits(self)
+	}
+
+	/// Unpacks an [Entity] packed by [Entity::to_bits] with the same `registry`.  
+	/// Returns a [null](Entity::null) [Entity] if `bits` is 0 or refers to an instance that is no longer allocated.
+	///
+	/// The returned [Entity] might have been destroyed since it was packed,
+	/// which can be checked through [EntityRegistry::validate_entities].
+	pub fn from_bits(bits: u64, registry: &EntityRegistry) -> Entity {
+		registry.entity_from_bits(bits)
+	}
+
 	#[inline(always)]
 	pub(crate) fn get_instance(&self, context_id: u32) -> &EntityInstance {
 		assert_entity(self, context_id);
@@ -46,6 +98,14 @@ impl Entity {
 	}
 }
 
+impl WeakEntity {
+	/// Gets the [Entity] the [WeakEntity] refers to,
+	/// or *None* if it has been destroyed or does not belong to `registry`.
+	pub fn upgrade(&self, registry: &EntityRegistry) -> Option<Entity> {
+		registry.is_alive_weak(&self.entity).then(|| self.entity.clone())
+	}
+}
+
 #[inline(always)]
 pub(crate) fn assert_entity(entity: &Entity, context_id: u32) {
 	// SAFETY:
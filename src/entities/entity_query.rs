@@ -1,4 +1,4 @@
-use crate::components::{Component, ComponentSet};
+use crate::components::{Component, ComponentId, ComponentSet};
 use crate::data_structures::BitField;
 use std::hash::BuildHasherDefault;
 use nohash_hasher::NoHashHasher;
@@ -16,6 +16,7 @@ lazy_static! {
 	static ref QUERY_TO_DATA: RwLock<Vec<EntityQueryData>> = RwLock::new(Vec::default());
 	static ref PTR_TO_QUERY: RwLock<HashMap<(usize, usize), EntityQuery>> = RwLock::new(HashMap::default());
 	static ref TYPE_TO_QUERY: RwLock<HashMap<TypeId, EntityQuery, Hasher>> = RwLock::new(HashMap::default());
+	static ref BITS_TO_QUERY: RwLock<HashMap<(BitField, BitField), EntityQuery>> = RwLock::new(HashMap::default());
 }
 
 /// A handle to [BitField] based entity filter.
@@ -32,8 +33,12 @@ impl EntityQuery {
 }
 
 /// A utility structure to build [EntityQueries](EntityQuery).
+///
+/// Queries skip [disabled](crate::entities::Disabled) [entities](crate::entities::Entity),
+/// unless they include [Disabled](crate::entities::Disabled) or opt in through [QueryBuilder::include_disabled].
 #[derive(Default)]
 pub struct QueryBuilder<I: 'static + ComponentSet = (), E: 'static + ComponentSet = ()> {
+	include_disabled: bool,
 	i_phantom: PhantomData<&'static I>,
 	e_phantom: PhantomData<&'static E>,
 }
@@ -42,22 +47,151 @@ impl<I: 'static + ComponentSet, E: 'static + ComponentSet> QueryBuilder<I, E> {
 	/// Specify which types to include in the query.
 	pub fn include<TI: 'static + ComponentSet>(self) -> QueryBuilder<TI, E> {
 		QueryBuilder {
-			i_phantom: PhantomData::default(),
-			e_phantom: PhantomData::default(),
+			include_disabled: self.include_disabled,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
 		}
 	}
 
 	/// Specify which types to exclude from the query.
 	pub fn exclude<TE: 'static + ComponentSet>(self) -> QueryBuilder<I, TE> {
 		QueryBuilder {
-			i_phantom: PhantomData::default(),
-			e_phantom: PhantomData::default(),
+			include_disabled: self.include_disabled,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
 		}
 	}
 
+	/// Make the query match [disabled](crate::entities::Disabled) [entities](crate::entities::Entity) too.
+	pub fn include_disabled(mut self) -> Self {
+		self.include_disabled = true;
+		self
+	}
+
 	/// Construct a query from the previously specified types.
 	pub fn create(self) -> EntityQuery {
-		<(I, E)>::get_query()
+		let query = <(I, E)>::get_query();
+		match self.include_disabled {
+			true => with_disabled(query),
+			false => query,
+		}
+	}
+
+	/// Gets the [components](Component) read and written by the included types.
+	pub fn access(&self) -> QueryAccess {
+		QueryAccess::of::<I>()
+	}
+}
+
+/// The [components](Component) read and written by the arguments of a query.
+///
+/// [Queries](EntityQuery) only depend on which [components](Component) are included and excluded,
+/// so queries differing only by the mutability of their arguments share the same [EntityQuery].
+#[derive(Default, Clone)]
+pub struct QueryAccess {
+	reads: BitField,
+	writes: BitField,
+}
+
+impl QueryAccess {
+	/// Gets the access of a query whose arguments are `I`.
+	pub fn of<I: 'static + ComponentSet>() -> Self {
+		Self {
+			reads: I::get_bitfield().0.deref().clone(),
+			writes: I::get_written_bitfield().deref().clone(),
+		}
+	}
+
+	/// Gets the access of a query whose arguments are `I`, excluding the [components](Component) in `E`.
+	///
+	/// Excluded [sparse](crate::components::ComponentStorage::Sparse) [components](Component) are also read,
+	/// since their sets are looked up to filter out [entities](crate::entities::Entity).
+	pub(crate) fn of_filter<I: 'static + ComponentSet, E: 'static + ComponentSet>() -> Self {
+		let mut access = Self::of::<I>();
+		let sparse = crate::components::component_id::sparse_ids();
+		access.reads.union_with(&E::get_bitfield().0.intersection(&sparse));
+		access
+	}
+
+	/// Gets the [BitField] of the [component ids](ComponentId) accessed by the query, including written ones.
+	pub fn reads(&self) -> &BitField {
+		&self.reads
+	}
+
+	/// Gets the [BitField] of the [component ids](ComponentId) accessed mutably by the query.
+	pub fn writes(&self) -> &BitField {
+		&self.writes
+	}
+
+	/// Gets the names of the [components](Component) accessed mutably by the query.
+	pub fn written_names(&self) -> Vec<&'static str> {
+		crate::entities::component_names(&self.writes)
+	}
+
+	/// Checks if two queries cannot be iterated at the same time,
+	/// because one of them writes a [component](Component) accessed by the other.
+	pub fn conflicts_with(&self, other: &QueryAccess) -> bool {
+		!self.writes.intersection(&other.reads).is_empty() || !self.reads.intersection(&other.writes).is_empty()
+	}
+
+	/// Adds the [components](Component) accessed by `other` to the ones accessed by `self`.
+	pub fn merge(&mut self, other: &QueryAccess) {
+		self.reads.union_with(&other.reads);
+		self.writes.union_with(&other.writes);
+	}
+}
+
+/// A utility structure to build [EntityQueries](EntityQuery) from runtime [component ids](ComponentId).
+///
+/// A dynamic query resolves to the same [EntityQuery] as the typed query over the same [components](Component).
+#[derive(Default, Clone)]
+pub struct DynQueryBuilder {
+	include: BitField,
+	exclude: BitField,
+	include_disabled: bool,
+}
+
+impl DynQueryBuilder {
+	/// Create a new [DynQueryBuilder]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Specify a [component](Component) to include in the query.
+	pub fn include_id(mut self, id: ComponentId) -> Self {
+		self.include.set(id.value(), true);
+		self
+	}
+
+	/// Specify a [component](Component) to exclude from the query.
+	pub fn exclude_id(mut self, id: ComponentId) -> Self {
+		self.exclude.set(id.value(), true);
+		self
+	}
+
+	/// Specify several [components](Component) to include in the query.  
+	/// Unlike typed queries, the number of [components](Component) is not limited.
+	pub fn include_ids(self, ids: impl IntoIterator<Item = ComponentId>) -> Self {
+		ids.into_iter().fold(self, Self::include_id)
+	}
+
+	/// Specify several [components](Component) to exclude from the query.
+	pub fn exclude_ids(self, ids: impl IntoIterator<Item = ComponentId>) -> Self {
+		ids.into_iter().fold(self, Self::exclude_id)
+	}
+
+	/// Make the query match [disabled](crate::entities::Disabled) [entities](crate::entities::Entity) too.
+	pub fn include_disabled(mut self) -> Self {
+		self.include_disabled = true;
+		self
+	}
+
+	/// Construct a query from the previously specified [component ids](ComponentId).
+	pub fn build(mut self) -> EntityQuery {
+		if !self.include_disabled {
+			crate::entities::disabled::exclude_disabled(&self.include, &mut self.exclude);
+		}
+		get_query_from_bitfields(self.include, self.exclude)
 	}
 }
 
@@ -101,15 +235,55 @@ impl<I: 'static + ComponentSet, E: 'static + ComponentSet> ComponentQuery for (I
 pub(crate) struct EntityQueryData {
 	include: Arc<BitField>,
 	exclude: Arc<BitField>,
+	table_include: Arc<BitField>,
+	table_exclude: Arc<BitField>,
+	sparse_include: Arc<BitField>,
+	sparse_exclude: Arc<BitField>,
 }
 
 impl EntityQueryData {
+	/// Splits the query's [components](Component) by [storage](crate::components::ComponentStorage).
+	fn new(include: Arc<BitField>, exclude: Arc<BitField>) -> Self {
+		let sparse = crate::components::component_id::sparse_ids();
+		let split = |set: &Arc<BitField>| {
+			let sparse = set.intersection(&sparse);
+			match sparse.is_empty() {
+				true => (set.clone(), Arc::new(sparse)),
+				false => (Arc::new(set.difference(&sparse)), Arc::new(sparse)),
+			}
+		};
+
+		let (table_include, sparse_include) = split(&include);
+		let (table_exclude, sparse_exclude) = split(&exclude);
+
+		Self {
+			include,
+			exclude,
+			table_include,
+			table_exclude,
+			sparse_include,
+			sparse_exclude,
+		}
+	}
+
 	pub fn include(&self) -> &BitField {
 		&self.include
 	}
 	pub fn exclude(&self) -> &BitField {
 		&self.exclude
 	}
+	pub fn table_include(&self) -> &BitField {
+		&self.table_include
+	}
+	pub fn table_exclude(&self) -> &BitField {
+		&self.table_exclude
+	}
+	pub fn sparse_include(&self) -> &BitField {
+		&self.sparse_include
+	}
+	pub fn sparse_exclude(&self) -> &BitField {
+		&self.sparse_exclude
+	}
 }
 
 pub(crate) fn get_query_data(query: EntityQuery) -> EntityQueryData {
@@ -117,6 +291,38 @@ pub(crate) fn get_query_data(query: EntityQuery) -> EntityQueryData {
 	vec[query.index].clone()
 }
 
+/// Gets the [EntityQuery] matching the same [entities](crate::entities::Entity) as `query`,
+/// plus the [disabled](crate::entities::Disabled) ones.
+pub(crate) fn with_disabled(query: EntityQuery) -> EntityQuery {
+	let data = get_query_data(query);
+	let mut exclude = data.exclude().clone();
+	crate::entities::disabled::include_disabled(&mut exclude);
+	get_query_from_bitfields(data.include().clone(), exclude)
+}
+
+/// Gets the [EntityQuery] matching the specified sets of [component ids](crate::components::ComponentId),
+/// creating it if necessary.
+#[inline(never)]
+pub(crate) fn get_query_from_bitfields(include: BitField, exclude: BitField) -> EntityQuery {
+	let key = (include, exclude);
+	if let Some(query) = BITS_TO_QUERY.read().get(&key) {
+		return *query;
+	}
+
+	let mut btq = BITS_TO_QUERY.write();
+	if let Some(query) = btq.get(&key) {
+		return *query;
+	}
+
+	let data = EntityQueryData::new(Arc::new(key.0.clone()), Arc::new(key.1.clone()));
+	let mut qtd = QUERY_TO_DATA.write();
+	let query = EntityQuery { index: qtd.len() };
+
+	qtd.push(data);
+	btq.insert(key, query);
+	query
+}
+
 #[inline(never)]
 fn create_query<I: 'static + ComponentSet, E: 'static + ComponentSet>(key: TypeId) -> EntityQuery {
 	let mut ttq = TYPE_TO_QUERY.write();
@@ -128,23 +334,35 @@ fn create_query<I: 'static + ComponentSet, E: 'static + ComponentSet>(key: TypeI
 		panic!("An entity query cannot include a type multiple times")
 	}
 
-	let data = EntityQueryData { include, exclude };
-
 	let ptr = (
-		data.include.deref() as *const BitField as usize,
-		data.exclude.deref() as *const BitField as usize,
+		include.deref() as *const BitField as usize,
+		exclude.deref() as *const BitField as usize,
 	);
 
+	let mut exclude = exclude.deref().clone();
+	crate::entities::disabled::exclude_disabled(&include, &mut exclude);
+	let data = EntityQueryData::new(include, Arc::new(exclude));
+
 	let mut ptq = PTR_TO_QUERY.write();
 	if let Some(query) = ptq.get(&ptr) {
 		return *query;
 	}
 
+	// An identical query might have been created from runtime component ids
+	let bits = (data.include.deref().clone(), data.exclude.deref().clone());
+	let mut btq = BITS_TO_QUERY.write();
+	if let Some(query) = btq.get(&bits) {
+		ptq.insert(ptr, *query);
+		ttq.insert(key, *query);
+		return *query;
+	}
+
 	let mut qtd = QUERY_TO_DATA.write();
 	let query = EntityQuery { index: qtd.len() };
 
 	qtd.push(data);
 	ptq.insert(ptr, query);
+	btq.insert(bits, query);
 	ttq.insert(key, query);
 	query
 }
@@ -0,0 +1,113 @@
+use crate::entities::{Entity, EntityRegistry};
+use crate::archetypes::Archetype;
+use crate::components::{Component, MutableComponent};
+
+/// A read-only handle to a single [entity](Entity) and its [components](Component).
+pub struct EntityRef<'l> {
+	registry: &'l EntityRegistry,
+	entity: Entity,
+}
+
+/// A handle to a single [entity](Entity), allowing its [components](Component) to be mutated, added and removed.
+///
+/// The handle mutably borrows the [EntityRegistry] for its whole lifetime.
+pub struct EntityMut<'l> {
+	registry: &'l mut EntityRegistry,
+	entity: Entity,
+}
+
+impl EntityRegistry {
+	/// Gets a read-only handle to the specified [entity](Entity).
+	pub fn entity(&self, entity: &Entity) -> EntityRef<'_> {
+		// Fails early on destroyed entities or ones belonging to another registry.
+		self.archetype_of(entity);
+		EntityRef {
+			registry: self,
+			entity: entity.clone(),
+		}
+	}
+
+	/// Gets a mutable handle to the specified [entity](Entity).
+	pub fn entity_mut(&mut self, entity: &Entity) -> EntityMut<'_> {
+		self.archetype_of(entity);
+		EntityMut {
+			registry: self,
+			entity: entity.clone(),
+		}
+	}
+
+	/// Creates `count` [entities](Entity) belonging to the specified [archetype](Archetype),
+	/// calling `init` with the index and a [mutable handle](EntityMut) of each of them.
+	pub fn spawn_initialized<F>(&mut self, archetype: Archetype, count: usize, mut init: F) -> Vec<Entity>
+	where
+		F: FnMut(usize, EntityMut),
+	{
+		let entities: Vec<_> = self.create_entities_from_archetype(archetype, count).collect();
+		for (i, entity) in entities.iter().enumerate() {
+			init(i, self.entity_mut(entity));
+		}
+		entities
+	}
+}
+
+impl<'l> EntityRef<'l> {
+	/// Gets the [entity](Entity) the handle refers to.
+	pub fn id(&self) -> &Entity {
+		&self.entity
+	}
+
+	/// Gets the [archetype](Archetype) the [entity](Entity) belongs to.
+	pub fn archetype(&self) -> Archetype {
+		self.registry.archetype_of(&self.entity)
+	}
+
+	/// Checks if the [entity](Entity) has a [component](Component) of type `T`.
+	pub fn contains<T: Component>(&self) -> bool {
+		self.registry.get_component::<T>(&self.entity).is_some()
+	}
+
+	/// Gets a reference to the [entity](Entity)'s [component](Component) of type `T`.
+	pub fn get<T: Component>(&self) -> Option<&'l T> {
+		self.registry.get_component(&self.entity)
+	}
+}
+
+impl EntityMut<'_> {
+	/// Gets the [entity](Entity) the handle refers to.
+	pub fn id(&self) -> &Entity {
+		&self.entity
+	}
+
+	/// Gets the [archetype](Archetype) the [entity](Entity) belongs to.
+	pub fn archetype(&self) -> Archetype {
+		self.registry.archetype_of(&self.entity)
+	}
+
+	/// Checks if the [entity](Entity) has a [component](Component) of type `T`.
+	pub fn contains<T: Component>(&self) -> bool {
+		self.registry.get_component::<T>(&self.entity).is_some()
+	}
+
+	/// Gets a reference to the [entity](Entity)'s [component](Component) of type `T`.
+	pub fn get<T: Component>(&self) -> Option<&T> {
+		self.registry.get_component(&self.entity)
+	}
+
+	/// Gets a mutable reference to the [entity](Entity)'s [component](Component) of type `T`.
+	pub fn get_mut<T: MutableComponent>(&mut self) -> Option<&mut T> {
+		self.registry.get_component_mut(&self.entity)
+	}
+
+	/// Adds a [component](Component) to the [entity](Entity).
+	/// The value is discarded if a [component](Component) of the same type is already present.
+	pub fn insert<T: Component>(&mut self, value: T) -> &mut Self {
+		self.registry.add_component(&self.entity, value);
+		self
+	}
+
+	/// Removes the [component](Component) of type `T` from the [entity](Entity), if present.
+	pub fn remove<T: Component>(&mut self) -> &mut Self {
+		self.registry.remove_component::<T>(&self.entity);
+		self
+	}
+}
@@ -1,45 +1,102 @@
 use crate::archetypes::{
-	Archetype, ArchetypeInstance, ArchetypeStore, ArchetypeTransition, ArchetypeTransitionKind, IterArchetype,
-	IterArchetypeParallel,
+	Archetype, ArchetypeEvent, ArchetypeFetch, ArchetypeFetchAny, ArchetypeGrowth, ArchetypeInstance, ArchetypeStore,
+	ArchetypeTransition, ArchetypeTransitionKind, IterArchetype, IterArchetypeParallel, SparseFilter,
 };
-use crate::components::{Component, ComponentSet, ComponentType};
-use crate::entities::{ComponentQuery, Entity, EntityInstance};
-use crate::data_structures::{BitField, Pool};
-use std::sync::atomic::{AtomicU32, Ordering};
+use crate::components::component_id::UnknownComponentError;
+use crate::components::{
+	Bundle, Component, ComponentId, ComponentSet, ComponentVisitor, ErasedComponent, ComponentStorage, ComponentType,
+	ComponentTypeInfo, MutableComponent, SparseStorage,
+};
+use crate::entities::{
+	AnyFilter, ArchetypeExplain, ComponentAccessError, ComponentQuery, Entity, EntityInstance, EntityQuery,
+	MemoryStats, QueryExplain, QueryIter, RelationshipRegistry, StructuralChanges, ComponentWatches, AddedTicks,
+};
+use crate::data_structures::{BitField, Pool, PoolBorrow, ScratchBuffer, SyncPool};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::marker::PhantomData;
-use std::alloc::Layout;
-use std::ops::Range;
+use std::ops::{ControlFlow, Range};
+use std::iter::Filter;
+use std::time::Duration;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+type Observer = Box<dyn FnMut(Entity)>;
 
-static mut NEXT_ID: AtomicU32 = AtomicU32::new(1);
+/// A buffer of [entity](Entity) instances, whose indices start from `index`.
+struct InstanceBuffer {
+	index: usize,
+	instances: Box<[EntityInstance]>,
+	// Handles must be derived from this pointer, as reborrowing the buffer would invalidate them
+	ptr: *mut EntityInstance,
+}
 
 /// A container for [Entities](crate::entities::Entity) and their associated [Components](crate::components::Component).
 pub struct EntityRegistry {
 	id: u32,
 	capacity: usize,
-	instance_buffers: Vec<Box<[EntityInstance]>>,
+	instance_buffers: Vec<InstanceBuffer>,
+	next_instance_index: usize,
 	available_instances: Vec<*mut EntityInstance>,
+	first_version: u32,
+	strict_entity_checks: bool,
 
 	pub(crate) archetype_store: ArchetypeStore,
+	pub(crate) sparse_storage: SparseStorage,
+	relationships: RelationshipRegistry,
+	structural_changes: StructuralChanges,
+	watches: ComponentWatches,
+	added_ticks: AddedTicks,
+	tick: u64,
+	created_observers: Vec<Observer>,
+	destroyed_observers: Vec<Observer>,
 
 	bitfield: BitField,
-	usize_vec_pool: Pool<Vec<usize>>,
-	range_vec_pool: Pool<Vec<Range<usize>>>,
+	pub(crate) usize_vec_pool: SyncPool<Vec<usize>>,
+	range_vec_pool: SyncPool<Vec<Range<usize>>>,
+	entity_vec_pool: Pool<Vec<Entity>>,
+	pub(crate) scratch_pool: Pool<ScratchBuffer>,
 }
 
 impl EntityRegistry {
 	pub(crate) fn new() -> Self {
+		// Ids must never be reused: an entity's instance pointer is only dereferenced
+		// after checking its registry id, which keeps handles that outlived their registry harmless.
+		let id = NEXT_ID
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+			.expect("Too many entity registries have been created");
 		Self {
-			id: unsafe { NEXT_ID.fetch_and(1, Ordering::Relaxed) },
+			id,
 
 			capacity: 0,
 			instance_buffers: vec![],
+			next_instance_index: 0,
 			available_instances: vec![],
-			archetype_store: ArchetypeStore::new(),
+			first_version: 1,
+			strict_entity_checks: true,
+			archetype_store: ArchetypeStore::new(id),
+			sparse_storage: SparseStorage::default(),
+			relationships: RelationshipRegistry::default(),
+			structural_changes: StructuralChanges::default(),
+			watches: ComponentWatches::default(),
+			added_ticks: AddedTicks::default(),
+			tick: 0,
+			created_observers: vec![],
+			destroyed_observers: vec![],
 
 			bitfield: BitField::new(),
-			usize_vec_pool: Pool::default(),
-			range_vec_pool: Pool::default(),
+			usize_vec_pool: SyncPool::default(),
+			range_vec_pool: SyncPool::default(),
+			entity_vec_pool: Pool::default(),
+			scratch_pool: Pool::default(),
+		}
+	}
+
+	pub(crate) fn with_capacity(entities: usize) -> Self {
+		let mut registry = Self::new();
+		if entities != 0 {
+			registry.new_instance_buffer(entities);
 		}
+		registry
 	}
 
 	/// Creates a single [entity](Entity) with no [components](Component) attached.
@@ -48,8 +105,58 @@ impl EntityRegistry {
 	}
 
 	/// Creates a single [entity](Entity) belonging to the specified [archetype](Archetype).
-	#[inline(never)]
 	pub fn create_entity_from_archetype(&mut self, archetype: Archetype) -> Entity {
+		let entity = self.create_entity_internal::<true>(archetype);
+		self.notify_created(&entity);
+		entity
+	}
+
+	/// Creates a single [entity](Entity) belonging to the specified [archetype](Archetype)
+	/// without initializing its [components](Component). **Should not be called from user code.**
+	///
+	/// # Safety
+	/// Every [component](Component) of the [archetype](Archetype) must be initialized through
+	/// [write_component](Self::write_component) before the [entity](Entity) is used in any other way.  
+	/// [finish_entity](Self::finish_entity) must be called once all of them have been written.
+	#[doc(hidden)]
+	pub unsafe fn create_entity_uninit(&mut self, archetype: Archetype) -> Entity {
+		self.create_entity_internal::<false>(archetype)
+	}
+
+	/// Notifies the creation of an [entity](Entity) created by [create_entity_uninit](Self::create_entity_uninit)
+	/// once all of its [components](Component) have been written. **Should not be called from user code.**
+	#[doc(hidden)]
+	pub fn finish_entity(&mut self, entity: &Entity) {
+		self.notify_created(entity);
+	}
+
+	/// Writes a [component](Component) to the specified [entity](Entity) without dropping the previous value.
+	/// **Should not be called from user code.**
+	///
+	/// # Safety
+	/// The previous value must be uninitialized or already dropped.
+	/// Failure to do so may result in memory leaks and/or other unintended behaviour.
+	#[doc(hidden)]
+	pub unsafe fn write_component<T: Component>(&mut self, entity: &Entity, value: T) {
+		let instance = entity.get_instance(self.id);
+		if T::LINK {
+			self.link::<T>(entity, &value);
+		}
+
+		if T::STORAGE == ComponentStorage::Sparse {
+			let set = self.sparse_storage.get_or_insert::<T>(T::component_id());
+			set.insert(entity.instance as usize, value);
+			return;
+		}
+
+		let archetype = self.archetype_store.get_mut(instance.archetype);
+		let component = archetype.get_component_mut::<T>(instance.slot);
+		std::ptr::write(component.expect("Entity does not contain component T"), value);
+	}
+
+	#[inline(never)]
+	fn create_entity_internal<const INIT: bool>(&mut self, archetype: Archetype) -> Entity {
+		self.assert_archetype(archetype);
 		let instance = match self.available_instances.pop() {
 			None => unsafe {
 				self.new_instance_buffer(usize::max(16, self.capacity));
@@ -61,25 +168,30 @@ impl EntityRegistry {
 
 		let mut slot_ranges = self.range_vec_pool.take_one();
 
-		let archetype_instance = self.archetype_store.get_mut(archetype.index as usize);
-		archetype_instance.take_slots(1, &mut slot_ranges);
+		let archetype_instance = self.archetype_store.get_mut(archetype.index);
+		match INIT {
+			true => archetype_instance.take_slots(1, &mut slot_ranges),
+			false => archetype_instance.take_slots_no_init(1, &mut slot_ranges),
+		}
 
 		instance.slot = slot_ranges[0].start;
 		instance.archetype = archetype.index;
 
-		Entity {
+		let entity = Entity {
 			instance,
 			registry_id: self.id,
 			version: instance.version,
-		}
+		};
+
+		archetype_instance.entities_mut()[instance.slot] = entity.clone();
+		entity
 	}
 
 	/// Creates a series of [entities](Entity) belonging to the specified [archetype](Archetype).  
-	/// The new [entities](Entity) will be written into the provided slice.
+	/// The returned iterator owns the new [entities](Entity), so the registry can be used while iterating it.
 	#[inline(never)]
-	pub fn create_entities_from_archetype(
-		&mut self, archetype: Archetype, count: usize,
-	) -> impl Iterator<Item = Entity> + '_ {
+	pub fn create_entities_from_archetype(&mut self, archetype: Archetype, count: usize) -> std::vec::IntoIter<Entity> {
+		self.assert_archetype(archetype);
 		if self.available_instances.len() < count {
 			let required = count - self.available_instances.len();
 			self.new_instance_buffer(usize::max(required, self.capacity));
@@ -101,12 +213,12 @@ impl EntityRegistry {
 		unsafe {
 			let mut slots = slots.iter().cloned().flatten();
 
-			for i in 0..count {
+			for instance in instances.iter() {
 				let next = slots.next();
 				debug_assert_ne!(next, None);
 
 				let slot = next.unwrap_unchecked();
-				let instance = &mut *instances[i];
+				let instance = &mut **instance;
 
 				instance.slot = slot;
 				instance.archetype = archetype_id;
@@ -123,13 +235,93 @@ impl EntityRegistry {
 
 		self.available_instances.drain(start..end);
 
-		slots.into_iter().flatten().map(|i| archetype_entities[i].clone())
+		let entities: Vec<_> = slots.into_iter().flatten().map(|i| archetype_entities[i].clone()).collect();
+		if !self.created_observers.is_empty() || !self.added_ticks.is_empty() {
+			entities.iter().for_each(|e| self.notify_created(e));
+		}
+		entities.into_iter()
+	}
+
+	/// Creates a new [entity](Entity) with a clone of every [component](Component) of the specified [entity](Entity).  
+	/// This function will panic if any of the [entity](Entity)'s [components](Component) does not implement [Clone].
+	pub fn clone_entity(&mut self, entity: &Entity) -> Entity {
+		let instance = entity.get_instance(self.id);
+		let (archetype, src_slot) = (instance.archetype, instance.slot);
+		let key = entity.instance as usize;
+		assert!(
+			self.archetype_store.get(archetype).can_clone() && self.sparse_storage.can_clone(key),
+			"Entity contains components that do not implement Clone"
+		);
+
+		let archetype = self.archetype_store.get(archetype).id();
+		let clone = self.create_entity_internal::<false>(archetype);
+		let dst_slot = clone.get_instance(self.id).slot;
+		unsafe { self.archetype_store.get_mut(archetype.index).clone_components(src_slot, dst_slot) };
+		if !self.sparse_storage.is_empty() {
+			self.sparse_storage.clone_all(key, clone.instance as usize);
+		}
+
+		// Cloned links must be registered, so they are removed along with the original ones
+		let mut links: Vec<_> =
+			self.archetype_store.get(archetype.index).components().iter().filter_map(|t| t.link_fn()).collect();
+		self.sparse_storage.for_each_value(clone.instance as usize, |id, _| {
+			links.extend(ComponentType::by_id(id).ok().and_then(|t| t.link_fn()));
+		});
+		for link in links {
+			link(self, &clone);
+		}
+
+		self.notify_created(&clone);
+		clone
+	}
+
+	/// Moves the specified [entity](Entity) and its [components](Component) to another registry,
+	/// returning the [entity](Entity) created there.  
+	/// The [entity](Entity) is destroyed in this registry, without dropping its [components](Component).
+	///
+	/// [Component ids](crate::components::component_id::ComponentId) are global,
+	/// so the [entity](Entity) is placed in the destination's [archetype](Archetype) with the same
+	/// [components](Component), which is created if needed.  
+	/// [Links](crate::entities::Link) targeting the [entity](Entity) are removed as if it had been destroyed,
+	/// while the ones it holds keep referring to [entities](Entity) of this registry.
+	pub fn transfer_entity(&mut self, entity: &Entity, dst: &mut EntityRegistry) -> Entity {
+		let mut handle = entity.clone();
+		let instance = handle.get_instance_mut(self.id);
+
+		let src = self.archetype_store.get_mut(instance.archetype);
+		let archetype = dst.archetype_store.create_archetype(src.components());
+		let transferred = dst.create_entity_internal::<false>(archetype);
+		let dst_slot = transferred.get_instance(dst.id).slot;
+
+		// SAFETY: Ownership of all components is transferred to the destination archetype, so we don't call drop on them.
+		unsafe {
+			src.copy_components(dst.archetype_store.get_mut(archetype.index), instance.slot, dst_slot);
+			src.return_slot_no_drop(instance.slot);
+		}
+
+		if !self.sparse_storage.is_empty() {
+			let (src, dst_key) = (entity.instance as usize, transferred.instance as usize);
+			self.sparse_storage.move_all(src, &mut dst.sparse_storage, dst_key);
+		}
+
+		for observer in self.destroyed_observers.iter_mut() {
+			observer(entity.clone());
+		}
+
+		let released = self.available_instances.len();
+		instance.version += 1;
+		self.available_instances.push(entity.instance);
+		self.unlink_destroyed(released);
+
+		dst.notify_created(&transferred);
+		transferred
 	}
 
 	/// Destroys the provided [entities](Entity).  
 	/// This function will panic if it encounters an invalid [entity](Entity).
 	#[inline(never)]
 	pub fn destroy_entities(&mut self, entities: &[Entity]) {
+		let released = self.available_instances.len();
 		unsafe {
 			self.bitfield.clear();
 			let mut slots = self.usize_vec_pool.take_one();
@@ -143,8 +335,8 @@ impl EntityRegistry {
 			let mut last_archetype = 0;
 			let archetypes = &mut self.archetype_store;
 
-			for entity in entities {
-				let mut entity = entity.clone();
+			for handle in entities {
+				let mut entity = handle.clone();
 				let instance = entity.get_instance_mut(self.id);
 
 				let archetype = instance.archetype;
@@ -155,38 +347,212 @@ impl EntityRegistry {
 				}
 
 				if !self.bitfield.get_inlined_unchecked(instance.slot) {
+					for observer in self.destroyed_observers.iter_mut() {
+						observer(handle.clone());
+					}
 					instance.version += 1;
 					last_archetype = archetype;
-					slots.push(instance.slot as usize);
+					slots.push(instance.slot);
 					self.bitfield.set_inlined_unchecked(instance.slot, true);
+					self.available_instances.push(entity.instance);
+					if !self.sparse_storage.is_empty() {
+						self.sparse_storage.remove_all(entity.instance as usize);
+					}
 				}
 			}
 
 			if !slots.is_empty() {
-				archetypes.get_mut(last_archetype as usize).return_slots(&slots);
+				archetypes.get_mut(last_archetype).return_slots(&slots);
 			}
 		}
+
+		self.unlink_destroyed(released);
 	}
 
 	/// Gets a reference to a [component](Component) bound to a specific [entity](Entity).
 	pub fn get_component<T: Component>(&self, entity: &Entity) -> Option<&T> {
+		if !self.strict_entity_checks && !self.is_alive(entity) {
+			return None;
+		}
 		let instance = entity.get_instance(self.id);
-		let archetype = self.archetype_store.get(instance.archetype as usize);
-		let component = archetype.get_component::<T>(instance.slot as usize)?;
+		if T::STORAGE == ComponentStorage::Sparse {
+			let set = self.sparse_storage.get::<T>(T::component_id())?;
+			return set.get(entity.instance as usize);
+		}
+		let archetype = self.archetype_store.get(instance.archetype);
+		let component = archetype.get_component::<T>(instance.slot)?;
 		unsafe { Some(&*(component as *const T)) }
 	}
 
-	/// Gets a mutable reference to a [component](Component) bound to a specific [entity](Entity).
-	pub fn get_component_mut<T: Component>(&mut self, entity: &Entity) -> Option<&mut T> {
+	/// Sets whether accessing the [components](Component) of invalid [entities](Entity) panics, which is the default.
+	///
+	/// When disabled, [get_component](Self::get_component) and [get_component_mut](Self::get_component_mut)
+	/// return *None* for [entities](Entity) that were destroyed or belong to another registry,
+	/// while [add_component](Self::add_component) and [remove_component](Self::remove_component) return *false*.  
+	/// All other functions keep panicking.
+	pub fn set_strict_entity_checks(&mut self, strict: bool) {
+		self.strict_entity_checks = strict;
+	}
+
+	/// Gets a reference to a [component](Component) bound to a specific [entity](Entity).  
+	/// Unlike [EntityRegistry::get_component], invalid [entities](Entity) are reported instead of causing a panic.
+	pub fn try_get_component<T: Component>(&self, entity: &Entity) -> Result<&T, ComponentAccessError> {
+		if entity.registry_id != self.id {
+			return Err(ComponentAccessError::WrongContext);
+		}
+		if !self.is_alive(entity) {
+			return Err(ComponentAccessError::DeadEntity);
+		}
+		self.get_component(entity).ok_or(ComponentAccessError::MissingComponent)
+	}
+
+	/// Gets the [archetype](Archetype) a specific [entity](Entity) belongs to.  
+	/// [Entities](Entity) with no [components](Component) belong to the default [archetype](Archetype).
+	///
+	/// The handle changes whenever the [entity](Entity) gains or loses a [table](ComponentStorage::Table)
+	/// [component](Component), and can be used with [for_each_in_archetype](Self::for_each_in_archetype).
+	pub fn archetype_of(&self, entity: &Entity) -> Archetype {
+		let index = entity.get_instance(self.id).archetype;
+		self.archetype_store.get(index).id()
+	}
+
+	/// Gets a mutable reference to a [component](Component) bound to a specific [entity](Entity).  
+	/// [Immutable](MutableComponent) [components](Component) cannot be accessed mutably.
+	pub fn get_component_mut<T: MutableComponent>(&mut self, entity: &Entity) -> Option<&mut T> {
+		self.component_mut(entity)
+	}
+
+	/// Gets a mutable reference to a [component](Component) regardless of its mutability,
+	/// so it can be replaced as a whole.
+	fn component_mut<T: Component>(&mut self, entity: &Entity) -> Option<&mut T> {
+		if !self.strict_entity_checks && !self.is_alive(entity) {
+			return None;
+		}
 		let instance = entity.get_instance(self.id);
-		let archetype = self.archetype_store.get_mut(instance.archetype as usize);
-		let component = archetype.get_component_mut::<T>(instance.slot as usize)?;
+		if !self.watches.is_empty() {
+			self.watches.mark(entity, T::component_id());
+		}
+		if T::STORAGE == ComponentStorage::Sparse {
+			let set = self.sparse_storage.get_mut::<T>(T::component_id())?;
+			return set.get_mut(entity.instance as usize);
+		}
+		let archetype = self.archetype_store.get_mut(instance.archetype);
+		let component = archetype.get_component_mut::<T>(instance.slot)?;
 		unsafe { Some(&mut *(component as *mut T)) }
 	}
 
+	/// Checks which of the specified [entities](Entity) contain a [component](Component) of type `T`.  
+	/// Bit `i` of `out` is set if and only if `entities[i]` contains the [component](Component);
+	/// all other bits are cleared.
+	pub fn components_present<T: Component>(&self, entities: &[Entity], out: &mut BitField) {
+		out.clear();
+		out.ensure_capacity(entities.len());
+
+		if T::STORAGE == ComponentStorage::Sparse {
+			let set = self.sparse_storage.get::<T>(T::component_id());
+			for (i, entity) in entities.iter().enumerate() {
+				entity.get_instance(self.id);
+				if set.is_some_and(|s| s.get(entity.instance as usize).is_some()) {
+					unsafe { out.set_inlined_unchecked(i, true) };
+				}
+			}
+			return;
+		}
+
+		// Archetype lookups are cached, since batches usually contain many entities of the same archetype.
+		let id = T::component_id().value();
+		let mut checked = BitField::new();
+		let mut contained = BitField::new();
+		for (i, entity) in entities.iter().enumerate() {
+			let archetype = entity.get_instance(self.id).archetype;
+			if !checked.get_inlined(archetype) {
+				checked.set_inlined(archetype, true);
+				let contains = self.archetype_store.get(archetype).component_bitfield().get_inlined(id);
+				contained.set_inlined(archetype, contains);
+			}
+			if contained.get_inlined(archetype) {
+				unsafe { out.set_inlined_unchecked(i, true) };
+			}
+		}
+	}
+
+	/// Gets the [component types](ComponentType) of the specified [entity](Entity)'s [archetype](Archetype).  
+	/// [Sparse](ComponentStorage::Sparse) components are not part of an [archetype](Archetype), so they are not included.
+	pub fn component_types(&self, entity: &Entity) -> &[ComponentType] {
+		let instance = entity.get_instance(self.id);
+		self.archetype_store.get(instance.archetype).components()
+	}
+
+	/// Gets the [archetype](Archetype) containing the [components](Component) with the specified ids,
+	/// creating it if it doesn't exist yet.  
+	/// Useful when the [components](Component) are only known at runtime, e.g. when loaded from data files.
+	///
+	/// The function will return an error if any of the ids does not belong to a registered [component](Component).
+	pub fn get_or_create_archetype(&mut self, ids: &[ComponentId]) -> Result<Archetype, UnknownComponentError> {
+		let components: Result<Vec<_>, _> = ids.iter().map(|id| ComponentType::by_id(*id)).collect();
+		Ok(self.archetype_store.create_archetype(&components?))
+	}
+
+	/// Invokes `visitor` with every [component](Component) of the specified [entity](Entity),
+	/// including [sparse](ComponentStorage::Sparse) ones.  
+	/// Useful for tooling that needs to handle [components](Component) generically, e.g. serializers or inspectors.
+	pub fn visit_components(&self, entity: &Entity, visitor: &mut impl ComponentVisitor) {
+		let instance = entity.get_instance(self.id);
+		let archetype = self.archetype_store.get(instance.archetype);
+
+		// SAFETY: The entity is alive, so its slot is occupied and all of its components are initialized.
+		unsafe {
+			archetype.for_each_component(instance.slot, |component, value| {
+				visitor.visit(ErasedComponent::new(component.clone(), value));
+			});
+		}
+
+		if !self.sparse_storage.is_empty() {
+			self.sparse_storage.for_each_value(entity.instance as usize, |id, value| {
+				let component = ComponentType::by_id(id).expect("Sparse component is not registered");
+				// SAFETY: Sparse values are initialized for as long as they're in their set.
+				visitor.visit(unsafe { ErasedComponent::new(component, value) });
+			});
+		}
+	}
+
+	/// Checks if the specified [archetype](Archetype) contains a [component](Component) of type `T`.
+	pub fn archetype_contains<T: Component>(&self, archetype: Archetype) -> bool {
+		self.archetype_signature(archetype).get(T::component_id().value())
+	}
+
+	/// Gets the set of [component ids](crate::components::component_id::ComponentId)
+	/// contained in the specified [archetype](Archetype).  
+	/// Two [archetypes](Archetype) contain the same [components](Component) if their signatures are equal.
+	pub fn archetype_signature(&self, archetype: Archetype) -> &BitField {
+		self.assert_archetype(archetype);
+		self.archetype_store.get(archetype.index).component_bitfield()
+	}
+
 	/// Add a new [component](Component) to the specified [entity](Entity).  
 	/// The function will return *false* if a [component](Component) of the same type is already present.
+	///
+	/// [Sparse](ComponentStorage::Sparse) components don't move the [entity](Entity) to another [archetype](Archetype).
 	pub fn add_component<T: Component>(&mut self, entity: &Entity, value: T) -> bool {
+		if !self.strict_entity_checks && !self.is_alive(entity) {
+			return false;
+		}
+		if T::STORAGE == ComponentStorage::Sparse {
+			entity.get_instance(self.id);
+			let set = self.sparse_storage.get_or_insert::<T>(T::component_id());
+			let key = entity.instance as usize;
+			if set.index_of(key).is_some() {
+				return false;
+			}
+			if T::LINK {
+				self.link::<T>(entity, &value);
+			}
+			let set = self.sparse_storage.get_or_insert::<T>(T::component_id());
+			set.insert(key, value);
+			self.record_entered(T::component_id(), entity);
+			return true;
+		}
+
 		let component = ComponentType::of::<T>();
 		let kind = ArchetypeTransitionKind::Add;
 		let transition = self.apply_archetype_transition(entity, component, kind);
@@ -194,16 +560,116 @@ impl EntityRegistry {
 		match transition {
 			None => false,
 			Some((_, (archetype, slot))) => unsafe {
+				if T::LINK {
+					self.link::<T>(entity, &value);
+				}
 				let dst = self.archetype_store.get_mut(archetype.index);
 				std::ptr::write(dst.get_component_mut(slot).unwrap(), value);
+				self.record_entered(T::component_id(), entity);
 				true
 			},
 		}
 	}
 
+	/// Adds all [components](Component) of a [Bundle] to the specified [entity](Entity)
+	/// with a single [archetype](Archetype) transition.  
+	/// The function will return *false*, without adding any of them,
+	/// if a [component](Component) of the same type as one of the [Bundle]'s is already present.
+	///
+	/// Each [component](Component) type may only appear once in the [Bundle].
+	pub fn add_bundle<B: Bundle>(&mut self, entity: &Entity, bundle: B) -> bool {
+		let mut handle = entity.clone();
+		let instance = handle.get_instance_mut(self.id);
+		let key = entity.instance as usize;
+
+		let src = self.archetype_store.get(instance.archetype);
+		let mut components = src.components().to_vec();
+		let mut added = BitField::new();
+		let mut moves = false;
+
+		for component in B::component_types() {
+			let id = component.id();
+			assert!(
+				!added.get(id.value()),
+				"Bundle contains the same component more than once"
+			);
+			added.set(id.value(), true);
+
+			let present = match component.storage() {
+				ComponentStorage::Sparse => self.sparse_storage.contains(id, key),
+				ComponentStorage::Table => src.component_bitfield().get(id.value()),
+			};
+			if present {
+				return false;
+			}
+
+			moves |= component.storage() == ComponentStorage::Table;
+			components.push(component);
+		}
+
+		if moves {
+			let archetype = self.archetype_store.create_archetype(&components);
+			let (src, dst) = self.archetype_store.get_pair_mut(instance.archetype, archetype.index);
+			let src_slot = instance.slot;
+
+			let dst_slot = {
+				let mut slots = self.range_vec_pool.take_one();
+				dst.take_slots_no_init(1, &mut slots);
+				slots[0].start
+			};
+
+			instance.slot = dst_slot;
+			instance.archetype = archetype.index;
+			dst.entities_mut()[dst_slot] = entity.clone();
+
+			// SAFETY:
+			// Ownership of the existing components is transferred to the destination archetype,
+			// the bundle's components are written right after.
+			unsafe {
+				src.copy_components(dst, src_slot, dst_slot);
+				src.return_slot_no_drop(src_slot);
+			}
+		}
+
+		// SAFETY: The bundle's components are either in freshly taken slots or not present at all.
+		unsafe { bundle.write(self, entity) };
+		if !self.structural_changes.is_empty() {
+			for component in B::component_types() {
+				self.structural_changes.record_entered(component.id(), entity);
+			}
+		}
+		if !self.added_ticks.is_empty() {
+			for component in B::component_types() {
+				self.added_ticks.record_entered(component.id(), entity.instance as usize, self.tick);
+			}
+		}
+		true
+	}
+
 	/// Remove a [component](Component) from the specified [entity](Entity).  
 	/// The function will return *false* if the [component](Component) is not present.
+	///
+	/// [Sparse](ComponentStorage::Sparse) components don't move the [entity](Entity) to another [archetype](Archetype).
 	pub fn remove_component<T: Component>(&mut self, entity: &Entity) -> bool {
+		if !self.strict_entity_checks && !self.is_alive(entity) {
+			return false;
+		}
+		if T::LINK {
+			self.unlink::<T>(entity);
+		}
+
+		if T::STORAGE == ComponentStorage::Sparse {
+			entity.get_instance(self.id);
+			let removed = match self.sparse_storage.get_mut::<T>(T::component_id()) {
+				None => false,
+				Some(set) => set.remove(entity.instance as usize).is_some(),
+			};
+			if removed {
+				self.record_exited(T::component_id(), entity);
+			}
+			return removed;
+		}
+
 		let component = ComponentType::of::<T>();
 		let kind = ArchetypeTransitionKind::Remove;
 		let transition = self.apply_archetype_transition(entity, component, kind);
@@ -213,43 +679,848 @@ impl EntityRegistry {
 			Some(((archetype, slot), _)) => unsafe {
 				let src = self.archetype_store.get_mut(archetype.index);
 				std::ptr::drop_in_place(src.get_component_mut::<T>(slot).unwrap());
+				self.record_exited(T::component_id(), entity);
 				true
 			},
 		}
 	}
 
+	/// Removes the [component](Component) of type `T` from all provided [entities](Entity).  
+	/// Each run of adjacent [entities](Entity) sharing an [archetype](Archetype) is moved in a single transition,
+	/// so `entities` should be grouped by [archetype](Archetype) and must not contain duplicates.
+	fn remove_component_batch<T: Component>(&mut self, entities: &[Entity]) {
+		if T::STORAGE == ComponentStorage::Sparse {
+			for entity in entities {
+				self.remove_component::<T>(entity);
+			}
+			return;
+		}
+
+		let id = self.id;
+		let component = ComponentType::of::<T>();
+		let mut slots = self.usize_vec_pool.take_one();
+		let mut ranges = self.range_vec_pool.take_one();
+
+		for run in entities.chunk_by(|a, b| a.get_instance(id).archetype == b.get_instance(id).archetype) {
+			if T::LINK {
+				run.iter().for_each(|entity| self.unlink::<T>(entity));
+			}
+
+			let transition = self.archetype_store.get_archetype_transition(ArchetypeTransition {
+				archetype: self.archetype_store.get(run[0].get_instance(id).archetype).id(),
+				component: component.clone(),
+				kind: ArchetypeTransitionKind::Remove,
+			});
+
+			let Some((src, dst)) = transition else {
+				continue;
+			};
+
+			slots.clear();
+			dst.take_slots_no_init(run.len(), &mut ranges);
+			for (entity, dst_slot) in run.iter().zip(ranges.iter().cloned().flatten()) {
+				let mut entity = entity.clone();
+				let instance = entity.get_instance_mut(id);
+				let src_slot = instance.slot;
+				instance.archetype = dst.id().index;
+				instance.slot = dst_slot;
+				dst.entities_mut()[dst_slot] = entity.clone();
+
+				// SAFETY: The removed component is dropped, and ownership of the others is transferred to `dst`.
+				unsafe {
+					std::ptr::drop_in_place(src.get_component_mut::<T>(src_slot).unwrap());
+					src.copy_components(dst, src_slot, dst_slot);
+				}
+				slots.push(src_slot);
+			}
+
+			unsafe { src.return_slots_no_drop(&slots) };
+			for entity in run {
+				self.record_exited(T::component_id(), entity);
+			}
+		}
+	}
+
+	/// Replaces the [component](Component) of type `Old` of the specified [entity](Entity) with `new`,
+	/// returning the removed value.  
+	/// The function will return *None* and leave the [entity](Entity) untouched if `Old` is not present.
+	///
+	/// Unlike removing `Old` and adding `New`, the [entity](Entity) is moved to its new [archetype](Archetype)
+	/// in a single transition. If `New` is already present, its value is replaced.
+	pub fn swap_component<Old: Component, New: Component>(&mut self, entity: &Entity, new: New) -> Option<Old> {
+		assert_ne!(
+			Old::component_id(),
+			New::component_id(),
+			"Old and New must be different component types"
+		);
+
+		let instance = entity.get_instance(self.id);
+		let (src_index, src_slot) = (instance.archetype, instance.slot);
+		let old = match Old::STORAGE {
+			ComponentStorage::Sparse => {
+				let key = entity.instance as usize;
+				self.sparse_storage.get::<Old>(Old::component_id())?.index_of(key)?;
+				if Old::LINK {
+					self.unlink::<Old>(entity);
+				}
+				let set = self.sparse_storage.get_mut::<Old>(Old::component_id()).unwrap();
+				let old = set.remove(key).unwrap();
+				self.record_exited(Old::component_id(), entity);
+				self.replace_component(entity, new);
+				return Some(old);
+			},
+
+			ComponentStorage::Table => {
+				let src = self.archetype_store.get_mut(src_index);
+				let old = src.get_component_mut::<Old>(src_slot)? as *mut Old;
+				if Old::LINK {
+					self.unlink::<Old>(entity);
+				}
+				// SAFETY: The value is moved out, so the transition below must not drop it.
+				unsafe { std::ptr::read(old) }
+			},
+		};
+		self.record_exited(Old::component_id(), entity);
+
+		let has_new = self.archetype_store.get(src_index).component_bitfield().get(New::component_id().value());
+		if New::STORAGE == ComponentStorage::Sparse || has_new {
+			let component = ComponentType::of::<Old>();
+			self.apply_archetype_transition(entity, component, ArchetypeTransitionKind::Remove);
+			self.replace_component(entity, new);
+			return Some(old);
+		}
+
+		let src = self.archetype_store.get(src_index);
+		let mut components: Vec<_> =
+			src.components().iter().filter(|t| t.id() != Old::component_id()).cloned().collect();
+		components.push(ComponentType::of::<New>());
+		let archetype = self.archetype_store.create_archetype(&components);
+
+		let mut handle = entity.clone();
+		let instance = handle.get_instance_mut(self.id);
+		let (src, dst) = self.archetype_store.get_pair_mut(src_index, archetype.index);
+		let dst_slot = {
+			let mut slots = self.range_vec_pool.take_one();
+			dst.take_slots_no_init(1, &mut slots);
+			slots[0].start
+		};
+
+		instance.slot = dst_slot;
+		instance.archetype = archetype.index;
+		dst.entities_mut()[dst_slot] = entity.clone();
+
+		// SAFETY:
+		// Shared components are moved to the destination archetype and `Old` has already been moved out,
+		// so only `New` is left to be initialized.
+		unsafe {
+			src.copy_components(dst, src_slot, dst_slot);
+			src.return_slot_no_drop(src_slot);
+			if New::LINK {
+				self.link::<New>(entity, &new);
+			}
+			let dst = self.archetype_store.get_mut(archetype.index);
+			std::ptr::write(dst.get_component_mut::<New>(dst_slot).unwrap(), new);
+		}
+
+		self.record_entered(New::component_id(), entity);
+		Some(old)
+	}
+
+	/// Adds a [component](Component) to the specified [entity](Entity), replacing its value if already present.
+	fn replace_component<T: Component>(&mut self, entity: &Entity, value: T) {
+		if self.get_component::<T>(entity).is_none() {
+			self.add_component(entity, value);
+			return;
+		}
+
+		if T::LINK {
+			self.unlink::<T>(entity);
+			self.link::<T>(entity, &value);
+		}
+		*self.component_mut::<T>(entity).unwrap() = value;
+	}
+
+	/// Moves the specified [entity](Entity) to another [archetype](Archetype) in a single transition.
+	///
+	/// [Components](Component) shared by both archetypes are preserved,
+	/// newly gained [components](Component) are default-initialized and lost [components](Component) are dropped.
+	pub fn move_entity_to(&mut self, entity: &Entity, archetype: Archetype) {
+		self.assert_archetype(archetype);
+
+		let mut entity = entity.clone();
+		let instance = entity.get_instance_mut(self.id);
+		if instance.archetype == archetype.index {
+			return;
+		}
+
+		let (src, dst) = self.archetype_store.get_pair_mut(instance.archetype, archetype.index);
+		let src_slot = instance.slot;
+
+		let dst_slot = {
+			let mut slots = self.range_vec_pool.take_one();
+			dst.take_slots_no_init(1, &mut slots);
+			slots[0].start
+		};
+
+		instance.slot = dst_slot;
+		instance.archetype = archetype.index;
+		dst.entities_mut()[dst_slot] = entity.clone();
+
+		// SAFETY:
+		// Shared components are moved to the destination archetype, lost components are dropped
+		// and gained ones are default-initialized, so every component is initialized exactly once.
+		unsafe {
+			src.copy_components(dst, src_slot, dst_slot);
+			src.drop_components_not_in(dst, src_slot);
+			dst.default_components_not_in(src, dst_slot);
+			src.return_slot_no_drop(src_slot);
+		}
+
+		if !self.structural_changes.is_empty() {
+			let (src, dst) = (src.component_bitfield(), dst.component_bitfield());
+			self.structural_changes.record_transition(&entity, src, dst);
+		}
+		if !self.added_ticks.is_empty() {
+			let (src, dst) = (src.component_bitfield(), dst.component_bitfield());
+			self.added_ticks.record_transition(entity.instance as usize, self.tick, src, dst);
+		}
+	}
+
 	/// Create a new filter for the currently existing [entities](Entity).
 	///
 	/// The filter can then be used to iterate over those [entities](Entity)
 	/// or perform other kinds of operations.
 	#[inline(always)]
-	pub fn filter(&mut self) -> EntityFilter<(), ()> {
+	pub fn filter(&mut self) -> EntityFilter<'_, (), ()> {
 		EntityFilter {
 			entity_store: self,
-			i_phantom: PhantomData::default(),
-			e_phantom: PhantomData::default(),
+			extra_include: BitField::new(),
+			extra_exclude: BitField::new(),
+			include_disabled: false,
+			limit: usize::MAX,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
 		}
 	}
 
-	fn new_instance_buffer(&mut self, size: usize) -> &mut [EntityInstance] {
-		unsafe {
-			let ptr = std::alloc::alloc(Layout::array::<EntityInstance>(size).unwrap()) as *mut EntityInstance;
-			let buffer = std::slice::from_raw_parts_mut(ptr, size);
-			let instances = Box::from_raw(buffer);
+	/// Mutate the [component](Component) of type `T` of every [entity](Entity) that has one.  
+	/// Shorthand for `filter().include::<&mut T>().for_each(func)`.
+	pub fn for_each_component_mut<T: MutableComponent + ComponentTypeInfo<ComponentType = T>>(
+		&mut self, func: impl FnMut(&mut T),
+	) {
+		self.filter().include::<&mut T>().for_each(func);
+	}
+
+	/// Mutate the [component](Component) of type `T` of every [entity](Entity) that has one, in parallel.  
+	/// Shorthand for `filter().include::<&mut T>().par_for_each(func)`.
+	pub fn par_for_each_component_mut<T: MutableComponent + ComponentTypeInfo<ComponentType = T> + Send + Sync>(
+		&mut self, func: impl Fn(&mut T) + Send + Sync,
+	) {
+		self.filter().include::<&mut T>().par_for_each(func);
+	}
+
+	/// Destroys every [entity](Entity) matching the query for which `predicate` returns `true`.  
+	/// Matching [entities](Entity) are collected first and destroyed in a single batch once iteration is over.
+	pub fn despawn_where<I: 'static + ComponentSet, E: 'static + ComponentSet>(
+		&mut self, mut predicate: impl FnMut(<(I, E) as ComponentQuery>::Arguments) -> bool,
+	) where
+		ArchetypeInstance: IterArchetype<I>,
+	{
+		let mut entities = self.entity_vec_pool.take_one();
+		entities.clear();
+		self.filter().include::<I>().exclude::<E>().entities_for_each(|entity, args| {
+			if predicate(args) {
+				entities.push(entity);
+			}
+		});
+
+		if !entities.is_empty() {
+			self.destroy_entities(&entities);
+		}
+	}
+
+	/// Gets the number of [entity](Entity) instances currently allocated by the registry.
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Gets the number of [entities](Entity) that can be created before new instances are allocated.
+	pub fn available_capacity(&self) -> usize {
+		self.available_instances.len()
+	}
+
+	/// Gets the number of [entities](Entity) that can be added to the specified [archetype](Archetype)
+	/// before its storage has to grow.
+	pub fn archetype_free_slots(&self, archetype: Archetype) -> usize {
+		self.assert_archetype(archetype);
+		self.archetype_store.get(archetype.index).free_slots()
+	}
+
+	/// Gets the values of the [component](Component) of type `T` of all [entities](Entity)
+	/// belonging to the specified [archetype](Archetype), in slot order.  
+	/// Useful to initialize the [entities](Entity) returned by
+	/// [create_entities_from_archetype](Self::create_entities_from_archetype) without looking each of them up.
+	///
+	/// # Panics
+	/// Panics if the [archetype](Archetype) does not contain `T`, or if its slots are fragmented,
+	/// which can only happen after [entities](Entity) have been removed from it.
+	pub fn archetype_column_mut<T: MutableComponent>(&mut self, archetype: Archetype) -> &mut [T] {
+		self.assert_archetype(archetype);
+		let instance = self.archetype_store.get_mut(archetype.index);
+		instance.column_mut::<T>().expect("Archetype does not contain the requested component")
+	}
+
+	/// Iterate the [entities](Entity) belonging to the specified [archetype](Archetype) with the provided function,
+	/// ignoring all other [archetypes](Archetype) containing the same [components](Component).  
+	/// Useful to process a deliberately grouped set of [entities](Entity), such as the ones of a
+	/// [pinned archetype](crate::context::EcsContext::create_pinned_archetype).
+	///
+	/// # Panics
+	/// Panics if the [archetype](Archetype) does not contain the [table](crate::components::ComponentStorage::Table)
+	/// [components](Component) accessed by `I`.
+	pub fn for_each_in_archetype<I: 'static + ComponentSet>(
+		&mut self, archetype: Archetype, mut func: impl FnMut(Entity, <(I, ()) as ComponentQuery>::Arguments),
+	) where
+		ArchetypeInstance: IterArchetype<I>,
+	{
+		self.assert_archetype(archetype);
+		let data = crate::entities::get_query_data(<(I, ())>::get_query());
+		let filter = SparseFilter::new(&self.sparse_storage, &data);
+
+		let instance = self.archetype_store.get_mut(archetype.index);
+		assert!(
+			instance.matches_query(data.table_include()),
+			"Archetype does not contain the requested component"
+		);
+		IterArchetype::entities_for_each(instance, &mut self.sparse_storage, &filter, &mut func);
+	}
+
+	/// Overwrites the values of the [component](Component) of type `T` of all [entities](Entity)
+	/// belonging to the specified [archetype](Archetype), in slot order.  
+	/// Unlike [archetype_column_mut](Self::archetype_column_mut), fragmented slots are supported.
+	/// This is the fastest way to bulk-update a [component](Component), e.g. when writing back the results of a physics step.
+	///
+	/// # Panics
+	/// Panics if the [archetype](Archetype) does not contain `T`,
+	/// or if the number of values does not match the number of [entities](Entity) in the [archetype](Archetype).
+	pub fn write_component_column<T: MutableComponent + Clone>(&mut self, archetype: Archetype, values: &[T]) {
+		self.assert_archetype(archetype);
+		let instance = self.archetype_store.get_mut(archetype.index);
+		assert!(
+			instance.write_column(values),
+			"Archetype does not contain the requested component"
+		);
+	}
+
+	/// Creates the [archetype](Archetype) reached by adding or removing a [component](Component) of type `T`
+	/// from the specified [archetype](Archetype), and caches the transition between the two.  
+	/// Useful to move the cost of creating [archetypes](Archetype) from the first structural change to load time.
+	///
+	/// Returns the destination [archetype](Archetype), which is the source one if the transition changes nothing.
+	pub fn prebuild_transition<T: Component>(&mut self, from: Archetype, kind: ArchetypeTransitionKind) -> Archetype {
+		self.assert_archetype(from);
+		if T::STORAGE == ComponentStorage::Sparse {
+			return from;
+		}
+
+		self.archetype_store.prebuild_transition(ArchetypeTransition {
+			archetype: from,
+			component: ComponentType::of::<T>(),
+			kind,
+		})
+	}
+
+	/// Replaces every `Old` [component](Component) with a `New` one, converting their values with `conv`.
+	///
+	/// Meant for hot-reload workflows, where a [component](Component)'s layout changes after reloading its code.  
+	/// [Entities](Entity) keep their slots, and [archetypes](Archetype) containing `Old` are updated in place,
+	/// so existing [Archetype] handles now refer to the same [components](Component) with `New` instead of `Old`.  
+	/// The `Old` values are dropped once all of them have been converted.
+	///
+	/// # Panics
+	/// Panics before migrating anything if an [archetype](Archetype) containing `Old` also contains `New`,
+	/// or if replacing `Old` with `New` in one would yield the [components](Component) of another existing [archetype](Archetype).  
+	/// If `conv` panics, the `New` values converted so far are dropped and no [archetype](Archetype) is migrated,
+	/// so every [entity](Entity) keeps its `Old` value.
+	pub fn migrate_component<Old: Component, New: Component>(&mut self, conv: impl Fn(&Old) -> New) {
+		assert!(
+			Old::STORAGE == ComponentStorage::Table && New::STORAGE == ComponentStorage::Table,
+			"Only table components can be migrated"
+		);
+		assert!(!Old::LINK && !New::LINK, "Links cannot be migrated");
+		self.archetype_store.migrate_component(conv);
+	}
+
+	/// Sets how [archetypes](Archetype) grow when spawning [entities](Entity) into them requires more room.
+	///
+	/// Defaults to [ArchetypeGrowth::Double].
+	/// Explicit reservations, such as [EntityRegistry::reserve_archetype], are never rounded up.
+	pub fn set_archetype_growth(&mut self, growth: ArchetypeGrowth) {
+		self.archetype_store.set_growth(growth);
+	}
+
+	/// Sets a callback notified of every archetype creation, query initialization and transition resolution,
+	/// along with the time it took.
+	///
+	/// Useful to find out where structural costs occur when profiling frame spikes.  
+	/// Replaces the previous callback, if any. When no callback is set, no timing is performed.
+	pub fn set_archetype_profiler(&mut self, profiler: impl Fn(ArchetypeEvent, Duration) + Send + Sync + 'static) {
+		self.archetype_store.set_profiler(Some(Box::new(profiler)));
+	}
+
+	/// Removes the callback set through [EntityRegistry::set_archetype_profiler].
+	pub fn clear_archetype_profiler(&mut self) {
+		self.archetype_store.set_profiler(None);
+	}
+
+	/// Reserves space for at least `additional` more [entities](Entity) in the specified [archetype](Archetype).
+	///
+	/// Useful to avoid reallocations before a known burst of spawns.
+	pub fn reserve_archetype(&mut self, archetype: Archetype, additional: usize) {
+		self.assert_archetype(archetype);
+		let instance = self.archetype_store.get_mut(archetype.index);
+		instance.ensure_capacity(instance.len() + additional);
+	}
+
+	/// Reserves space for at least `additional` more [entities](Entity)
+	/// in every [archetype](Archetype) matched by the specified [query](EntityQuery).
+	pub fn reserve_query(&mut self, query: EntityQuery, additional: usize) {
+		for instance in self.archetype_store.query(query) {
+			instance.ensure_capacity(instance.len() + additional);
+		}
+	}
+
+	/// Frees all instance buffers that contain no live [entities](Entity).
+	///
+	/// # Safety
+	/// [Entity] handles refer to their instance by pointer.  
+	/// Handles to destroyed [entities](Entity) whose instance buffer was freed must not be used afterwards.
+	#[inline(never)]
+	pub unsafe fn shrink_to_fit(&mut self) {
+		let id = self.id;
+		self.relationships.retain_holders(|h| h.registry_id == id && (*h.instance).version == h.version);
+
+		let mut available = self.usize_vec_pool.take_one();
+		available.clear();
+		available.extend(self.available_instances.iter().map(|i| *i as usize));
+		available.sort_unstable();
+
+		let mut freed = self.range_vec_pool.take_one();
+		freed.clear();
+
+		self.instance_buffers.retain(|InstanceBuffer { instances: buffer, .. }| {
+			let range = buffer.as_ptr_range();
+			let range = range.start as usize..range.end as usize;
+			let start = available.partition_point(|i| *i < range.start);
+			let end = available.partition_point(|i| *i < range.end);
+
+			if end - start != buffer.len() {
+				return true;
+			}
+
+			// New instances might reuse the freed memory, so their versions must not match any outstanding handle.
+			let last_version = buffer.iter().map(|i| i.version).max().unwrap_or(0);
+			self.first_version = u32::max(self.first_version, last_version + 1);
+			freed.push(range);
+			false
+		});
+
+		if freed.is_empty() {
+			return;
+		}
+
+		let size = std::mem::size_of::<EntityInstance>();
+		self.capacity -= freed.iter().map(|r| r.len() / size).sum::<usize>();
+		self.available_instances.retain(|i| !freed.iter().any(|r| r.contains(&(*i as usize))));
+
+		let slots = self.archetype_store.iter().map(|a| a.capacity()).max().unwrap_or(0);
+		self.bitfield.shrink_to(usize::max(self.capacity, slots));
+	}
+
+	/// Checks if the specified [entity](Entity) is matched by the specified [query](EntityQuery)
+	/// without going through the [archetypes](Archetype) matched by the query.  
+	/// The function will return *false* if the [entity](Entity) has been destroyed or belongs to another registry.
+	pub fn entity_matches(&self, entity: &Entity, query: EntityQuery) -> bool {
+		if !self.is_alive(entity) {
+			return false;
+		}
+
+		let instance = entity.get_instance(self.id);
+		let data = crate::entities::get_query_data(query);
+		if !self.archetype_store.get(instance.archetype).matches(&data) {
+			return false;
+		}
+
+		// SAFETY: The entity is alive, and the filter only reads the instance it points to.
+		let filter = SparseFilter::new(&self.sparse_storage, &data);
+		filter.is_empty() || unsafe { filter.matches(entity, 0) }
+	}
+
+	/// Describes which [archetypes](Archetype) are matched by the specified [query](EntityQuery).
+	///
+	/// Useful for finding out why a query does not visit the expected [entities](Entity).
+	pub fn explain_query(&self, query: EntityQuery) -> QueryExplain {
+		let data = crate::entities::get_query_data(query);
+		let archetypes = self.archetype_store.iter().filter(|a| a.matches(&data));
+
+		QueryExplain {
+			include: crate::entities::component_names(data.include()),
+			exclude: crate::entities::component_names(data.exclude()),
+			cached: self.archetype_store.is_query_initialized(query),
+			archetypes: archetypes
+				.map(|a| ArchetypeExplain {
+					archetype: a.id(),
+					components: a.components().iter().map(|t| t.id().name()).collect(),
+					entities: a.len(),
+				})
+				.collect(),
+		}
+	}
+
+	/// Reports the memory allocated by the registry's [archetypes](Archetype) and [entity](Entity) instances.  
+	/// [Component](Component) values are not accessed, only capacities are read.
+	pub fn memory_stats(&self) -> MemoryStats {
+		let mut stats = MemoryStats {
+			instance_bytes: self.capacity * std::mem::size_of::<EntityInstance>(),
+			..MemoryStats::default()
+		};
+
+		for archetype in self.archetype_store.iter() {
+			for (id, bytes) in archetype.component_bytes() {
+				stats.archetype_bytes += bytes;
+				*stats.components.entry(id.name()).or_default() += bytes;
+			}
+		}
+
+		stats
+	}
+
+	fn new_instance_buffer(&mut self, size: usize) {
+		let version = self.first_version;
+		let instances = (0..size).map(|_| EntityInstance {
+			version,
+			..EntityInstance::default()
+		});
+
+		self.capacity += size;
+		self.bitfield.reserve(size);
+		self.instance_buffers.push(InstanceBuffer {
+			index: self.next_instance_index,
+			instances: instances.collect(),
+			ptr: std::ptr::null_mut(),
+		});
+		self.next_instance_index += size;
+
+		// Instance pointers must be derived from the stored buffer, or moving it into the list would invalidate them
+		let buffer = self.instance_buffers.last_mut().unwrap();
+		buffer.ptr = buffer.instances.as_mut_ptr();
+		let ptr = buffer.ptr;
+		for i in 0..size {
+			self.available_instances.push(unsafe { ptr.add(i) });
+		}
+	}
+
+	/// Registers the [entity](Entity) as a holder of the link to `value`'s target.
+	fn link<T: Component>(&mut self, holder: &Entity, value: &T) {
+		if let Some(target) = value.link_target() {
+			self.link_to::<T>(holder, target.clone());
+		}
+	}
+
+	/// Registers the [entity](Entity)'s link of type `T`, which is already attached to it, if any.
+	pub(crate) fn link_attached<T: Component>(&mut self, holder: &Entity) {
+		let target = self.get_component::<T>(holder).and_then(T::link_target).cloned();
+		if let Some(target) = target {
+			self.link_to::<T>(holder, target);
+		}
+	}
+
+	fn link_to<T: Component>(&mut self, holder: &Entity, target: Entity) {
+		if !target.instance.is_null() {
+			target.get_instance(self.id);
+			let unlink = |registry: &mut EntityRegistry, holder: &Entity, target: usize| {
+				let linked = registry.get_component::<T>(holder).and_then(T::link_target);
+				if linked.is_some_and(|linked| linked.instance as usize == target) {
+					registry.remove_component::<T>(holder);
+				}
+			};
+			self.relationships.insert(target.instance as usize, holder.clone(), T::component_id(), unlink);
+		}
+	}
+
+	/// Unregisters the [entity](Entity)'s link of type `T`, if any.
+	fn unlink<T: Component>(&mut self, holder: &Entity) {
+		let target = self.get_component::<T>(holder).and_then(T::link_target).map(|t| t.instance as usize);
+		if let Some(target) = target {
+			self.relationships.remove(target, holder, T::component_id());
+		}
+	}
+
+	/// Starts recording which [entities](Entity) gain or lose a [component](Component) of type `T`.  
+	/// See [entities_entered](Self::entities_entered) and [entities_exited](Self::entities_exited).
+	pub fn track_structural_changes<T: Component>(&mut self) {
+		self.structural_changes.track(T::component_id());
+	}
+
+	/// Gets the [entities](Entity) that gained a [component](Component) of type `T` during the last tick,
+	/// by adding, swapping or moving to an [archetype](Archetype) containing it.  
+	/// [Entities](Entity) created with the [component](Component) are not included,
+	/// and the returned ones might have been destroyed since.
+	///
+	/// This function will panic if the changes of `T` are not [tracked](Self::track_structural_changes).
+	pub fn entities_entered<T: Component>(&self) -> &[Entity] {
+		let entered = self.structural_changes.entered(T::component_id());
+		entered.expect("Structural changes of the component are not tracked")
+	}
+
+	/// Gets the [entities](Entity) that lost a [component](Component) of type `T` during the last tick,
+	/// by removing, swapping or moving to an [archetype](Archetype) not containing it.  
+	/// Destroyed [entities](Entity) are not included.
+	///
+	/// This function will panic if the changes of `T` are not [tracked](Self::track_structural_changes).
+	pub fn entities_exited<T: Component>(&self) -> &[Entity] {
+		let exited = self.structural_changes.exited(T::component_id());
+		exited.expect("Structural changes of the component are not tracked")
+	}
+
+	/// Ends the current tick, making its structural changes visible through
+	/// [entities_entered](Self::entities_entered) and [entities_exited](Self::entities_exited)
+	/// until the next one ends.  
+	/// Called by [run_systems](crate::context::EcsContext::run_systems) once all [systems](crate::systems::System) have run.
+	pub fn advance_tick(&mut self) {
+		self.structural_changes.advance();
+		self.tick += 1;
+
+		let mut watches = std::mem::take(&mut self.watches);
+		watches.notify(self);
+		self.watches = watches;
+	}
+
+	/// Gets the number of ticks ended through [advance_tick](Self::advance_tick) so far.
+	pub fn tick(&self) -> u64 {
+		self.tick
+	}
+
+	/// Starts recording the tick at which a [component](Component) of type `T` is added to each [entity](Entity).  
+	/// See [component_added_tick](Self::component_added_tick).
+	pub fn track_added_ticks<T: Component>(&mut self) {
+		self.added_ticks.track(T::component_id());
+	}
+
+	/// Gets the [tick](Self::tick) during which the [component](Component) of type `T` was added to the specified [entity](Entity),
+	/// either when creating the [entity](Entity) or afterwards.  
+	/// Returns [None] if the [entity](Entity) does not have the [component](Component),
+	/// or if it was added before its ticks started being [tracked](Self::track_added_ticks).
+	///
+	/// Unlike [watch_component](Self::watch_component), mutations do not affect the recorded tick.
+	///
+	/// This function will panic if the added ticks of `T` are not [tracked](Self::track_added_ticks).
+	pub fn component_added_tick<T: Component>(&self, entity: &Entity) -> Option<u64> {
+		assert!(
+			self.added_ticks.is_tracked(T::component_id()),
+			"Added ticks of the component are not tracked"
+		);
+		entity.get_instance(self.id);
+		self.added_ticks.get(T::component_id(), entity.instance as usize)
+	}
+
+	/// Registers a function invoked with the [component](Component) of type `T` of a specific [entity](Entity)
+	/// whenever it might have changed.
+	///
+	/// The [component](Component) is considered changed when it's accessed through
+	/// [get_component_mut](Self::get_component_mut) or added to the [entity](Entity).
+	/// Mutations through queries are not detected.  
+	/// Callbacks are invoked at most once per tick, when it ends through [advance_tick](Self::advance_tick),
+	/// and only if the [entity](Entity) still has the [component](Component) by then.
+	/// The watch is dropped when the [entity](Entity) is destroyed.
+	pub fn watch_component<T: Component>(&mut self, entity: &Entity, mut callback: impl FnMut(&T) + 'static) {
+		entity.get_instance(self.id);
+		self.watches.watch(
+			entity,
+			T::component_id(),
+			Box::new(move |registry, entity| {
+				if let Some(value) = registry.get_component::<T>(entity) {
+					callback(value);
+				}
+			}),
+		);
+	}
+
+	/// Registers a function invoked with every [entity](Entity) created from now on,
+	/// once all of its [components](Component) have been initialized.
+	pub fn on_entity_created(&mut self, observer: impl FnMut(Entity) + 'static) {
+		self.created_observers.push(Box::new(observer));
+	}
 
-			self.capacity += size;
-			self.bitfield.reserve(size);
-			self.instance_buffers.push(instances);
-			buffer.fill_with(EntityInstance::default);
+	/// Registers a function invoked with every [entity](Entity) destroyed from now on,
+	/// right before it is invalidated.
+	pub fn on_entity_destroyed(&mut self, observer: impl FnMut(Entity) + 'static) {
+		self.destroyed_observers.push(Box::new(observer));
+	}
+
+	#[inline(always)]
+	fn record_entered(&mut self, id: ComponentId, entity: &Entity) {
+		if !self.structural_changes.is_empty() {
+			self.structural_changes.record_entered(id, entity);
+		}
+		if !self.watches.is_empty() {
+			self.watches.mark(entity, id);
+		}
+		if !self.added_ticks.is_empty() {
+			self.added_ticks.record_entered(id, entity.instance as usize, self.tick);
+		}
+	}
+
+	#[inline(always)]
+	fn record_exited(&mut self, id: ComponentId, entity: &Entity) {
+		if !self.structural_changes.is_empty() {
+			self.structural_changes.record_exited(id, entity);
+		}
+		if !self.added_ticks.is_empty() {
+			self.added_ticks.record_exited(id, entity.instance as usize);
+		}
+	}
+
+	#[inline(always)]
+	fn notify_created(&mut self, entity: &Entity) {
+		if !self.added_ticks.is_empty() {
+			let instance = entity.get_instance(self.id);
+			let table = self.archetype_store.get(instance.archetype).component_bitfield();
+			let sparse = &self.sparse_storage;
+			let key = entity.instance as usize;
+			let contains = |id| {
+				let in_sparse = || sparse.get_erased(ComponentId::from_value(id)).is_some_and(|s| s.contains_key(key));
+				table.get(id) || in_sparse()
+			};
+			self.added_ticks.record_created(key, self.tick, contains);
+		}
 
-			for i in 0..size {
-				self.available_instances.push(ptr.add(i));
+		for observer in self.created_observers.iter_mut() {
+			observer(entity.clone());
+		}
+	}
+
+	/// Removes the watches on, the added ticks of and the links targeting the [entities](Entity) whose instances
+	/// were released to `available_instances` starting from `start`.
+	fn unlink_destroyed(&mut self, start: usize) {
+		if !self.watches.is_empty() {
+			for instance in self.available_instances[start..].iter() {
+				self.watches.remove(*instance as usize);
+			}
+		}
+		if !self.added_ticks.is_empty() {
+			for instance in self.available_instances[start..].iter() {
+				self.added_ticks.remove(*instance as usize);
+			}
+		}
+
+		if self.relationships.is_empty() {
+			return;
+		}
+
+		let mut targets = self.usize_vec_pool.take_one();
+		targets.clear();
+		targets.extend(self.available_instances[start..].iter().map(|i| *i as usize));
+
+		for target in targets.iter() {
+			let links: Vec<_> = self.relationships.take(*target).collect();
+			for (holder, unlink) in links {
+				if self.is_alive(&holder) {
+					unlink(self, &holder, *target);
+				}
 			}
+		}
+	}
 
-			buffer
+	#[inline(always)]
+	fn is_alive(&self, entity: &Entity) -> bool {
+		// SAFETY: Instances are only freed by shrink_to_fit, which prunes the handles it invalidates.
+		entity.registry_id == self.id && unsafe { (*entity.instance).version == entity.version }
+	}
+
+	/// Checks if the [entity](Entity) is alive without assuming its instance is still allocated,
+	/// since [WeakEntity](crate::entities::WeakEntity) handles are not pruned by [shrink_to_fit](Self::shrink_to_fit).
+	pub(crate) fn is_alive_weak(&self, entity: &Entity) -> bool {
+		if entity.registry_id != self.id {
+			return false;
+		}
+
+		let instance = entity.instance as *const EntityInstance;
+		let allocated = self.instance_buffers.iter().any(|b| b.instances.as_ptr_range().contains(&instance));
+		allocated && unsafe { (*instance).version == entity.version }
+	}
+
+	/// Packs the [entity](Entity) as described by [Entity::to_bits].
+	pub(crate) fn entity_to_bits(&self, entity: &Entity) -> u64 {
+		if entity.is_null() {
+			return 0;
+		}
+
+		assert_eq!(entity.registry_id, self.id, "Entity does not belong to this context");
+		let instance = entity.instance as *const EntityInstance;
+		let buffer = self.instance_buffers.iter().find(|b| b.instances.as_ptr_range().contains(&instance));
+		let buffer = buffer.expect("Entity instance has been freed");
+
+		// Indices are offset by one, so that null entities are the only ones packed as 0
+		let index = buffer.index + unsafe { instance.offset_from(buffer.ptr) } as usize + 1;
+		let index = u32::try_from(index).expect("Entity index does not fit in 32 bits");
+		((index as u64) << 32) | entity.version as u64
+	}
+
+	/// Unpacks an [entity](Entity) as described by [Entity::from_bits].
+	pub(crate) fn entity_from_bits(&self, bits: u64) -> Entity {
+		let (index, version) = ((bits >> 32) as usize, bits as u32);
+		let Some(index) = index.checked_sub(1) else {
+			return Entity::null();
+		};
+
+		let buffer = self.instance_buffers.iter().find(|b| (b.index..b.index + b.instances.len()).contains(&index));
+		match buffer {
+			None => Entity::null(),
+			Some(buffer) => Entity {
+				version,
+				registry_id: self.id,
+				instance: unsafe { buffer.ptr.add(index - buffer.index) },
+			},
 		}
 	}
 
+	/// Checks which of the specified [entities](Entity) are alive, without panicking on invalid ones.
+	///
+	/// Useful to validate externally supplied [entities](Entity) before operating on them,
+	/// as most other functions panic when given a destroyed [entity](Entity).  
+	/// The returned mask has the same length and order as `entities`.
+	pub fn validate_entities(&self, entities: &[Entity]) -> Vec<bool> {
+		// Instance buffers are sorted by address, so that each entity can be located with a binary search.
+		let mut buffers: Vec<_> = self.instance_buffers.iter().map(|b| b.instances.as_ptr_range()).collect();
+		buffers.sort_unstable_by_key(|b| b.start);
+
+		let is_allocated = |instance: *const EntityInstance| {
+			let i = buffers.partition_point(|b| b.start <= instance);
+			i != 0 && buffers[i - 1].contains(&instance)
+		};
+
+		let is_alive = |entity: &Entity| {
+			entity.registry_id == self.id
+				&& is_allocated(entity.instance)
+				&& unsafe { (*entity.instance).version == entity.version }
+		};
+
+		entities.iter().map(is_alive).collect()
+	}
+
+	#[inline(always)]
+	fn assert_archetype(&self, archetype: Archetype) {
+		assert!(
+			archetype.registry_id == self.id || archetype == Archetype::default(),
+			"Archetype does not belong to this context"
+		);
+		assert!(
+			!self.archetype_store.is_collected(archetype.index),
+			"Archetype has been collected"
+		);
+	}
+
 	#[inline(never)]
 	fn apply_archetype_transition(
 		&mut self, entity: &Entity, component: ComponentType, kind: ArchetypeTransitionKind,
@@ -258,9 +1529,7 @@ impl EntityRegistry {
 		let instance = entity.get_instance_mut(self.id);
 
 		let transition = self.archetype_store.get_archetype_transition(ArchetypeTransition {
-			archetype: Archetype {
-				index: instance.archetype,
-			},
+			archetype: self.archetype_store.get(instance.archetype).id(),
 			component,
 			kind,
 		});
@@ -270,7 +1539,7 @@ impl EntityRegistry {
 			Some((src, dst)) => (src, dst),
 		};
 
-		let src_slot = instance.slot as usize;
+		let src_slot = instance.slot;
 		instance.archetype = dst.id().index;
 
 		let dst_slot = {
@@ -282,6 +1551,8 @@ impl EntityRegistry {
 			slot
 		};
 
+		dst.entities_mut()[dst_slot] = entity.clone();
+
 		// SAFETY: Always safe.
 		// Ownership of all components is transferred to the destination archetype, so we don't call drop on them.
 		// The component data in the source archetype can be safely overwritten by subsequent allocations.
@@ -299,11 +1570,19 @@ impl EntityRegistry {
 /// It defines the set of [components](Component) an [entity](Entity) must or must not include.
 pub struct EntityFilter<'l, I: 'static + ComponentSet, E: 'static + ComponentSet> {
 	entity_store: &'l mut EntityRegistry,
+	extra_include: BitField,
+	extra_exclude: BitField,
+	include_disabled: bool,
+	limit: usize,
 	i_phantom: PhantomData<&'l I>,
 	e_phantom: PhantomData<&'l E>,
 }
 
 /// It allows for iteration over a set of matching [entities](Entity) in an [EntityFilter].
+///
+/// Within each [archetype](Archetype), [entities](Entity) are visited in ascending slot order.
+/// Slots freed by destroyed [entities](Entity) are reused by the next ones created,
+/// so the order is deterministic for a given sequence of operations, but it may not match creation order.
 pub trait EntityFilterForEach<I: 'static + ComponentSet, E: 'static + ComponentSet>
 where
 	ArchetypeInstance: IterArchetype<I>,
@@ -311,8 +1590,25 @@ where
 	/// Iterate all matching entities with the provided function.
 	fn for_each(self, func: impl FnMut(<(I, E) as ComponentQuery>::Arguments));
 
+	/// Iterate all matching entities with the provided function, in the same order as [for_each](Self::for_each).
+	///
+	/// [Entities](Entity) are processed in tiles of 256, issuing a software prefetch for the
+	/// [table](crate::components::ComponentStorage::Table) [components](Component) of the next tile
+	/// before processing the current one.
+	/// This can reduce cache misses in memory-bound iterations over large [archetypes](Archetype).  
+	/// Prefetching is skipped on architectures without stable prefetch intrinsics.
+	fn for_each_prefetched(self, func: impl FnMut(<(I, E) as ComponentQuery>::Arguments));
+
 	/// Iterate all matching entities with the provided function.
+	///
+	/// The [entities](Entity) passed to `func` are their current handles, which can be stored for later use.
 	fn entities_for_each(self, func: impl FnMut(Entity, <(I, E) as ComponentQuery>::Arguments));
+
+	/// Iterate matching entities with the provided function until it returns [ControlFlow::Break].
+	fn try_for_each(self, func: impl FnMut(<(I, E) as ComponentQuery>::Arguments) -> ControlFlow<()>);
+
+	/// Iterate matching entities with the provided function until it returns [ControlFlow::Break].
+	fn try_entities_for_each(self, func: impl FnMut(Entity, <(I, E) as ComponentQuery>::Arguments) -> ControlFlow<()>);
 }
 
 /// It allows for parallel iteration over a set of matching [entities](Entity) in an [EntityFilter].
@@ -321,34 +1617,219 @@ where
 	ArchetypeInstance: IterArchetypeParallel<I>,
 {
 	/// Iterate all matching entities in parallel with the provided function.
-	fn par_for_each(self, func: (impl Fn(<(I, E) as ComponentQuery>::Arguments) + Send + Sync));
+	fn par_for_each(self, func: impl Fn(<(I, E) as ComponentQuery>::Arguments) + Send + Sync);
 
 	/// Iterate all matching entities in parallel with the provided function.
-	fn par_entities_for_each(self, func: (impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) + Send + Sync));
+	///
+	/// The [entities](Entity) passed to `func` are their current handles, which can be stored for later use.
+	fn par_entities_for_each(self, func: impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) + Send + Sync);
+
+	/// Aggregate all matching entities in parallel.
+	///
+	/// Each [archetype](Archetype) is split into chunks, which are accumulated with `fold`
+	/// starting from a value created by `identity`. Partial results are then merged with `reduce`.  
+	/// `reduce` must be associative and `identity` must be its neutral element,
+	/// as the way entities are partitioned into chunks is unspecified.
+	fn par_fold<A: Send>(
+		self, identity: impl Fn() -> A + Send + Sync,
+		fold: impl Fn(A, <(I, E) as ComponentQuery>::Arguments) -> A + Send + Sync,
+		reduce: impl Fn(A, A) -> A + Send + Sync,
+	) -> A;
+
+	/// Maps all matching entities in parallel, collecting the results.
+	///
+	/// Results are in the same order as the entities visited by
+	/// [entities_for_each](EntityFilterForEach::entities_for_each), regardless of how the work is split between threads.
+	fn par_map_collect<R: Send>(
+		self, func: impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) -> R + Send + Sync,
+	) -> Vec<R>;
 }
 
 impl<'l, I: 'static + ComponentSet, E: 'static + ComponentSet> EntityFilter<'l, I, E> {
 	/// It specifies which [components](Component) an [entity](Entity) must include to be picked up by the [EntityFilter].  
-	/// This function creates a new [EntityFilter] each time it's invoked, so it should ideally only be called once
-	/// with all the desired [component](Component) types.
-	pub fn include<TI: 'static + ComponentSet>(self) -> EntityFilter<'l, TI, E> {
+	/// Repeated calls accumulate: [components](Component) included by previous calls are still required,
+	/// but only the ones specified by the last call are passed to the iteration functions.
+	pub fn include<TI: 'static + ComponentSet>(mut self) -> EntityFilter<'l, TI, E> {
+		self.extra_include.union_with(&I::get_bitfield().0);
 		EntityFilter {
 			entity_store: self.entity_store,
-			i_phantom: PhantomData::default(),
-			e_phantom: PhantomData::default(),
+			extra_include: self.extra_include,
+			extra_exclude: self.extra_exclude,
+			include_disabled: self.include_disabled,
+			limit: self.limit,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
 		}
 	}
 
 	/// It specifies which [components](Component) an [entity](Entity) must not include to be picked up by the [EntityFilter].  
-	/// This function creates a new [EntityFilter] each time it's invoked, so it should ideally only be called once
-	/// with all the desired [component](Component) types.
-	pub fn exclude<TE: 'static + ComponentSet>(self) -> EntityFilter<'l, I, TE> {
+	/// Repeated calls accumulate: [components](Component) excluded by previous calls are still excluded.
+	pub fn exclude<TE: 'static + ComponentSet>(mut self) -> EntityFilter<'l, I, TE> {
+		self.extra_exclude.union_with(&E::get_bitfield().0);
 		EntityFilter {
 			entity_store: self.entity_store,
-			i_phantom: PhantomData::default(),
-			e_phantom: PhantomData::default(),
+			extra_include: self.extra_include,
+			extra_exclude: self.extra_exclude,
+			include_disabled: self.include_disabled,
+			limit: self.limit,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
+		}
+	}
+
+	/// It makes the [EntityFilter] pick up the [entities](Entity) including at least one of the [components](Component) in `A`.  
+	/// [Components](Component) included by previous calls are still required, but only the ones in `A` are passed
+	/// to the iteration functions, each wrapped in an [Option] as a matched [entity](Entity) may lack some of them.
+	pub fn include_any<A: 'static + ComponentSet>(self) -> AnyFilter<'l, A>
+	where
+		ArchetypeInstance: ArchetypeFetchAny<'l, A>,
+	{
+		let query = self.query();
+		AnyFilter::new(self.entity_store, query, self.limit)
+	}
+
+	/// Makes the [EntityFilter] pick up [disabled](crate::entities::Disabled) [entities](Entity),
+	/// which are otherwise skipped.
+	pub fn include_disabled(mut self) -> Self {
+		self.include_disabled = true;
+		self
+	}
+
+	/// Limits the number of [entities](Entity) visited by the [EntityFilter] to at most `n`.  
+	/// Sequential iteration stops as soon as the limit is reached;
+	/// parallel iteration still visits every matching [entity](Entity), but only invokes the function `n` times.
+	pub fn take(mut self, n: usize) -> Self {
+		self.limit = usize::min(self.limit, n);
+		self
+	}
+
+	/// Gets the number of [entities](Entity) the [EntityFilter] will visit, without iterating them.
+	///
+	/// The hint is exact, unless the query involves [sparse](ComponentStorage::Sparse) [components](Component),
+	/// in which case it is an upper bound, as their presence is only checked while iterating.
+	pub fn len_hint(&self) -> usize {
+		let store = &self.entity_store.archetype_store;
+		let count: usize = store.query_shared(self.query()).map(|archetype| archetype.len()).sum();
+		usize::min(count, self.limit)
+	}
+
+	/// Iterate all matching entities with the provided function,
+	/// then remove the [component](Component) of type `T` from every visited [entity](Entity).
+	///
+	/// Removals are queued during iteration and applied once it is over,
+	/// so no [archetype](Archetype) is modified while it is being iterated.  
+	/// The visited [entities](Entity) of each [archetype](Archetype) are then moved out of it in a single batch.
+	pub fn for_each_then_remove<T: Component>(self, mut func: impl FnMut(<(I, E) as ComponentQuery>::Arguments))
+	where
+		ArchetypeInstance: IterArchetype<I>,
+	{
+		let EntityFilter {
+			entity_store,
+			extra_include,
+			extra_exclude,
+			include_disabled,
+			limit,
+			..
+		} = self;
+
+		let mut visited = entity_store.entity_vec_pool.take_one();
+		visited.clear();
+
+		let filter = EntityFilter::<I, E> {
+			entity_store: &mut *entity_store,
+			extra_include,
+			extra_exclude,
+			include_disabled,
+			limit,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
+		};
+
+		filter.entities_for_each(|entity, args| {
+			func(args);
+			visited.push(entity);
+		});
+
+		entity_store.remove_component_batch::<T>(&visited);
+	}
+
+	/// Collects the matching [entities](Entity) into a [Vec] borrowed from the registry.  
+	/// The [Vec] is returned to the registry when dropped, so its storage is reused by later calls
+	/// instead of being allocated every time.
+	pub fn collect_entities(self) -> PoolBorrow<Vec<Entity>>
+	where
+		ArchetypeInstance: IterArchetype<I>,
+	{
+		let len = self.len_hint();
+		let mut entities = self.entity_store.entity_vec_pool.take_one();
+		entities.clear();
+		entities.reserve(len);
+		self.entities_for_each(|entity, _| entities.push(entity));
+		entities
+	}
+
+	/// Creates an iterator over the matching entities for which `predicate` returns `true`.
+	///
+	/// It allows filtering by [component](Component) values rather than just by their presence.
+	/// Limits set through [EntityFilter::take] count the entities visited before `predicate` is applied;
+	/// use [Iterator::take] on the result to limit the entities passing it instead.
+	pub fn filter_value<P: FnMut(&<ArchetypeInstance as ArchetypeFetch<'l, I>>::Item) -> bool>(
+		self, predicate: P,
+	) -> Filter<QueryIter<'l, I>, P>
+	where
+		ArchetypeInstance: ArchetypeFetch<'l, I>,
+	{
+		self.into_iter().filter(predicate)
+	}
+
+	/// Iterate all matching entities with the provided function, in ascending order of the key extracted by `key`.  
+	/// Entities with equal keys are visited in the usual iteration order.
+	///
+	/// Since [components](Component) are not stored sorted, all matching entities are gathered into
+	/// a temporary buffer before `func` is invoked.  
+	/// That buffer and the indices being sorted are borrowed from the registry, so their storage is reused across calls,
+	/// and `key` may be invoked several times per entity.
+	pub fn for_each_sorted_by<K: Ord>(
+		self, mut key: impl FnMut(&<ArchetypeInstance as ArchetypeFetch<'l, I>>::Item) -> K,
+		mut func: impl FnMut(<ArchetypeInstance as ArchetypeFetch<'l, I>>::Item),
+	) where
+		ArchetypeInstance: ArchetypeFetch<'l, I>,
+	{
+		let mut order = self.entity_store.usize_vec_pool.take_one();
+		let mut scratch = self.entity_store.scratch_pool.take_one();
+		let mut items = scratch.as_vec(self.len_hint());
+		self.into_iter().for_each(|item| items.push(Some(item)));
+
+		// Ties are broken by index, so the unstable sort keeps equal keys in iteration order without a scratch buffer
+		order.clear();
+		order.extend(0..items.len());
+		order.sort_unstable_by(|a, b| {
+			let (ka, kb) = (key(items[*a].as_ref().unwrap()), key(items[*b].as_ref().unwrap()));
+			ka.cmp(&kb).then(a.cmp(b))
+		});
+
+		for i in order.iter() {
+			func(items[*i].take().unwrap());
 		}
 	}
+
+	fn query(&self) -> EntityQuery {
+		let query = <(I, E)>::get_query();
+		if self.extra_include.is_empty() && self.extra_exclude.is_empty() && !self.include_disabled {
+			return query;
+		}
+
+		let data = crate::entities::get_query_data(query);
+		let mut include = data.include().clone();
+		let mut exclude = data.exclude().clone();
+		crate::entities::disabled::include_disabled(&mut exclude);
+		include.union_with(&self.extra_include);
+		exclude.union_with(&self.extra_exclude);
+		if !self.include_disabled {
+			crate::entities::disabled::exclude_disabled(&include, &mut exclude);
+		}
+		crate::entities::get_query_from_bitfields(include, exclude)
+	}
 }
 
 impl<I: 'static + ComponentSet, E: 'static + ComponentSet> EntityFilterForEach<I, E> for EntityFilter<'_, I, E>
@@ -356,16 +1837,79 @@ where
 	ArchetypeInstance: IterArchetype<I>,
 {
 	fn for_each(self, mut func: impl FnMut(<(I, E) as ComponentQuery>::Arguments)) {
-		let query = <(I, E)>::get_query();
-		for archetype in self.entity_store.archetype_store.query(query) {
-			IterArchetype::for_each(archetype, &mut func);
+		if self.limit != usize::MAX {
+			return self.try_for_each(|args| {
+				func(args);
+				ControlFlow::Continue(())
+			});
+		}
+
+		let query = self.query();
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+		for archetype in store.archetype_store.query_populated(query) {
+			IterArchetype::for_each(archetype, &mut store.sparse_storage, &filter, &mut func);
+		}
+	}
+
+	fn for_each_prefetched(self, mut func: impl FnMut(<(I, E) as ComponentQuery>::Arguments)) {
+		if self.limit != usize::MAX {
+			return self.for_each(func);
+		}
+
+		let query = self.query();
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+		for archetype in store.archetype_store.query_populated(query) {
+			IterArchetype::for_each_prefetched(archetype, &mut store.sparse_storage, &filter, &mut func);
 		}
 	}
 
 	fn entities_for_each(self, mut func: impl FnMut(Entity, <(I, E) as ComponentQuery>::Arguments)) {
-		let query = <(I, E)>::get_query();
-		for archetype in self.entity_store.archetype_store.query(query) {
-			IterArchetype::entities_for_each(archetype, &mut func);
+		if self.limit != usize::MAX {
+			return self.try_entities_for_each(|entity, args| {
+				func(entity, args);
+				ControlFlow::Continue(())
+			});
+		}
+
+		let query = self.query();
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+		for archetype in store.archetype_store.query_populated(query) {
+			IterArchetype::entities_for_each(archetype, &mut store.sparse_storage, &filter, &mut func);
+		}
+	}
+
+	fn try_for_each(self, mut func: impl FnMut(<(I, E) as ComponentQuery>::Arguments) -> ControlFlow<()>) {
+		self.try_entities_for_each(|_, args| func(args));
+	}
+
+	fn try_entities_for_each(
+		self, mut func: impl FnMut(Entity, <(I, E) as ComponentQuery>::Arguments) -> ControlFlow<()>,
+	) {
+		let query = self.query();
+		let mut remaining = self.limit;
+		if remaining == 0 {
+			return;
+		}
+
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+		let mut func = |entity, args| {
+			func(entity, args)?;
+			remaining -= 1;
+			match remaining {
+				0 => ControlFlow::Break(()),
+				_ => ControlFlow::Continue(()),
+			}
+		};
+
+		for archetype in store.archetype_store.query_populated(query) {
+			if IterArchetype::try_entities_for_each(archetype, &mut store.sparse_storage, &filter, &mut func).is_break()
+			{
+				return;
+			}
 		}
 	}
 }
@@ -374,21 +1918,127 @@ impl<I: 'static + ComponentSet, E: 'static + ComponentSet> EntityFilterParallelF
 where
 	ArchetypeInstance: IterArchetypeParallel<I>,
 {
-	fn par_for_each(self, func: (impl Fn(<(I, E) as ComponentQuery>::Arguments) + Send + Sync)) {
-		let query = <(I, E)>::get_query();
+	fn par_for_each(self, func: impl Fn(<(I, E) as ComponentQuery>::Arguments) + Send + Sync) {
+		if self.limit != usize::MAX {
+			return self.par_entities_for_each(|_, args| func(args));
+		}
 
-		self.entity_store
-			.archetype_store
-			.query(query)
-			.for_each(|archetype| IterArchetypeParallel::for_each(archetype, &func));
+		let query = self.query();
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+
+		store.archetype_store.query_populated(query).for_each(|archetype| {
+			IterArchetypeParallel::for_each(archetype, &mut store.sparse_storage, &filter, &func)
+		});
 	}
 
-	fn par_entities_for_each(self, func: (impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) + Send + Sync)) {
-		let query = <(I, E)>::get_query();
+	fn par_entities_for_each(self, func: impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) + Send + Sync) {
+		let query = self.query();
+		let limit = self.limit;
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+
+		if limit == usize::MAX {
+			store.archetype_store.query_populated(query).for_each(|archetype| {
+				IterArchetypeParallel::entities_for_each(archetype, &mut store.sparse_storage, &filter, &func)
+			});
+			return;
+		}
 
-		self.entity_store
-			.archetype_store
-			.query(query)
-			.for_each(|archetype| IterArchetypeParallel::entities_for_each(archetype, &func));
+		let visited = AtomicUsize::new(0);
+		let func = |entity, args| {
+			if visited.fetch_add(1, Ordering::Relaxed) < limit {
+				func(entity, args);
+			}
+		};
+		for archetype in store.archetype_store.query_populated(query) {
+			if visited.load(Ordering::Relaxed) >= limit {
+				return;
+			}
+			IterArchetypeParallel::entities_for_each(archetype, &mut store.sparse_storage, &filter, &func);
+		}
+	}
+
+	fn par_fold<A: Send>(
+		self, identity: impl Fn() -> A + Send + Sync,
+		fold: impl Fn(A, <(I, E) as ComponentQuery>::Arguments) -> A + Send + Sync,
+		reduce: impl Fn(A, A) -> A + Send + Sync,
+	) -> A {
+		let query = self.query();
+		let limit = self.limit;
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+
+		let visited = AtomicUsize::new(0);
+		let fold = |acc, args| match limit == usize::MAX || visited.fetch_add(1, Ordering::Relaxed) < limit {
+			true => fold(acc, args),
+			false => acc,
+		};
+
+		let mut result = identity();
+		for archetype in store.archetype_store.query_populated(query) {
+			let partial =
+				IterArchetypeParallel::fold(archetype, &mut store.sparse_storage, &filter, &identity, &fold, &reduce);
+			result = reduce(result, partial);
+		}
+		result
+	}
+
+	fn par_map_collect<R: Send>(
+		self, func: impl Fn(Entity, <(I, E) as ComponentQuery>::Arguments) -> R + Send + Sync,
+	) -> Vec<R> {
+		let mut results = Vec::with_capacity(self.len_hint());
+		let query = self.query();
+		let limit = self.limit;
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+
+		for archetype in store.archetype_store.query_populated(query) {
+			if results.len() >= limit {
+				break;
+			}
+			IterArchetypeParallel::map_collect(archetype, &mut store.sparse_storage, &filter, &func, &mut results);
+		}
+
+		// The last archetype is mapped as a whole, the results past the limit are discarded.
+		results.truncate(limit);
+		results
+	}
+}
+
+/// Iterate all matching entities with a plain `for` loop.
+///
+/// ```
+/// use turbo_ecs::prelude::*;
+///
+/// #[derive(Default, Component)]
+/// struct Position(f32);
+///
+/// #[derive(Default, Component)]
+/// struct Velocity(f32);
+///
+/// let mut ecs = EcsContext::new();
+/// let archetype = create_archetype!(ecs, [Position, Velocity]);
+/// ecs.create_entities_from_archetype(archetype, 4);
+///
+/// for (position, velocity) in ecs.filter().include::<(&mut Position, &Velocity)>() {
+///     position.0 += velocity.0;
+/// }
+/// ```
+impl<'l, I: 'static + ComponentSet, E: 'static + ComponentSet> IntoIterator for EntityFilter<'l, I, E>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	type Item = <ArchetypeInstance as ArchetypeFetch<'l, I>>::Item;
+	type IntoIter = QueryIter<'l, I>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		let query = self.query();
+		let limit = self.limit;
+		let store = self.entity_store;
+		let filter = SparseFilter::new(&store.sparse_storage, &crate::entities::get_query_data(query));
+		let archetypes = store.archetype_store.query_populated(query).map(|a| a as *mut ArchetypeInstance).collect();
+		// SAFETY: The registry is mutably borrowed for 'l.
+		unsafe { QueryIter::new(&mut store.sparse_storage, filter, archetypes, limit) }
 	}
 }
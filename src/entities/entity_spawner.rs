@@ -0,0 +1,65 @@
+use crate::entities::{Entity, EntityRegistry};
+use crate::components::{Component, ComponentType};
+
+type Write = Box<dyn FnOnce(&mut EntityRegistry, &Entity)>;
+
+/// Builds an [entity](Entity) one [component](Component) at a time.
+///
+/// [Components](Component) are accumulated and the [entity](Entity) is only created when [EntitySpawner::id] is called,
+/// directly in the [archetype](crate::archetypes::Archetype) containing all of them.
+/// No intermediate [archetypes](crate::archetypes::Archetype) are created or transitioned through,
+/// regardless of the number of inserted [components](Component).
+#[must_use = "The entity is only created when `id` is called"]
+pub struct EntitySpawner<'l> {
+	registry: &'l mut EntityRegistry,
+	components: Vec<ComponentType>,
+	writes: Vec<Write>,
+}
+
+impl EntityRegistry {
+	/// Starts building an [entity](Entity) with no [components](Component) attached.
+	///
+	/// See [EntitySpawner] for when the [entity](Entity) is created.
+	pub fn spawn_empty(&mut self) -> EntitySpawner<'_> {
+		EntitySpawner {
+			registry: self,
+			components: vec![],
+			writes: vec![],
+		}
+	}
+}
+
+impl EntitySpawner<'_> {
+	/// Adds a [component](Component) to the pending [entity](Entity).
+	/// Inserting a [component](Component) type more than once replaces the previous value.
+	pub fn insert<T: Component>(mut self, value: T) -> Self {
+		let write: Write = Box::new(move |registry, entity| unsafe { registry.write_component(entity, value) });
+
+		let component = ComponentType::of::<T>();
+		match self.components.iter().position(|c| *c == component) {
+			Some(i) => self.writes[i] = write,
+			None => {
+				self.components.push(component);
+				self.writes.push(write);
+			},
+		}
+
+		self
+	}
+
+	/// Creates the [entity](Entity) with all inserted [components](Component), in a single structural change.
+	pub fn id(self) -> Entity {
+		let registry = self.registry;
+		let archetype = registry.archetype_store.create_archetype(&self.components);
+
+		// SAFETY: The archetype contains exactly the inserted components, which are all written before finishing.
+		unsafe {
+			let entity = registry.create_entity_uninit(archetype);
+			for write in self.writes {
+				write(registry, &entity);
+			}
+			registry.finish_entity(&entity);
+			entity
+		}
+	}
+}
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+/// A summary of the memory allocated by an [EntityRegistry](crate::entities::EntityRegistry).
+///
+/// It can be obtained through [EntityRegistry::memory_stats](crate::entities::EntityRegistry::memory_stats).
+/// All sizes are in bytes and account for allocated capacity, not just live [entities](crate::entities::Entity).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStats {
+	/// The bytes allocated for the [component](crate::components::Component) columns of all
+	/// [archetypes](crate::archetypes::Archetype).
+	pub archetype_bytes: usize,
+	/// The bytes allocated for [entity](crate::entities::Entity) instances.
+	pub instance_bytes: usize,
+	/// The bytes allocated for each [component](crate::components::Component) type across all
+	/// [archetypes](crate::archetypes::Archetype), keyed by the type's name.
+	pub components: HashMap<&'static str, usize>,
+}
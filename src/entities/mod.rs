@@ -5,10 +5,42 @@
 //!
 //! TODO
 
+mod access_error;
+mod any_filter;
+mod added_ticks;
+mod command_buffer;
+mod component_watches;
+mod disabled;
 mod entity_query;
+mod entity_ref;
 mod entity_registry;
+mod entity_spawner;
 mod entity_instance;
+mod memory_stats;
+mod query_explain;
+mod query_iter;
+mod query_macros;
+mod query_set;
+mod registry_view;
+mod relationships;
+mod structural_changes;
 
+pub use access_error::*;
+pub use any_filter::AnyFilter;
+pub use command_buffer::*;
+pub use disabled::Disabled;
 pub use entity_query::*;
+pub use entity_ref::*;
 pub use entity_registry::*;
+pub use entity_spawner::*;
 pub use entity_instance::*;
+pub use memory_stats::*;
+pub use query_explain::*;
+pub use query_iter::*;
+pub use query_set::*;
+pub use registry_view::*;
+pub use relationships::Link;
+pub(crate) use relationships::RelationshipRegistry;
+pub(crate) use structural_changes::StructuralChanges;
+pub(crate) use component_watches::ComponentWatches;
+pub(crate) use added_ticks::AddedTicks;
@@ -0,0 +1,55 @@
+use crate::components::ComponentId;
+use crate::data_structures::BitField;
+use crate::archetypes::Archetype;
+use std::fmt::{Display, Formatter};
+
+/// A description of which [archetypes](Archetype) are matched by an [EntityQuery](crate::entities::EntityQuery).
+///
+/// It can be obtained through [EntityRegistry::explain_query](crate::entities::EntityRegistry::explain_query).
+#[derive(Debug, Clone)]
+pub struct QueryExplain {
+	/// The names of the [components](crate::components::Component) an [entity](crate::entities::Entity) must include.
+	pub include: Vec<&'static str>,
+	/// The names of the [components](crate::components::Component) an [entity](crate::entities::Entity) must not include.
+	pub exclude: Vec<&'static str>,
+	/// Whether the query has already been matched against the existing [archetypes](Archetype),
+	/// either by iterating it or by [warming](crate::prelude::EcsContext::warm_query) it.
+	pub cached: bool,
+	/// The matched [archetypes](Archetype).
+	pub archetypes: Vec<ArchetypeExplain>,
+}
+
+/// A description of an [Archetype] matched by an [EntityQuery](crate::entities::EntityQuery).
+#[derive(Debug, Clone)]
+pub struct ArchetypeExplain {
+	/// The handle of the [Archetype].
+	pub archetype: Archetype,
+	/// The names of the [components](crate::components::Component) contained in the [Archetype].
+	pub components: Vec<&'static str>,
+	/// The number of [entities](crate::entities::Entity) currently belonging to the [Archetype].
+	pub entities: usize,
+}
+
+pub(crate) fn component_names(bitfield: &BitField) -> Vec<&'static str> {
+	let ids = bitfield.iter_ranges().flatten();
+	ids.map(|i| ComponentId::from_value(i).name()).collect()
+}
+
+impl Display for QueryExplain {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "include: [{}]", self.include.join(", "))?;
+		writeln!(f, "exclude: [{}]", self.exclude.join(", "))?;
+		writeln!(f, "cached: {}", self.cached)?;
+		writeln!(f, "matched archetypes: {}", self.archetypes.len())?;
+		for archetype in &self.archetypes {
+			writeln!(
+				f,
+				"  {:?} [{}]: {} entities",
+				archetype.archetype,
+				archetype.components.join(", "),
+				archetype.entities
+			)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,128 @@
+use crate::archetypes::{ArchetypeFetch, ArchetypeInstance, SparseFilter};
+use crate::components::SparseStorage;
+use crate::entities::{Entity, EntityRegistry};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::vec;
+
+/// An iterator over the [components](crate::components::Component) of the [entities](Entity)
+/// matched by an [EntityFilter](crate::entities::EntityFilter).
+///
+/// The iterator mutably borrows the [EntityRegistry] for its whole lifetime.
+pub struct QueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	sparse: *mut SparseStorage,
+	filter: SparseFilter,
+	archetypes: vec::IntoIter<*mut ArchetypeInstance>,
+	columns: Option<<ArchetypeInstance as ArchetypeFetch<'l, I>>::Columns>,
+	entities: *const Entity,
+	ranges: vec::IntoIter<Range<usize>>,
+	slots: Range<usize>,
+	remaining: usize,
+	phantom: PhantomData<&'l mut EntityRegistry>,
+}
+
+impl<'l, I> QueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	/// # Safety
+	/// `sparse` and `archetypes` must be valid for `'l`, and the [components](crate::components::Component)
+	/// written by `I` must not be accessed by anything else during `'l`.
+	pub(crate) unsafe fn new(
+		sparse: *mut SparseStorage, filter: SparseFilter, archetypes: Vec<*mut ArchetypeInstance>, limit: usize,
+	) -> Self {
+		Self {
+			sparse,
+			filter,
+			archetypes: archetypes.into_iter(),
+			columns: None,
+			entities: std::ptr::null(),
+			ranges: Vec::new().into_iter(),
+			slots: 0..0,
+			remaining: limit,
+			phantom: PhantomData,
+		}
+	}
+}
+
+impl<'l, I> QueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	/// Makes the iterator yield the [entity](Entity) of each item alongside it,
+	/// like [entities_for_each](crate::entities::EntityFilterForEach::entities_for_each).
+	pub fn with_entities(self) -> EntityQueryIter<'l, I> {
+		EntityQueryIter { iter: self }
+	}
+
+	fn next_entry(&mut self) -> Option<(&'l Entity, <ArchetypeInstance as ArchetypeFetch<'l, I>>::Item)> {
+		if self.remaining == 0 {
+			return None;
+		}
+
+		loop {
+			if let Some(columns) = &self.columns {
+				for slot in self.slots.by_ref() {
+					unsafe {
+						if !self.filter.is_empty() && !self.filter.matches(self.entities, slot) {
+							continue;
+						}
+						if let Some(item) = ArchetypeInstance::fetch(columns, self.entities, slot) {
+							self.remaining -= 1;
+							return Some((&*self.entities.add(slot), item));
+						}
+					}
+				}
+
+				if let Some(range) = self.ranges.next() {
+					self.slots = range;
+					continue;
+				}
+			}
+
+			// Other iterators may be accessing the same archetype, so it's only borrowed immutably
+			let archetype = self.archetypes.next()?;
+			let instance = unsafe { &*archetype };
+			self.entities = instance.entities_ptr();
+			self.ranges = instance.used_ranges().collect::<Vec<_>>().into_iter();
+			self.slots = 0..0;
+			self.columns = Some(unsafe { ArchetypeInstance::columns(archetype, self.sparse) });
+		}
+	}
+}
+
+impl<'l, I> Iterator for QueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	type Item = <ArchetypeInstance as ArchetypeFetch<'l, I>>::Item;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.next_entry().map(|(_, item)| item)
+	}
+}
+
+/// A [QueryIter] also yielding the [entity](Entity) each item belongs to, created by [QueryIter::with_entities].
+///
+/// The yielded [entities](Entity) are the current handles stored by their [archetype](crate::archetypes::Archetype),
+/// so they remain valid after the iteration ends, until the [entity](Entity) is destroyed.
+pub struct EntityQueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	iter: QueryIter<'l, I>,
+}
+
+impl<'l, I> Iterator for EntityQueryIter<'l, I>
+where
+	ArchetypeInstance: ArchetypeFetch<'l, I>,
+{
+	type Item = (Entity, <ArchetypeInstance as ArchetypeFetch<'l, I>>::Item);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.iter.next_entry().map(|(entity, item)| (entity.clone(), item))
+	}
+}
@@ -0,0 +1,38 @@
+/// Create a query from a readable list of [components](crate::components::Component):
+/// `query!(&mut A, &B; with C; without D)`.
+///
+/// The listed references are the arguments passed to the iteration functions,
+/// the [components](crate::components::Component) after `with` must be present without being passed,
+/// and the ones after `without` must be absent. Both clauses are optional, but must appear in this order.
+///
+/// On its own, the macro creates the matching [EntityQuery](crate::entities::EntityQuery).  
+/// Ending it with `; in ecs` creates an [EntityFilter](crate::entities::EntityFilter) over `ecs` instead,
+/// equivalent to `ecs.filter().include::<(C,)>().exclude::<(D,)>().include::<(&mut A, &B)>()`.
+#[macro_export]
+macro_rules! query {
+	(@args $arg: ty) => {
+		$arg
+	};
+
+	(@args $($arg: ty),+) => {
+		($($arg),+)
+	};
+
+	($($arg: ty),+ $(; with $($with: ty),+)? $(; without $($without: ty),+)?; in $ecs: expr $(,)?) => {
+		$ecs.filter()
+			.include::<($($($with,)+)?)>()
+			.exclude::<($($($without,)+)?)>()
+			.include::<$crate::query!(@args $($arg),+)>()
+	};
+
+	($($arg: ty),+ $(; with $($with: ty),+)? $(; without $($without: ty),+)? $(,)?) => {
+		$crate::entities::EntityQuery::build()
+			.include::<($($arg,)+ $($($with,)+)?)>()
+			.exclude::<($($($without,)+)?)>()
+			.create()
+	};
+
+	($($tokens: tt)*) => {
+		compile_error!("expected `query!(<components>; with <components>; without <components>; in <registry>)`, where every clause but the first is optional")
+	};
+}
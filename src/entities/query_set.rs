@@ -0,0 +1,72 @@
+use crate::archetypes::{ArchetypeFetch, ArchetypeInstance, ArchetypeStore, SparseFilter};
+use crate::entities::{ComponentQuery, EntityRegistry, QueryAccess, QueryIter};
+use crate::components::{ComponentSet, SparseStorage};
+use std::marker::PhantomData;
+
+/// Hands out iterators over multiple queries of the same [EntityRegistry] at once.
+///
+/// Each query may only access [components](crate::components::Component) which are not accessed
+/// by the queries requested before it, unless both of them only read them.  
+/// Requesting a conflicting query panics, so the iterators never hand out aliasing references.
+///
+/// Conflicts are only detected at runtime, when a query is requested through [QuerySet::iter].
+/// The type system does not verify that the queries of a set are disjoint.
+pub struct QuerySet<'l> {
+	store: *const ArchetypeStore,
+	archetypes: *mut ArchetypeInstance,
+	sparse: *mut SparseStorage,
+	access: QueryAccess,
+	phantom: PhantomData<&'l mut EntityRegistry>,
+}
+
+impl EntityRegistry {
+	/// Create a [QuerySet], allowing multiple queries over disjoint [components](crate::components::Component)
+	/// to be iterated at the same time.
+	pub fn query_set(&mut self) -> QuerySet<'_> {
+		// The archetypes and sparse sets are only ever accessed through these pointers,
+		// since reborrowing the registry for each iterator would invalidate the previous ones.
+		let registry: *mut EntityRegistry = self;
+		unsafe {
+			QuerySet {
+				store: std::ptr::addr_of!((*registry).archetype_store),
+				archetypes: (*registry).archetype_store.as_mut_ptr(),
+				sparse: std::ptr::addr_of_mut!((*registry).sparse_storage),
+				access: QueryAccess::default(),
+				phantom: PhantomData,
+			}
+		}
+	}
+}
+
+impl<'l> QuerySet<'l> {
+	/// Creates an iterator over the [entities](crate::entities::Entity) including the [components](crate::components::Component)
+	/// in `I` and excluding the ones in `E`.
+	///
+	/// The function will panic if `I` accesses a [component](crate::components::Component) in a way
+	/// that conflicts with a previously requested query.  
+	/// Excluded [sparse](crate::components::ComponentStorage::Sparse) [components](crate::components::Component)
+	/// are read to filter out [entities](crate::entities::Entity), so they count as accessed immutably.
+	pub fn iter<I: 'static + ComponentSet, E: 'static + ComponentSet>(&mut self) -> QueryIter<'l, I>
+	where
+		ArchetypeInstance: ArchetypeFetch<'l, I>,
+	{
+		let access = QueryAccess::of_filter::<I, E>();
+		assert!(
+			!access.conflicts_with(&self.access),
+			"Query conflicts with a previous query of the QuerySet"
+		);
+		self.access.merge(&access);
+
+		// SAFETY: The registry is mutably borrowed for 'l, and the registry itself is never borrowed here.
+		// Only the query cache, the archetype signatures and the sparse set map are read,
+		// which the iterators created by the set never access mutably.
+		// The iterators never access the same component mutably and immutably at once.
+		unsafe {
+			let query = <(I, E)>::get_query();
+			let filter = SparseFilter::new(&*self.sparse, &crate::entities::get_query_data(query));
+			let indices = (*self.store).query_indices(query);
+			let archetypes = indices.iter().map(|i| self.archetypes.add(*i)).filter(|a| !(**a).is_empty()).collect();
+			QueryIter::new(self.sparse, filter, archetypes, usize::MAX)
+		}
+	}
+}
@@ -0,0 +1,136 @@
+use crate::archetypes::{ArchetypeInstance, IterArchetypeShared, SparseFilter};
+use crate::entities::{ComponentQuery, EntityQuery, EntityRegistry};
+use crate::components::ComponentSet;
+use std::marker::PhantomData;
+
+/// A read-only view of an [EntityRegistry].
+///
+/// The view cannot make structural changes nor hand out mutable [components](crate::components::Component),
+/// so multiple views of the same registry can be shared across threads.  
+/// Only [components](crate::components::Component) that are [Sync] can be read through it.
+#[derive(Copy, Clone)]
+pub struct RegistryView<'l> {
+	registry: &'l EntityRegistry,
+}
+
+// SAFETY: A view only ever reads the archetypes and sparse sets of the registry,
+// which cannot be modified while it's borrowed.
+// Components are only handed out as shared references, and ViewFilter requires them to be Sync.
+unsafe impl Send for RegistryView<'_> {}
+unsafe impl Sync for RegistryView<'_> {}
+
+impl EntityRegistry {
+	/// Create a read-only [view](RegistryView) of the registry.
+	pub fn view(&self) -> RegistryView<'_> {
+		RegistryView { registry: self }
+	}
+
+	/// Create a new read-only filter for the currently existing [entities](crate::entities::Entity).
+	///
+	/// Unlike [EntityRegistry::filter], it only borrows the registry immutably,
+	/// so several read-only iterations can be in progress at the same time.
+	#[inline(always)]
+	pub fn filter_ref(&self) -> EntityFilterRef<'_, (), ()> {
+		self.view().filter()
+	}
+}
+
+/// A read-only [EntityFilter](crate::entities::EntityFilter), created by [EntityRegistry::filter_ref].
+pub type EntityFilterRef<'l, I, E> = ViewFilter<'l, I, E>;
+
+impl<'l> RegistryView<'l> {
+	/// Create a new filter for the currently existing [entities](crate::entities::Entity).
+	///
+	/// Only immutable [component](crate::components::Component) references can be requested through it.
+	#[inline(always)]
+	pub fn filter(&self) -> ViewFilter<'l, (), ()> {
+		ViewFilter {
+			view: *self,
+			include_disabled: false,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
+		}
+	}
+}
+
+/// The read-only counterpart of [EntityFilter](crate::entities::EntityFilter), created by a [RegistryView].
+#[derive(Copy, Clone)]
+pub struct ViewFilter<'l, I: 'static + ComponentSet, E: 'static + ComponentSet> {
+	view: RegistryView<'l>,
+	include_disabled: bool,
+	i_phantom: PhantomData<&'l I>,
+	e_phantom: PhantomData<&'l E>,
+}
+
+impl<'l, I: 'static + ComponentSet, E: 'static + ComponentSet> ViewFilter<'l, I, E> {
+	/// It specifies which [components](crate::components::Component) an [entity](crate::entities::Entity)
+	/// must include to be picked up by the [ViewFilter].
+	pub fn include<TI: 'static + ComponentSet>(self) -> ViewFilter<'l, TI, E> {
+		ViewFilter {
+			view: self.view,
+			include_disabled: self.include_disabled,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
+		}
+	}
+
+	/// It specifies which [components](crate::components::Component) an [entity](crate::entities::Entity)
+	/// must not include to be picked up by the [ViewFilter].
+	pub fn exclude<TE: 'static + ComponentSet>(self) -> ViewFilter<'l, I, TE> {
+		ViewFilter {
+			view: self.view,
+			include_disabled: self.include_disabled,
+			i_phantom: PhantomData,
+			e_phantom: PhantomData,
+		}
+	}
+
+	/// Makes the [ViewFilter] pick up [disabled](crate::entities::Disabled) [entities](crate::entities::Entity),
+	/// which are otherwise skipped.
+	pub fn include_disabled(mut self) -> Self {
+		self.include_disabled = true;
+		self
+	}
+
+	/// Iterate all matching entities with the provided function.
+	///
+	/// The view may be shared with other threads, so the [components](crate::components::Component) must be [Sync].
+	pub fn for_each(self, mut func: impl FnMut(<(I, E) as ComponentQuery>::Arguments))
+	where
+		I: Sync,
+		ArchetypeInstance: IterArchetypeShared<I>,
+	{
+		let registry = self.view.registry;
+		let query = self.query();
+		let data = crate::entities::get_query_data(query);
+		let filter = SparseFilter::new(&registry.sparse_storage, &data);
+
+		for archetype in registry.archetype_store.query_shared_populated(query) {
+			IterArchetypeShared::for_each(archetype, &registry.sparse_storage, &filter, &mut func);
+		}
+	}
+
+	/// Iterate all matching entities in parallel with the provided function.
+	pub fn par_for_each(self, func: impl Fn(<(I, E) as ComponentQuery>::Arguments) + Send + Sync)
+	where
+		I: Sync,
+		ArchetypeInstance: IterArchetypeShared<I>,
+	{
+		let registry = self.view.registry;
+		let query = self.query();
+		let data = crate::entities::get_query_data(query);
+		let filter = SparseFilter::new(&registry.sparse_storage, &data);
+
+		for archetype in registry.archetype_store.query_shared_populated(query) {
+			IterArchetypeShared::par_for_each(archetype, &registry.sparse_storage, &filter, &func);
+		}
+	}
+
+	fn query(&self) -> EntityQuery {
+		let query = <(I, E)>::get_query();
+		match self.include_disabled {
+			true => crate::entities::with_disabled(query),
+			false => query,
+		}
+	}
+}
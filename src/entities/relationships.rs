@@ -0,0 +1,130 @@
+use crate::components::component_id::{self, ComponentId};
+use crate::components::{
+	CloneFn, CloneProbe, CloneProbeClone, Component, MutableComponent, ComponentStorage, ComponentTypeInfo, DefaultFn,
+	DefaultProbe, DefaultProbeDefault,
+};
+use crate::entities::{Entity, EntityRegistry};
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+
+/// A [component](Component) referencing another [entity](Entity) through a relationship of kind `R`.
+///
+/// When the target [entity](Entity) is destroyed, the [Link] is automatically removed from its holder.
+/// The reverse index is only updated when a [Link] is added or removed,
+/// so retargeting one should be done by adding a new [Link] rather than by modifying it in place.
+pub struct Link<R: 'static>(pub Entity, PhantomData<fn() -> R>);
+
+impl<R: 'static> Link<R> {
+	/// Create a new [Link] to `target`.
+	pub fn new(target: Entity) -> Self {
+		Self(target, PhantomData)
+	}
+
+	/// Gets the linked [entity](Entity).
+	pub fn target(&self) -> &Entity {
+		&self.0
+	}
+}
+
+impl<R: 'static> Default for Link<R> {
+	fn default() -> Self {
+		Self(Entity::default(), PhantomData)
+	}
+}
+
+impl<R: 'static> Clone for Link<R> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone(), PhantomData)
+	}
+}
+
+impl<R: 'static> MutableComponent for Link<R> {}
+
+impl<R: 'static> Component for Link<R> {
+	const LINK: bool = true;
+
+	#[inline(always)]
+	fn component_id() -> ComponentId {
+		component_id::get_generic::<Self>()
+	}
+
+	#[inline(always)]
+	fn link_target(&self) -> Option<&Entity> {
+		Some(&self.0)
+	}
+
+	#[inline(always)]
+	fn clone_fn() -> Option<CloneFn> {
+		CloneProbe::<Self>::new().clone_fn()
+	}
+
+	#[inline(always)]
+	fn default_fn() -> Option<DefaultFn> {
+		DefaultProbe::<Self>::new().default_fn()
+	}
+}
+
+impl<R: 'static> ComponentTypeInfo for Link<R> {
+	type ComponentType = Self;
+	const STORAGE: ComponentStorage = ComponentStorage::Table;
+
+	#[inline(always)]
+	fn component_id() -> ComponentId {
+		<Self as Component>::component_id()
+	}
+}
+
+struct Relationship {
+	holder: Entity,
+	component: ComponentId,
+	unlink: fn(&mut EntityRegistry, &Entity, usize),
+}
+
+/// A reverse index from [entities](Entity) to the holders of the [links](Link) targeting them.
+#[derive(Default)]
+pub(crate) struct RelationshipRegistry {
+	links: HashMap<usize, Vec<Relationship>, Hasher>,
+}
+
+impl RelationshipRegistry {
+	pub fn is_empty(&self) -> bool {
+		self.links.is_empty()
+	}
+
+	pub fn insert(
+		&mut self, target: usize, holder: Entity, component: ComponentId,
+		unlink: fn(&mut EntityRegistry, &Entity, usize),
+	) {
+		self.links.entry(target).or_default().push(Relationship {
+			holder,
+			component,
+			unlink,
+		});
+	}
+
+	pub fn remove(&mut self, target: usize, holder: &Entity, component: ComponentId) {
+		if let Some(links) = self.links.get_mut(&target) {
+			links.retain(|l| l.holder.instance != holder.instance || l.component != component);
+			if links.is_empty() {
+				self.links.remove(&target);
+			}
+		}
+	}
+
+	/// Removes all links to `target`, returning their holders and unlink functions.
+	pub fn take(&mut self, target: usize) -> impl Iterator<Item = (Entity, fn(&mut EntityRegistry, &Entity, usize))> {
+		let links = self.links.remove(&target).unwrap_or_default();
+		links.into_iter().map(|l| (l.holder, l.unlink))
+	}
+
+	pub fn retain_holders(&mut self, mut keep: impl FnMut(&Entity) -> bool) {
+		self.links.retain(|_, links| {
+			links.retain(|l| keep(&l.holder));
+			!links.is_empty()
+		});
+	}
+}
@@ -0,0 +1,73 @@
+use crate::components::ComponentId;
+use crate::data_structures::BitField;
+use std::hash::BuildHasherDefault;
+use nohash_hasher::NoHashHasher;
+use crate::entities::Entity;
+use std::collections::HashMap;
+
+type Hasher = BuildHasherDefault<NoHashHasher<usize>>;
+
+#[derive(Default)]
+struct Changes {
+	entered: Vec<Entity>,
+	exited: Vec<Entity>,
+}
+
+/// The [entities](Entity) that gained or lost each tracked [component](crate::components::Component),
+/// for the current and the last completed tick.
+#[derive(Default)]
+pub(crate) struct StructuralChanges {
+	current: HashMap<usize, Changes, Hasher>,
+	previous: HashMap<usize, Changes, Hasher>,
+}
+
+impl StructuralChanges {
+	pub fn is_empty(&self) -> bool {
+		self.current.is_empty()
+	}
+
+	pub fn track(&mut self, id: ComponentId) {
+		self.current.entry(id.value()).or_default();
+		self.previous.entry(id.value()).or_default();
+	}
+
+	pub fn entered(&self, id: ComponentId) -> Option<&[Entity]> {
+		self.previous.get(&id.value()).map(|c| c.entered.as_slice())
+	}
+
+	pub fn exited(&self, id: ComponentId) -> Option<&[Entity]> {
+		self.previous.get(&id.value()).map(|c| c.exited.as_slice())
+	}
+
+	pub fn record_entered(&mut self, id: ComponentId, entity: &Entity) {
+		if let Some(changes) = self.current.get_mut(&id.value()) {
+			changes.entered.push(entity.clone());
+		}
+	}
+
+	pub fn record_exited(&mut self, id: ComponentId, entity: &Entity) {
+		if let Some(changes) = self.current.get_mut(&id.value()) {
+			changes.exited.push(entity.clone());
+		}
+	}
+
+	/// Records the changes of an [entity](Entity) moving between archetypes with the specified signatures.
+	pub fn record_transition(&mut self, entity: &Entity, src: &BitField, dst: &BitField) {
+		for (id, changes) in self.current.iter_mut() {
+			match (src.get(*id), dst.get(*id)) {
+				(false, true) => changes.entered.push(entity.clone()),
+				(true, false) => changes.exited.push(entity.clone()),
+				_ => {},
+			}
+		}
+	}
+
+	/// Makes the changes of the current tick visible, discarding the ones of the previous tick.
+	pub fn advance(&mut self) {
+		std::mem::swap(&mut self.current, &mut self.previous);
+		for changes in self.current.values_mut() {
+			changes.entered.clear();
+			changes.exited.clear();
+		}
+	}
+}
@@ -31,16 +31,18 @@ pub mod systems;
 pub mod archetypes;
 mod context;
 
-pub use lazy_static::lazy_static;
+extern crate self as turbo_ecs;
 
 pub mod prelude {
 	//! All essential types and traits used by Turbo ECS
+	pub use crate::{create_archetype, query, spawn};
 	pub use crate::systems::{System};
 	pub use crate::context::EcsContext;
 	pub use crate::archetypes::Archetype;
 	pub use crate::components::{Component};
 	pub use crate::entities::{
 		Entity, EntityQuery, EntityRegistry, QueryBuilder, EntityFilterForEach, EntityFilterParallelForEach,
+		RegistryView, CommandBuffer,
 	};
 }
 
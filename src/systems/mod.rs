@@ -6,6 +6,8 @@
 
 mod system;
 mod system_registry;
+mod system_setup;
 
 pub use system::*;
+pub use system_setup::SystemSetup;
 pub(crate) use system_registry::*;
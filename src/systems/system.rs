@@ -1,12 +1,21 @@
-use crate::entities::EntityRegistry;
+use crate::entities::{CommandBuffer, EntityRegistry};
+use crate::systems::SystemSetup;
 
 /// It provides the logic for modifying the state of [Entities](crate::entities::Entity)
 /// and their associated [Components](crate::components::Component).
 pub trait System {
 	/// Initialises the [System].
 	/// **This function should not be called by user code.**
-	fn setup(&mut self) {}
+	///
+	/// Called once by [EcsContext::setup_systems](crate::context::EcsContext::setup_systems),
+	/// it can be used to create the [archetypes](crate::archetypes::Archetype) and warm the
+	/// [queries](crate::entities::EntityQuery) the [System] will need when it runs.
+	#[allow(unused_variables)]
+	fn setup(&mut self, context: &mut SystemSetup) {}
 
-	/// Executes the system
-	fn run(&mut self, entities: &mut EntityRegistry);
+	/// Executes the system.
+	///
+	/// Structural changes recorded in `commands` are applied right after the [System] completes,
+	/// before the next [System] runs.
+	fn run(&mut self, entities: &mut EntityRegistry, commands: &mut CommandBuffer);
 }
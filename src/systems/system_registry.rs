@@ -1,12 +1,16 @@
-use crate::entities::EntityRegistry;
+use crate::entities::{CommandBuffer, EntityRegistry};
 use std::collections::HashSet;
-use crate::systems::System;
+use crate::systems::{System, SystemSetup};
 use std::any::TypeId;
 
+type RunCriteria = Box<dyn FnMut(&EntityRegistry) -> bool>;
+
 pub(crate) struct SystemRegistry {
 	state: State,
 	set: HashSet<TypeId>,
 	systems: Vec<Box<dyn System>>,
+	criteria: Vec<Option<RunCriteria>>,
+	commands: CommandBuffer,
 }
 
 #[derive(Default)]
@@ -23,15 +27,22 @@ impl SystemRegistry {
 			set: HashSet::default(),
 			state: State::default(),
 			systems: Vec::default(),
+			criteria: Vec::default(),
+			commands: CommandBuffer::default(),
 		}
 	}
 
 	pub fn add_system<T: 'static + System>(&mut self, system: T) {
+		self.add_system_with(system, None);
+	}
+
+	pub fn add_system_with<T: 'static + System>(&mut self, system: T, criteria: Option<RunCriteria>) {
 		match self.state {
 			State::Uninitialized => {
 				let inserted = self.set.insert(TypeId::of::<T>());
 				assert!(inserted, "System was already added to the current context");
 				self.systems.push(Box::new(system));
+				self.criteria.push(criteria);
 			},
 			State::Initializing => {
 				panic!("Cannot add new systems during initialization");
@@ -42,11 +53,12 @@ impl SystemRegistry {
 		}
 	}
 
-	pub fn setup_systems(&mut self) {
+	pub fn setup_systems(&mut self, entities: &mut EntityRegistry) {
 		match self.state {
 			State::Uninitialized => {
 				self.state = State::Initializing;
-				self.systems.iter_mut().for_each(|s| s.setup());
+				let mut context = SystemSetup::new(entities);
+				self.systems.iter_mut().for_each(|s| s.setup(&mut context));
 				self.state = State::Initialized;
 			},
 			State::Initializing => {
@@ -64,7 +76,16 @@ impl SystemRegistry {
 				panic!("Systems must be initialized before they can run");
 			},
 			State::Initialized => {
-				self.systems.iter_mut().for_each(|s| s.run(entities));
+				for (system, criteria) in self.systems.iter_mut().zip(self.criteria.iter_mut()) {
+					if let Some(criteria) = criteria {
+						if !criteria(entities) {
+							continue;
+						}
+					}
+
+					system.run(entities, &mut self.commands);
+					self.commands.apply(entities);
+				}
 			},
 		}
 	}
@@ -0,0 +1,49 @@
+use crate::entities::{EntityQuery, EntityRegistry};
+use crate::components::ComponentType;
+use crate::archetypes::Archetype;
+use std::ops::{Deref, DerefMut};
+
+/// The context passed to [System::setup](crate::systems::System::setup).
+///
+/// It allows [systems](crate::systems::System) to prepare the [archetypes](Archetype) and [queries](EntityQuery)
+/// they will use ahead of their first run, as well as to access the [EntityRegistry] directly.
+pub struct SystemSetup<'l> {
+	entities: &'l mut EntityRegistry,
+}
+
+impl<'l> SystemSetup<'l> {
+	pub(crate) fn new(entities: &'l mut EntityRegistry) -> Self {
+		Self { entities }
+	}
+
+	/// Creates an [archetype](Archetype) containing the specified [`components`](crate::components::Component).
+	pub fn create_archetype(&mut self, components: &[ComponentType]) -> Archetype {
+		self.entities.archetype_store.create_archetype(components)
+	}
+
+	/// Creates an [archetype](Archetype) containing the specified [`components`](crate::components::Component) with the specified capacity.
+	pub fn create_archetype_with_capacity(&mut self, components: &[ComponentType], min_capacity: usize) -> Archetype {
+		self.entities.archetype_store.create_archetype_with_capacity(components, min_capacity)
+	}
+
+	/// Matches `query` against all existing [archetypes](Archetype) ahead of time.
+	pub fn warm_query(&mut self, query: EntityQuery) {
+		self.entities.archetype_store.warm_query(query);
+	}
+}
+
+impl Deref for SystemSetup<'_> {
+	type Target = EntityRegistry;
+
+	#[inline(always)]
+	fn deref(&self) -> &Self::Target {
+		self.entities
+	}
+}
+
+impl DerefMut for SystemSetup<'_> {
+	#[inline(always)]
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.entities
+	}
+}
@@ -0,0 +1,53 @@
+use crate::components::Component;
+use crate::context::EcsContext;
+use crate::spawn;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Default, Component, Debug, PartialEq)]
+struct Transform(Vec<f32>);
+
+#[derive(Default, Component, Debug, PartialEq)]
+struct Velocity(f32, f32);
+
+#[test]
+pub fn spawn_with_values() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Transform(vec![1.0, 2.0]), Velocity(3.0, 4.0));
+
+	assert_eq!(
+		ecs.get_component::<Transform>(&entity),
+		Some(&Transform(vec![1.0, 2.0]))
+	);
+	assert_eq!(ecs.get_component::<Velocity>(&entity), Some(&Velocity(3.0, 4.0)));
+}
+
+#[test]
+pub fn spawn_empty() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs);
+
+	assert_eq!(ecs.get_component::<Transform>(&entity), None);
+	assert_eq!(ecs.get_component::<Velocity>(&entity), None);
+}
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default, Component)]
+struct Dropped;
+
+impl Drop for Dropped {
+	fn drop(&mut self) {
+		DROPPED.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+#[test]
+pub fn spawn_rejects_repeated_components() {
+	let mut ecs = EcsContext::new();
+	let result = catch_unwind(AssertUnwindSafe(|| spawn!(ecs, Dropped, Velocity(0.0, 0.0), Dropped)));
+
+	assert!(result.is_err(), "Repeated components were accepted");
+	assert_eq!(DROPPED.load(Ordering::Relaxed), 2, "Rejected values were leaked");
+	assert_eq!(ecs.filter().include::<&Velocity>().collect_entities().len(), 0);
+}
@@ -0,0 +1,678 @@
+use crate::components::{Component, ComponentInitializer, ComponentType};
+use crate::archetypes::{Archetype, ArchetypeEvent, ArchetypeGrowth, ArchetypeTransition, ArchetypeTransitionKind};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
+use crate::context::EcsContext;
+use crate::entities::{Entity, EntityFilterForEach, EntityQuery};
+use crate::{create_archetype, spawn};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::rc::Rc;
+
+#[derive(Default, Component)]
+struct Health(u32);
+
+#[derive(Default, Component)]
+struct Enemy;
+
+#[test]
+pub fn custom_initializer() {
+	let mut ecs = EcsContext::new();
+	let players = create_archetype!(ecs, [Health]);
+	let enemies = ecs.create_archetype_with_initializers(
+		&[ComponentType::of::<Health>(), ComponentType::of::<Enemy>()],
+		vec![ComponentInitializer::new(|| Health(100))],
+	);
+
+	let player = ecs.create_entity_from_archetype(players);
+	let spawned: Vec<_> = ecs.create_entities_from_archetype(enemies, 50).collect();
+
+	assert_eq!(ecs.get_component::<Health>(&player).unwrap().0, 0);
+	for enemy in &spawned {
+		assert_eq!(ecs.get_component::<Health>(enemy).unwrap().0, 100);
+	}
+
+	ecs.destroy_entities(&spawned[..25]);
+	let enemy = ecs.create_entity_from_archetype(enemies);
+	assert_eq!(ecs.get_component::<Health>(&enemy).unwrap().0, 100);
+}
+
+#[derive(Default, Component)]
+struct Armor(u32);
+
+#[test]
+pub fn move_entity_between_archetypes() {
+	let mut ecs = EcsContext::new();
+	let small = create_archetype!(ecs, [Health]);
+	let large = ecs.create_archetype_with_initializers(
+		&[
+			ComponentType::of::<Health>(),
+			ComponentType::of::<Enemy>(),
+			ComponentType::of::<Armor>(),
+		],
+		vec![ComponentInitializer::new(|| Armor(5))],
+	);
+
+	let entity = ecs.create_entity_from_archetype(small);
+	let other = ecs.create_entity_from_archetype(large);
+	ecs.get_component_mut::<Health>(&entity).unwrap().0 = 42;
+
+	ecs.move_entity_to(&entity, large);
+	assert_eq!(ecs.get_component::<Health>(&entity).unwrap().0, 42);
+	assert_eq!(ecs.get_component::<Armor>(&entity).unwrap().0, 5);
+	assert!(ecs.get_component::<Enemy>(&entity).is_some());
+
+	ecs.move_entity_to(&entity, small);
+	assert_eq!(ecs.get_component::<Health>(&entity).unwrap().0, 42);
+	assert!(ecs.get_component::<Armor>(&entity).is_none());
+	assert!(ecs.get_component::<Enemy>(&entity).is_none());
+	assert_eq!(ecs.get_component::<Armor>(&other).unwrap().0, 5);
+}
+
+#[test]
+#[should_panic(expected = "Archetype does not belong to this context")]
+pub fn move_entity_to_foreign_archetype() {
+	let mut ecs = EcsContext::new();
+	let mut other = EcsContext::new();
+	let foreign = create_archetype!(other, [Health]);
+	let _ = create_archetype!(ecs, [Health]);
+
+	let entity = ecs.create_entity();
+	ecs.move_entity_to(&entity, foreign);
+}
+
+#[test]
+pub fn archetype_containment() {
+	let mut ecs = EcsContext::new();
+	let small = create_archetype!(ecs, [Health]);
+	let large = create_archetype!(ecs, [Health, Armor]);
+	let same = create_archetype!(ecs, [Armor, Health]);
+
+	assert!(ecs.archetype_contains::<Health>(small));
+	assert!(!ecs.archetype_contains::<Armor>(small));
+	assert!(ecs.archetype_contains::<Armor>(large));
+	assert!(!ecs.archetype_contains::<Enemy>(large));
+
+	assert!(ecs.archetype_signature(large) == ecs.archetype_signature(same));
+	assert!(ecs.archetype_signature(small) != ecs.archetype_signature(large));
+}
+
+#[test]
+pub fn reserve_before_spawn_burst() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Health, Armor]);
+	let _ = ecs.create_entities_from_archetype(archetype, 10);
+
+	ecs.reserve_archetype(archetype, 1000);
+	let capacity = ecs.archetype_store.get(archetype.index).capacity();
+	assert!(capacity >= 1010, "Archetype capacity did not grow");
+
+	for _ in 0..500 {
+		ecs.create_entity_from_archetype(archetype);
+	}
+	let _ = ecs.create_entities_from_archetype(archetype, 500);
+	assert_eq!(
+		ecs.archetype_store.get(archetype.index).capacity(),
+		capacity,
+		"Archetype was reallocated"
+	);
+
+	let query = EntityQuery::build().include::<&Armor>().create();
+	ecs.reserve_query(query, 100);
+	assert!(ecs.archetype_store.get(archetype.index).capacity() >= 1110);
+}
+
+#[test]
+pub fn prebuilt_transitions() {
+	let mut ecs = EcsContext::new();
+	let players = create_archetype!(ecs, [Health]);
+	let enemies = ecs.prebuild_transition::<Enemy>(players, ArchetypeTransitionKind::Add);
+	assert_ne!(enemies, players);
+	assert!(ecs.archetype_contains::<Enemy>(enemies));
+	assert_eq!(
+		ecs.prebuild_transition::<Enemy>(enemies, ArchetypeTransitionKind::Remove),
+		players
+	);
+	assert_eq!(
+		ecs.prebuild_transition::<Enemy>(enemies, ArchetypeTransitionKind::Add),
+		enemies
+	);
+
+	ecs.reserve_archetype(enemies, 16);
+	let archetypes = ecs.archetype_store.iter().count();
+	let capacity = ecs.archetype_store.get(enemies.index).capacity();
+
+	let entity = ecs.create_entity_from_archetype(players);
+	ecs.get_component_mut::<Health>(&entity).unwrap().0 = 10;
+	assert!(ecs.add_component(&entity, Enemy));
+
+	assert_eq!(ecs.archetype_store.iter().count(), archetypes);
+	assert_eq!(ecs.archetype_store.get(enemies.index).capacity(), capacity);
+	assert_eq!(ecs.archetype_store.get(enemies.index).len(), 1);
+	assert_eq!(ecs.get_component::<Health>(&entity).unwrap().0, 10);
+}
+
+#[test]
+pub fn conditional_archetype_builder() {
+	let mut ecs = EcsContext::new();
+	for hostile in [false, true] {
+		let archetype = ecs
+			.build_archetype()
+			.with::<Health>()
+			.with_if::<Enemy>(hostile)
+			.with::<Health>()
+			.capacity(32)
+			.create();
+
+		assert!(ecs.archetype_contains::<Health>(archetype));
+		assert_eq!(ecs.archetype_contains::<Enemy>(archetype), hostile);
+		assert_eq!(
+			ecs.archetype_store.get(archetype.index).components().len(),
+			1 + hostile as usize
+		);
+		assert!(ecs.archetype_store.get(archetype.index).capacity() >= 32);
+	}
+
+	let built = ecs.build_archetype().with::<Enemy>().with::<Health>().create();
+	assert_eq!(built, create_archetype!(ecs, [Health, Enemy]));
+}
+
+#[test]
+pub fn transition_hashes_do_not_collide() {
+	fn hash(transition: &ArchetypeTransition) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		transition.hash(&mut hasher);
+		hasher.finish()
+	}
+
+	let transition = |index: usize, kind: ArchetypeTransitionKind| ArchetypeTransition {
+		archetype: Archetype { index, registry_id: 1 },
+		component: ComponentType::of::<Health>(),
+		kind,
+	};
+
+	// Packing the index into the upper 31 bits of a 64 bit value truncated these to the same hash.
+	let pairs = [
+		(
+			transition(0, ArchetypeTransitionKind::Add),
+			transition(1 << 31, ArchetypeTransitionKind::Add),
+		),
+		(
+			transition(3, ArchetypeTransitionKind::Remove),
+			transition(3 | 1 << 31, ArchetypeTransitionKind::Remove),
+		),
+		(
+			transition(1 << 40, ArchetypeTransitionKind::Add),
+			transition(1 << 41, ArchetypeTransitionKind::Add),
+		),
+	];
+
+	let mut transitions = HashMap::new();
+	for (a, b) in pairs.iter() {
+		assert_ne!(hash(a), hash(b));
+		transitions.insert(a.clone(), a.archetype);
+		transitions.insert(b.clone(), b.archetype);
+	}
+
+	assert_eq!(transitions.len(), pairs.len() * 2);
+	for (a, b) in pairs.iter() {
+		assert_eq!(transitions[a], a.archetype);
+		assert_eq!(transitions[b], b.archetype);
+	}
+}
+
+#[derive(Default, Component)]
+struct Loading(u32);
+
+#[derive(Default, Component)]
+struct Loaded(u32);
+
+#[test]
+pub fn swap_component_in_one_transition() {
+	let mut ecs = EcsContext::new();
+	let loading = create_archetype!(ecs, [Health, Loading]);
+	let entity = ecs.create_entity_from_archetype(loading);
+	ecs.get_component_mut::<Health>(&entity).unwrap().0 = 50;
+	ecs.get_component_mut::<Loading>(&entity).unwrap().0 = 7;
+
+	let archetypes = ecs.archetype_store.iter().count();
+	let old = ecs.swap_component::<Loading, Loaded>(&entity, Loaded(8));
+	assert_eq!(old.map(|l| l.0), Some(7));
+	assert_eq!(
+		ecs.archetype_store.iter().count(),
+		archetypes + 1,
+		"Only the destination archetype should be created"
+	);
+
+	let loaded = create_archetype!(ecs, [Health, Loaded]);
+	assert_eq!(ecs.archetype_store.get(loaded.index).len(), 1);
+	assert_eq!(ecs.archetype_store.get(loading.index).len(), 0);
+	assert_eq!(ecs.get_component::<Health>(&entity).unwrap().0, 50);
+	assert_eq!(ecs.get_component::<Loaded>(&entity).unwrap().0, 8);
+	assert!(ecs.get_component::<Loading>(&entity).is_none());
+
+	assert!(ecs.swap_component::<Loading, Loaded>(&entity, Loaded(9)).is_none());
+	assert_eq!(ecs.get_component::<Loaded>(&entity).unwrap().0, 8);
+
+	ecs.add_component(&entity, Loading(1));
+	assert_eq!(
+		ecs.swap_component::<Loading, Loaded>(&entity, Loaded(2)).map(|l| l.0),
+		Some(1)
+	);
+	assert_eq!(ecs.get_component::<Loaded>(&entity).unwrap().0, 2);
+	assert_eq!(ecs.archetype_store.get(loaded.index).len(), 1);
+}
+
+#[test]
+pub fn pinned_archetypes_stay_distinct() {
+	let mut ecs = EcsContext::new();
+	let shared = create_archetype!(ecs, [Health, Armor]);
+	let pinned = ecs.create_pinned_archetype(&[ComponentType::of::<Health>(), ComponentType::of::<Armor>()]);
+	let other = ecs.create_pinned_archetype(&[ComponentType::of::<Health>(), ComponentType::of::<Armor>()]);
+	assert!(shared != pinned && pinned != other && shared != other);
+	assert!(create_archetype!(ecs, [Armor, Health]) == shared);
+	assert!(ecs.archetype_signature(pinned) == ecs.archetype_signature(shared));
+
+	let _ = ecs.create_entities_from_archetype(shared, 3);
+	let _ = ecs.create_entities_from_archetype(pinned, 4);
+	let moved = ecs.create_entity_from_archetype(shared);
+	ecs.get_component_mut::<Health>(&moved).unwrap().0 = 7;
+	ecs.move_entity_to(&moved, other);
+	assert_eq!(ecs.get_component::<Health>(&moved).unwrap().0, 7);
+
+	let query = EntityQuery::build().include::<(&Health, &Armor)>().create();
+	let explain = ecs.explain_query(query);
+	let entities: Vec<_> = explain.archetypes.iter().map(|a| (a.archetype, a.entities)).collect();
+	assert_eq!(entities, vec![(shared, 3), (pinned, 4), (other, 1)]);
+
+	// Structural changes leave the pinned archetype.
+	ecs.remove_component::<Armor>(&moved);
+	ecs.add_component(&moved, Armor(1));
+	assert_eq!(ecs.explain_query(query).archetypes[0].entities, 4);
+}
+
+#[derive(Default, Copy, Clone, Component)]
+struct Translation(f32, f32);
+
+#[test]
+pub fn fill_archetype_column() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Translation, Health]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 64).collect();
+
+	let values: Vec<_> = (0..64).map(|i| Translation(i as f32, -i as f32)).collect();
+	let column = ecs.archetype_column_mut::<Translation>(archetype);
+	assert_eq!(column.len(), entities.len());
+	column.copy_from_slice(&values);
+
+	let mut sum = 0.0;
+	ecs.filter().include::<&Translation>().for_each(|t| sum += t.0 + t.1 * 2.0);
+	assert_eq!(sum, -(0..64).sum::<i32>() as f32);
+	assert_eq!(ecs.get_component::<Translation>(&entities[10]).unwrap().0, 10.0);
+}
+
+#[test]
+#[should_panic(expected = "Archetype does not contain the requested component")]
+pub fn archetype_column_of_missing_component() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Health]);
+	let _ = ecs.archetype_column_mut::<Translation>(archetype);
+}
+
+#[test]
+pub fn write_fragmented_archetype_column() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Translation, Health]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 16).collect();
+	ecs.destroy_entities(&[entities[3].clone(), entities[10].clone()]);
+
+	let values: Vec<_> = (0..14).map(|i| Translation(i as f32, 0.0)).collect();
+	ecs.write_component_column(archetype, &values);
+
+	let mut written = vec![];
+	ecs.filter().include::<&Translation>().for_each(|t| written.push(t.0));
+	assert_eq!(written, (0..14).map(|i| i as f32).collect::<Vec<_>>());
+	assert_eq!(ecs.get_component::<Translation>(&entities[11]).unwrap().0, 9.0);
+}
+
+#[test]
+#[should_panic(expected = "Values do not match the number of entities")]
+pub fn write_archetype_column_length_mismatch() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Translation]);
+	let _ = ecs.create_entities_from_archetype(archetype, 4);
+	ecs.write_component_column(archetype, &[Translation::default(); 3]);
+}
+
+#[test]
+pub fn iterate_single_archetype() {
+	let mut ecs = EcsContext::new();
+	let players = create_archetype!(ecs, [Health, Translation]);
+	let enemies = create_archetype!(ecs, [Health, Translation, Enemy]);
+	let targeted: Vec<_> = ecs.create_entities_from_archetype(players, 3).collect();
+	let _ = ecs.create_entities_from_archetype(enemies, 5);
+
+	let mut visited = vec![];
+	ecs.for_each_in_archetype::<&mut Health>(players, |entity, health| {
+		health.0 += 1;
+		visited.push(entity);
+	});
+	assert_eq!(visited.len(), targeted.len());
+	assert!(visited.iter().all(|e| ecs.archetype_of(e) == players));
+
+	let mut total = 0;
+	ecs.filter().include::<&Health>().for_each(|health| total += health.0);
+	assert_eq!(total, 3, "Only the targeted archetype should be iterated");
+
+	let mut count = 0;
+	ecs.for_each_in_archetype::<()>(enemies, |_, _| count += 1);
+	assert_eq!(count, 5);
+}
+
+#[derive(Component)]
+struct DropCounter(Rc<Cell<usize>>);
+
+impl Drop for DropCounter {
+	fn drop(&mut self) {
+		self.0.set(self.0.get() + 1);
+	}
+}
+
+#[test]
+pub fn drop_trivial_and_droppable_components() {
+	let drops = Rc::new(Cell::new(0));
+	let mut ecs = EcsContext::new();
+
+	let mixed: Vec<_> = (0..10).map(|i| spawn!(ecs, Translation(i as f32, 0.0), DropCounter(drops.clone()))).collect();
+	let trivial: Vec<_> = (0..10).map(|i| spawn!(ecs, Translation(i as f32, 1.0))).collect();
+
+	let even = |entities: &[Entity]| entities.iter().step_by(2).cloned().collect::<Vec<_>>();
+	ecs.destroy_entities(&even(&mixed));
+	ecs.destroy_entities(&even(&trivial));
+	assert_eq!(drops.get(), 5);
+
+	let mut sums = (0.0, 0.0);
+	ecs.filter().include::<&Translation>().for_each(|t| match t.1 == 0.0 {
+		true => sums.0 += t.0,
+		false => sums.1 += t.0,
+	});
+	assert_eq!(sums, (25.0, 25.0));
+
+	drop(ecs);
+	assert_eq!(drops.get(), 10);
+}
+
+#[test]
+pub fn incremental_spawns_grow_geometrically() {
+	fn count_reallocations(growth: ArchetypeGrowth) -> usize {
+		let mut ecs = EcsContext::new();
+		ecs.set_archetype_growth(growth);
+		let archetype = create_archetype!(ecs, [Health, Armor]);
+
+		let mut reallocations = 0;
+		let mut capacity = ecs.archetype_store.get(archetype.index).capacity();
+		for _ in 0..1000 {
+			ecs.create_entity_from_archetype(archetype);
+			let new_capacity = ecs.archetype_store.get(archetype.index).capacity();
+			if new_capacity != capacity {
+				reallocations += 1;
+				capacity = new_capacity;
+			}
+		}
+
+		assert!(capacity >= 1000);
+		reallocations
+	}
+
+	assert_eq!(count_reallocations(ArchetypeGrowth::Exact), 1000);
+	assert!(count_reallocations(ArchetypeGrowth::Double) <= 11);
+}
+
+#[derive(Default, Component)]
+struct OldHealth {
+	hp: u8,
+}
+
+#[derive(Default, Component)]
+struct NewHealth {
+	hp: u32,
+}
+
+#[test]
+pub fn migrate_component_layout() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [OldHealth, Armor]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 4).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<OldHealth>(entity).unwrap().hp = 250 + i as u8;
+	}
+	ecs.destroy_entities(&entities[1..2]);
+
+	let mut visited = 0;
+	ecs.filter().include::<&OldHealth>().for_each(|_| visited += 1);
+	assert_eq!(visited, 3);
+
+	ecs.migrate_component(|old: &OldHealth| NewHealth {
+		hp: old.hp as u32 * 100,
+	});
+
+	assert!(ecs.archetype_contains::<NewHealth>(archetype));
+	assert!(!ecs.archetype_contains::<OldHealth>(archetype));
+	assert_eq!(ecs.get_component::<NewHealth>(&entities[0]).unwrap().hp, 25000);
+	assert_eq!(ecs.get_component::<NewHealth>(&entities[3]).unwrap().hp, 25300);
+	assert!(ecs.get_component::<Armor>(&entities[2]).is_some());
+
+	let mut visited = 0;
+	ecs.filter().include::<&OldHealth>().for_each(|_| visited += 1);
+	assert_eq!(visited, 0, "Query cache still matches the old component");
+	ecs.filter().include::<&NewHealth>().for_each(|_| visited += 1);
+	assert_eq!(visited, 3);
+
+	let same = create_archetype!(ecs, [NewHealth, Armor]);
+	assert_eq!(same, archetype, "Migrated archetype was not re-keyed");
+	let entity = spawn!(ecs, Armor(1));
+	ecs.add_component(&entity, NewHealth { hp: 7 });
+	assert_eq!(ecs.archetype_column_mut::<NewHealth>(archetype).len(), 4);
+}
+
+#[test]
+pub fn migrate_component_rejects_existing_signature() {
+	let mut ecs = EcsContext::new();
+	let old = spawn!(ecs, OldHealth { hp: 1 }, Armor(0));
+	spawn!(ecs, NewHealth { hp: 2 }, Armor(0));
+
+	let result = catch_unwind(AssertUnwindSafe(|| {
+		ecs.migrate_component(|old: &OldHealth| NewHealth { hp: old.hp as u32 })
+	}));
+	let message = *result.unwrap_err().downcast::<&str>().unwrap();
+	assert_eq!(message, "An archetype with the migrated components already exists");
+	assert_eq!(ecs.get_component::<OldHealth>(&old).unwrap().hp, 1);
+}
+
+static OLD_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default, Component)]
+struct DroppedHealth(u8);
+
+impl Drop for DroppedHealth {
+	fn drop(&mut self) {
+		OLD_DROPS.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+#[test]
+pub fn migrate_component_drops_old_values() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [DroppedHealth]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 3).collect();
+	ecs.destroy_entities(&entities[..1]);
+	assert_eq!(OLD_DROPS.load(Ordering::Relaxed), 1);
+
+	ecs.migrate_component(|old: &DroppedHealth| NewHealth { hp: old.0 as u32 });
+	assert_eq!(OLD_DROPS.load(Ordering::Relaxed), 3);
+	drop(ecs);
+	assert_eq!(OLD_DROPS.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+pub fn migrate_component_panic_keeps_archetypes() {
+	let mut ecs = EcsContext::new();
+	let first = create_archetype!(ecs, [OldHealth, Armor]);
+	let second = create_archetype!(ecs, [OldHealth]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(first, 2).collect();
+	entities.extend(ecs.create_entities_from_archetype(second, 2));
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<OldHealth>(entity).unwrap().hp = i as u8 + 1;
+	}
+
+	// The first archetype is fully converted before the second one panics
+	let result = catch_unwind(AssertUnwindSafe(|| {
+		ecs.migrate_component(|old: &OldHealth| match old.hp {
+			4 => panic!("Conversion failed"),
+			hp => NewHealth { hp: hp as u32 },
+		})
+	}));
+	assert!(result.is_err());
+
+	for archetype in [first, second] {
+		assert!(ecs.archetype_contains::<OldHealth>(archetype));
+		assert!(!ecs.archetype_contains::<NewHealth>(archetype));
+	}
+	let hp: Vec<_> = entities.iter().map(|e| ecs.get_component::<OldHealth>(e).unwrap().hp).collect();
+	assert_eq!(hp, [1, 2, 3, 4]);
+
+	let mut visited = 0;
+	ecs.filter().include::<&OldHealth>().for_each(|_| visited += 1);
+	assert_eq!(visited, 4);
+}
+
+#[test]
+pub fn component_order_is_canonical() {
+	let mut created = EcsContext::new();
+	let archetype = create_archetype!(created, [Armor, Enemy, Health]);
+	let entity = created.create_entity_from_archetype(archetype);
+
+	let mut transitioned = EcsContext::new();
+	let other = transitioned.create_entity();
+	transitioned.add_component(&other, Enemy);
+	transitioned.add_component(&other, Health(1));
+	transitioned.add_component(&other, Armor(2));
+
+	let ids =
+		|ecs: &EcsContext, entity: &Entity| ecs.component_types(entity).iter().map(|t| t.id()).collect::<Vec<_>>();
+	let (created_ids, transitioned_ids) = (ids(&created, &entity), ids(&transitioned, &other));
+	assert_eq!(created_ids, transitioned_ids);
+	assert!(created_ids.windows(2).all(|w| w[0].value() < w[1].value()));
+}
+
+#[test]
+pub fn collect_empty_archetypes() {
+	let mut ecs = EcsContext::new();
+	let players = create_archetype!(ecs, [Health]);
+	let enemies = create_archetype!(ecs, [Health, Enemy]);
+
+	let spawned: Vec<_> = ecs.create_entities_from_archetype(players, 8).collect();
+	let enemy = ecs.create_entity_from_archetype(enemies);
+	let query = EntityQuery::build().include::<&Health>().create();
+	ecs.warm_query(query);
+
+	// Caches a transition towards the archetype about to be collected.
+	ecs.remove_component::<Enemy>(&enemy);
+	ecs.add_component(&enemy, Enemy);
+	ecs.destroy_entities(&spawned);
+
+	let archetypes = ecs.archetype_store.iter().count();
+	assert_eq!(ecs.collect_empty_archetypes(), 1);
+	assert_eq!(
+		ecs.collect_empty_archetypes(),
+		0,
+		"Archetypes should only be collected once"
+	);
+	assert_eq!(ecs.archetype_store.iter().count(), archetypes - 1);
+	assert!(ecs.archetype_store.is_collected(players.index));
+
+	let matched: Vec<_> = ecs.explain_query(query).archetypes.iter().map(|a| a.archetype).collect();
+	assert_eq!(matched, vec![enemies]);
+
+	ecs.remove_component::<Enemy>(&enemy);
+	let recreated = create_archetype!(ecs, [Health]);
+	assert_ne!(recreated, players, "Collected indices should not be reused");
+	assert_eq!(ecs.archetype_of(&enemy), recreated);
+
+	let _ = ecs.create_entities_from_archetype(recreated, 2);
+	let mut visited = 0;
+	ecs.filter().include::<&Health>().for_each(|_| visited += 1);
+	assert_eq!(visited, 3);
+}
+
+#[test]
+#[should_panic(expected = "Archetype has been collected")]
+pub fn collected_archetype_handles_are_rejected() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Health]);
+	ecs.collect_empty_archetypes();
+	let _ = ecs.create_entity_from_archetype(archetype);
+}
+
+#[test]
+pub fn archetype_of_entities() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Health]);
+	let a = ecs.create_entity_from_archetype(archetype);
+	let b = ecs.create_entity_from_archetype(archetype);
+	assert_eq!(ecs.archetype_of(&a), ecs.archetype_of(&b));
+	assert_eq!(ecs.archetype_of(&a), archetype);
+
+	ecs.add_component(&b, Enemy);
+	assert_ne!(ecs.archetype_of(&a), ecs.archetype_of(&b));
+	assert_eq!(ecs.archetype_of(&b), create_archetype!(ecs, [Health, Enemy]));
+
+	let empty = ecs.create_entity();
+	assert_eq!(ecs.archetype_of(&empty), Archetype::default());
+}
+
+#[test]
+pub fn profile_structural_events() {
+	let mut ecs = EcsContext::new();
+	let events = Arc::new(Mutex::new(vec![]));
+	let recorded = events.clone();
+	ecs.set_archetype_profiler(move |event, _| recorded.lock().unwrap().push(event));
+
+	let archetype = create_archetype!(ecs, [Health]);
+	assert_eq!(*events.lock().unwrap(), [ArchetypeEvent::ArchetypeCreated(archetype)]);
+	create_archetype!(ecs, [Health]);
+	assert_eq!(
+		events.lock().unwrap().len(),
+		1,
+		"Existing archetypes should not be reported"
+	);
+
+	let entity = ecs.create_entity_from_archetype(archetype);
+	ecs.add_component(&entity, Armor(1));
+	let dst = ecs.archetype_of(&entity);
+	assert_eq!(
+		events.lock().unwrap()[1..],
+		[
+			ArchetypeEvent::ArchetypeCreated(dst),
+			ArchetypeEvent::TransitionResolved {
+				src: archetype,
+				dst,
+				kind: ArchetypeTransitionKind::Add
+			},
+		]
+	);
+
+	events.lock().unwrap().clear();
+	ecs.filter().include::<&Armor>().for_each(|_| {});
+	ecs.filter().include::<&Armor>().for_each(|_| {});
+	let query = EntityQuery::build().include::<&Armor>().create();
+	assert_eq!(*events.lock().unwrap(), [ArchetypeEvent::QueryInitialized(query)]);
+
+	events.lock().unwrap().clear();
+	ecs.clear_archetype_profiler();
+	create_archetype!(ecs, [Enemy]);
+	assert!(events.lock().unwrap().is_empty());
+}
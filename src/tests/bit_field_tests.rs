@@ -0,0 +1,172 @@
+#![allow(clippy::single_range_in_vec_init)]
+
+use crate::data_structures::BitField;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+fn bitfield(bits: &[usize]) -> BitField {
+	let mut bitfield = BitField::new();
+	for bit in bits {
+		bitfield.set(*bit, true);
+	}
+	bitfield
+}
+
+fn expected_ranges(bitfield: &BitField) -> Vec<Range<usize>> {
+	let mut ranges: Vec<Range<usize>> = Vec::new();
+	for i in (0..bitfield.capacity()).filter(|i| bitfield.get(*i)) {
+		match ranges.last_mut() {
+			Some(range) if range.end == i => range.end += 1,
+			_ => ranges.push(i..i + 1),
+		}
+	}
+	ranges
+}
+
+fn assert_ranges(bits: &[usize], expected: &[Range<usize>]) {
+	let bitfield = bitfield(bits);
+	let ranges: Vec<_> = bitfield.iter_ranges().collect();
+	assert_eq!(ranges, expected, "Unexpected ranges for bits {bits:?}");
+	assert_eq!(ranges, expected_ranges(&bitfield));
+}
+
+#[test]
+pub fn word_boundary_bits() {
+	assert_ranges(&[31], &[31..32]);
+	assert_ranges(&[32], &[32..33]);
+	assert_ranges(&[63], &[63..64]);
+	assert_ranges(&[64], &[64..65]);
+	assert_ranges(&[31, 32], &[31..33]);
+	assert_ranges(&[63, 64], &[63..65]);
+	assert_ranges(&[31, 32, 63, 64], &[31..33, 63..65]);
+	assert_ranges(&[0, 31, 63], &[0..1, 31..32, 63..64]);
+}
+
+#[test]
+pub fn runs_across_full_words() {
+	let run: Vec<_> = (17..141).collect();
+	assert_ranges(&run, &[17..141]);
+
+	let run: Vec<_> = (32..128).collect();
+	assert_ranges(&run, &[32..128]);
+
+	let run: Vec<_> = (0..96).chain(97..100).collect();
+	assert_ranges(&run, &[0..96, 97..100]);
+
+	let run: Vec<_> = (5..64).chain(65..200).chain([250]).collect();
+	assert_ranges(&run, &[5..64, 65..200, 250..251]);
+}
+
+#[test]
+pub fn trailing_run() {
+	let mut bitfield = BitField::with_capacity(128);
+	for i in 40..128 {
+		bitfield.set(i, true);
+	}
+
+	let ranges: Vec<_> = bitfield.iter_ranges().collect();
+	assert_eq!(ranges, [40..128]);
+}
+
+#[test]
+pub fn pseudo_random_patterns() {
+	let mut seed = 0x2545_f491_u32;
+	for _ in 0..200 {
+		let mut bitfield = BitField::with_capacity(256);
+		let mut i = 0;
+		while i < 256 {
+			seed ^= seed << 13;
+			seed ^= seed >> 17;
+			seed ^= seed << 5;
+
+			let len = (seed % 70) as usize;
+			if seed & 1 == 0 {
+				for bit in i..usize::min(i + len, 256) {
+					bitfield.set(bit, true);
+				}
+			}
+			i += len + 1;
+		}
+
+		let ranges: Vec<_> = bitfield.iter_ranges().collect();
+		assert_eq!(ranges, expected_ranges(&bitfield));
+	}
+}
+
+#[test]
+pub fn subset_semantics() {
+	let empty = BitField::new();
+	let mut cleared = bitfield(&[70]);
+	cleared.set(70, false);
+
+	assert!(empty.is_subset_of(&empty));
+	assert!(empty.is_subset_of(&bitfield(&[3, 40])));
+	assert!(cleared.is_subset_of(&empty));
+	assert!(!bitfield(&[3]).is_subset_of(&empty));
+
+	assert!(bitfield(&[3, 40]).is_subset_of(&bitfield(&[3, 40, 90])));
+	assert!(!bitfield(&[3, 40]).is_subset_of(&bitfield(&[3, 41])));
+	assert!(!bitfield(&[3, 40]).is_subset_of(&bitfield(&[40])));
+	assert!(!bitfield(&[3, 90]).is_subset_of(&bitfield(&[3])));
+}
+
+#[test]
+pub fn mixed_batch_updates() {
+	let initial = [1, 5, 31, 32, 64, 90];
+	let set = [0, 5, 33, 63, 95];
+	let clear = [1, 32, 90, 94, 95];
+
+	let mut batched = bitfield(&initial);
+	batched.ensure_capacity(96);
+	let mut manual = batched.clone();
+
+	unsafe { batched.set_batch_mixed_unchecked(&set, &clear) };
+	for i in set {
+		manual.set(i, true);
+	}
+	for i in clear {
+		manual.set(i, false);
+	}
+
+	assert!(batched == manual);
+	assert_eq!(
+		batched.iter_ranges().flatten().collect::<Vec<_>>(),
+		[0, 5, 31, 33, 63, 64]
+	);
+}
+
+#[test]
+pub fn copy_from_shorter_bitfield() {
+	let mut bitfield = BitField::new();
+	bitfield.set(70, true);
+	bitfield.copy_from(&BitField::new());
+	assert!(bitfield.is_empty());
+
+	let mut other = BitField::new();
+	other.set(3, true);
+	bitfield.set(40, true);
+	bitfield.copy_from(&other);
+	assert!(bitfield == other);
+}
+
+#[test]
+pub fn hash_includes_top_word() {
+	let hash = |bitfield: &BitField| {
+		let mut hasher = DefaultHasher::new();
+		bitfield.hash(&mut hasher);
+		hasher.finish()
+	};
+
+	let (a, b) = (bitfield(&[3, 70]), bitfield(&[3, 71]));
+	assert_ne!(
+		hash(&a),
+		hash(&b),
+		"Bitfields differing in their top word hash identically"
+	);
+	assert_ne!(hash(&bitfield(&[5])), hash(&BitField::new()));
+
+	let mut padded = bitfield(&[3, 70]);
+	padded.ensure_capacity(512);
+	assert_eq!(hash(&a), hash(&padded), "Trailing zero words changed the hash");
+}
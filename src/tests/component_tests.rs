@@ -0,0 +1,272 @@
+use crate::components::{component_id, Component, ComponentId, ComponentLoadError, ComponentLoader, ComponentType};
+use crate::entities::EntityFilterForEach;
+use crate::context::EcsContext;
+use std::num::NonZeroU32;
+use crate::components::component_id::UnknownComponentError;
+use crate::{create_archetype, spawn};
+
+#[derive(Default, Component)]
+#[component(version = 2)]
+struct Stats {
+	health: u32,
+	shield: u32,
+}
+
+#[derive(Default, Component)]
+struct Unversioned;
+
+fn loader() -> ComponentLoader<Stats> {
+	// v2 layout: health and shield as little endian u32s.
+	let decode = |bytes: &[u8]| Stats {
+		health: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+		shield: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+	};
+
+	// v1 layout: a single u8 health value, without shields.
+	ComponentLoader::new(decode).with_migration(|version, bytes| {
+		assert_eq!(version, 1);
+		Stats {
+			health: bytes[0] as u32,
+			shield: 0,
+		}
+	})
+}
+
+#[test]
+pub fn migrate_component_versions() {
+	assert_eq!(Stats::VERSION, 2);
+	assert_eq!(Unversioned::VERSION, 1);
+
+	let loader = loader();
+	let v1 = loader.load(1, &[75]).unwrap();
+	assert_eq!((v1.health, v1.shield), (75, 0));
+
+	let bytes: Vec<u8> = [100u32, 20].iter().flat_map(|v| v.to_le_bytes()).collect();
+	let v2 = loader.load(2, &bytes).unwrap();
+	assert_eq!((v2.health, v2.shield), (100, 20));
+
+	assert_eq!(
+		loader.load(3, &bytes).err(),
+		Some(ComponentLoadError::NewerVersion { stored: 3, current: 2 })
+	);
+	assert_eq!(
+		ComponentLoader::new(|_| Stats::default()).load(1, &[75]).err(),
+		Some(ComponentLoadError::MissingMigration { stored: 1, current: 2 })
+	);
+}
+
+/// A component wrapping a handle with no meaningful default value.
+#[derive(Component)]
+struct Handle(NonZeroU32);
+
+#[test]
+pub fn components_without_default() {
+	assert!(!ComponentType::of::<Handle>().has_default());
+	assert!(ComponentType::of::<Stats>().has_default());
+
+	let mut ecs = EcsContext::new();
+	let spawned = spawn!(ecs, Handle(NonZeroU32::new(7).unwrap()), Stats::default());
+	assert_eq!(ecs.get_component::<Handle>(&spawned).unwrap().0.get(), 7);
+
+	let entity = ecs.create_entity();
+	assert!(ecs.add_component(&entity, Handle(NonZeroU32::new(3).unwrap())));
+	assert_eq!(ecs.get_component::<Handle>(&entity).unwrap().0.get(), 3);
+
+	let mut sum = 0;
+	ecs.filter().include::<&Handle>().for_each(|handle| sum += handle.0.get());
+	assert_eq!(sum, 10);
+}
+
+mod shadowing {
+	use crate::components::Component;
+
+	#[derive(Default, Component)]
+	pub struct Stats;
+}
+
+#[test]
+pub fn lookup_component_by_name() {
+	let stats = ComponentType::of::<Stats>();
+	let _ = ComponentType::of::<shadowing::Stats>();
+
+	let name = std::any::type_name::<Stats>();
+	let found = ComponentType::by_name(name).unwrap();
+	assert!(found == stats);
+	assert_eq!(found.id().name(), name);
+
+	let _ = ComponentType::of::<Unversioned>();
+	assert!(ComponentType::by_name("Unversioned").unwrap() == ComponentType::of::<Unversioned>());
+	assert!(
+		ComponentType::by_name("Stats").is_none(),
+		"Ambiguous names should not resolve"
+	);
+	assert!(ComponentType::by_name("NotAComponent").is_none());
+
+	let mut ecs = EcsContext::new();
+	let archetype = ecs.create_archetype(&[found]);
+	let entity = ecs.create_entity_from_archetype(archetype);
+	assert!(ecs.get_component::<Stats>(&entity).is_some());
+}
+
+#[derive(Default, Component)]
+struct Position<T: Default>(T);
+
+#[test]
+pub fn generic_components() {
+	let float = ComponentType::of::<Position<f32>>();
+	let int = ComponentType::of::<Position<i32>>();
+	assert_ne!(float.id(), int.id());
+	assert_eq!(Position::<f32>::component_id(), float.id());
+	assert!(float.has_default());
+
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position(1.5f32), Position(7i32));
+	assert_eq!(ecs.get_component::<Position<f32>>(&entity).unwrap().0, 1.5);
+	assert_eq!(ecs.get_component::<Position<i32>>(&entity).unwrap().0, 7);
+
+	let mut sum = 0;
+	ecs.filter().include::<&Position<i32>>().for_each(|p| sum += p.0);
+	assert_eq!(sum, 7);
+}
+
+mod aliased {
+	use crate as ecs;
+
+	// Shadows the crate name, so generated code may only rely on absolute paths.
+	#[allow(dead_code)]
+	mod turbo_ecs {}
+
+	#[derive(Default, ecs::components::Component)]
+	pub struct Hygienic;
+
+	#[derive(Default, ecs::components::Component)]
+	#[component(crate = "ecs")]
+	pub struct Aliased;
+}
+
+#[test]
+pub fn derive_with_aliased_crate() {
+	let hygienic = ComponentType::of::<aliased::Hygienic>();
+	let aliased = ComponentType::of::<aliased::Aliased>();
+	assert_ne!(hygienic.id(), aliased.id());
+	assert_eq!(aliased::Aliased::component_id(), aliased.id());
+
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, aliased::Hygienic, aliased::Aliased);
+	assert!(ecs.get_component::<aliased::Aliased>(&entity).is_some());
+}
+
+#[derive(Default, Component)]
+struct FirstUse;
+
+#[test]
+pub fn component_ids_are_stable() {
+	let ids: Vec<_> = (0..8)
+		.map(|_| std::thread::spawn(ComponentId::of::<FirstUse>))
+		.collect::<Vec<_>>()
+		.into_iter()
+		.map(|t| t.join().unwrap())
+		.collect();
+	assert!(
+		ids.iter().all(|id| *id == ids[0]),
+		"Concurrent first uses produced different ids"
+	);
+	assert_ne!(ids[0], ComponentId::of::<Unversioned>());
+
+	let registered = component_id::registered();
+	let found: Vec<_> = registered.iter().filter(|(_, name)| *name == std::any::type_name::<FirstUse>()).collect();
+	assert_eq!(found.len(), 1);
+	assert_eq!(found[0].0, ids[0]);
+}
+
+#[test]
+pub fn component_ids_follow_first_use() {
+	#[derive(Component)]
+	struct A;
+	#[derive(Component)]
+	struct B;
+	#[derive(Component)]
+	struct C;
+	#[derive(Component)]
+	struct D;
+
+	// Ids follow the order of first use, so registering components in the opposite order reverses them.
+	let (a, b) = (ComponentId::of::<A>(), ComponentId::of::<B>());
+	let (d, c) = (ComponentId::of::<D>(), ComponentId::of::<C>());
+	assert!(a.value() < b.value());
+	assert!(d.value() < c.value());
+
+	let registered = component_id::registered();
+	let position = |id| registered.iter().position(|(r, _)| *r == id).unwrap();
+	assert!(position(a) < position(b) && position(b) < position(d) && position(d) < position(c));
+	assert_eq!(registered[position(c)].1, std::any::type_name::<C>());
+}
+
+#[test]
+pub fn archetype_from_component_ids() {
+	let _ = ComponentType::of::<Unversioned>();
+	let _ = ComponentType::of::<Position<f32>>();
+	let names = [
+		std::any::type_name::<Unversioned>(),
+		std::any::type_name::<Position<f32>>(),
+	];
+	let ids: Vec<_> = names.iter().map(|n| ComponentType::by_name(n).unwrap().id()).collect();
+
+	let mut ecs = EcsContext::new();
+	let archetype = ecs.get_or_create_archetype(&ids).unwrap();
+	assert_eq!(archetype, create_archetype!(ecs, [Position<f32>, Unversioned]));
+
+	let entity = ecs.create_entity_from_archetype(archetype);
+	assert!(ecs.get_component::<Position<f32>>(&entity).is_some());
+
+	let unknown = ComponentId::from_value(usize::MAX / 2);
+	let result = ecs.get_or_create_archetype(&[ids[0], unknown]);
+	assert_eq!(result, Err(UnknownComponentError(unknown)));
+}
+
+#[repr(C)]
+#[derive(Default, Component)]
+struct Packet {
+	tag: u8,
+	payload: u64,
+	checksum: u16,
+}
+
+#[test]
+pub fn component_type_layout() {
+	let packet = ComponentType::of::<Packet>();
+	assert_eq!(packet.size(), 24);
+	assert_eq!(packet.align(), 8);
+
+	let unversioned = ComponentType::of::<Unversioned>();
+	assert_eq!(unversioned.size(), 0);
+	assert_eq!(unversioned.align(), 1);
+
+	let by_id = ComponentType::by_id(packet.id()).unwrap();
+	assert_eq!((by_id.size(), by_id.align()), (packet.size(), packet.align()));
+}
+
+#[derive(Default, Component)]
+#[component(immutable)]
+struct Id(u64);
+
+#[test]
+pub fn immutable_component_access() {
+	let mut ecs = EcsContext::new();
+	let entity = ecs.create_entity();
+	ecs.add_component(&entity, Id(7));
+	assert_eq!(ecs.get_component::<Id>(&entity).map(|id| id.0), Some(7));
+
+	let mut sum = 0;
+	ecs.filter().include::<&Id>().for_each(|id| sum += id.0);
+	assert_eq!(sum, 7);
+
+	ecs.remove_component::<Id>(&entity);
+	ecs.add_component(&entity, Id(8));
+	assert_eq!(ecs.get_component::<Id>(&entity).map(|id| id.0), Some(8));
+}
+
+#[test]
+pub fn immutable_component_mutation_fails_to_compile() {
+	trybuild::TestCases::new().compile_fail("tests/ui/immutable_component.rs");
+}
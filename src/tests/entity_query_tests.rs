@@ -0,0 +1,293 @@
+use crate::components::{Component, ComponentId};
+use crate::entities::{DynQueryBuilder, Entity, EntityFilterForEach, EntityQuery, QueryAccess};
+use crate::context::EcsContext;
+use crate::{create_archetype, query, spawn};
+
+#[derive(Default, Component)]
+struct Mass;
+
+#[derive(Default, Component)]
+struct Frozen;
+
+#[derive(Default, Component)]
+struct Charge;
+
+#[test]
+pub fn dynamic_query_matches_typed() {
+	let mut ecs = EcsContext::new();
+	let _ = create_archetype!(ecs, [Mass]);
+	let _ = create_archetype!(ecs, [Mass, Frozen]);
+	let _ = create_archetype!(ecs, [Mass, Charge]);
+
+	let dynamic = DynQueryBuilder::new()
+		.include_id(ComponentId::of::<Mass>())
+		.exclude_id(ComponentId::of::<Frozen>())
+		.build();
+	let typed = EntityQuery::build().include::<&Mass>().exclude::<&Frozen>().create();
+	assert_eq!(dynamic, typed, "Identical queries should resolve to the same handle");
+
+	let typed = EntityQuery::build().include::<(&Charge, &Mass)>().create();
+	let dynamic = DynQueryBuilder::new()
+		.include_id(ComponentId::of::<Mass>())
+		.include_id(ComponentId::of::<Charge>())
+		.build();
+	assert_eq!(dynamic, typed);
+
+	let archetypes = |query| ecs.explain_query(query).archetypes.iter().map(|a| a.archetype).collect::<Vec<_>>();
+	assert_eq!(archetypes(dynamic), archetypes(typed));
+	assert_eq!(archetypes(dynamic).len(), 1);
+}
+
+#[test]
+pub fn warmed_queries_track_new_archetypes() {
+	let mut ecs = EcsContext::new();
+	let _ = create_archetype!(ecs, [Mass]);
+	let _ = create_archetype!(ecs, [Mass, Frozen]);
+
+	let query = EntityQuery::build().include::<&Mass>().exclude::<&Frozen>().create();
+	assert!(!ecs.explain_query(query).cached);
+	ecs.warm_query_of::<&Mass, &Frozen>();
+	assert!(ecs.explain_query(query).cached);
+
+	let charged = create_archetype!(ecs, [Mass, Charge]);
+	let _ = ecs.create_entities_from_archetype(charged, 3);
+
+	let mut visited = 0;
+	ecs.filter().include::<&Mass>().exclude::<&Frozen>().for_each(|_| visited += 1);
+	assert_eq!(visited, 3, "Archetypes created after warming should be matched");
+	assert_eq!(ecs.explain_query(query).archetypes.len(), 2);
+}
+
+#[test]
+pub fn query_access_tracks_writes() {
+	let access = EntityQuery::build().include::<(&mut Mass, &Charge)>().access();
+	let mass = ComponentId::of::<Mass>().value();
+	let charge = ComponentId::of::<Charge>().value();
+
+	assert!(access.writes().get(mass) && !access.writes().get(charge));
+	assert!(access.reads().get(mass) && access.reads().get(charge));
+	assert_eq!(access.written_names(), [std::any::type_name::<Mass>()]);
+
+	let reader = QueryAccess::of::<(&Charge, &Frozen)>();
+	let writer = QueryAccess::of::<&mut Charge>();
+	assert!(reader.writes().is_empty());
+	assert!(!reader.conflicts_with(&QueryAccess::of::<&Charge>()));
+	assert!(reader.conflicts_with(&writer) && writer.conflicts_with(&reader));
+	assert!(access.conflicts_with(&writer));
+	assert!(!writer.conflicts_with(&QueryAccess::of::<&mut Mass>()));
+
+	let typed = EntityQuery::build().include::<(&Mass, &Charge)>().create();
+	assert_eq!(
+		EntityQuery::build().include::<(&mut Mass, &Charge)>().create(),
+		typed,
+		"Mutability should not affect the query"
+	);
+}
+
+#[test]
+pub fn single_entity_matches() {
+	let mut ecs = EcsContext::new();
+	let mass = create_archetype!(ecs, [Mass]);
+	let frozen = create_archetype!(ecs, [Mass, Frozen]);
+	let moving = ecs.create_entity_from_archetype(mass);
+	let still = ecs.create_entity_from_archetype(frozen);
+	let empty = ecs.create_entity();
+
+	let query = EntityQuery::build().include::<&Mass>().exclude::<&Frozen>().create();
+	assert!(ecs.entity_matches(&moving, query));
+	assert!(!ecs.entity_matches(&still, query));
+	assert!(!ecs.entity_matches(&empty, query));
+	assert!(!ecs.entity_matches(&Entity::null(), query));
+
+	let mut other = EcsContext::new();
+	let foreign = create_archetype!(other, [Mass]);
+	let foreign = other.create_entity_from_archetype(foreign);
+	assert!(!ecs.entity_matches(&foreign, query));
+
+	ecs.destroy_entities(std::slice::from_ref(&moving));
+	assert!(!ecs.entity_matches(&moving, query));
+}
+
+macro_rules! wide_components {
+	($($name: ident),*) => {
+		$(
+			#[derive(Default, Component)]
+			struct $name;
+		)*
+	};
+}
+
+wide_components!(W1, W2, W3, W4, W5, W6, W7, W8, W9, W10, W11, W12, W13, W14);
+
+#[derive(Default, Component)]
+struct W0(u32);
+
+#[derive(Default, Component)]
+struct W15(u32);
+
+type Wide<'l> = (
+	&'l mut W0,
+	&'l W1,
+	&'l W2,
+	&'l W3,
+	&'l W4,
+	&'l W5,
+	&'l W6,
+	&'l W7,
+	&'l W8,
+	&'l W9,
+	&'l W10,
+	&'l W11,
+	&'l W12,
+	&'l W13,
+	&'l W14,
+	&'l W15,
+);
+
+#[test]
+pub fn sixteen_component_query() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(
+		ecs,
+		[W0, W1, W2, W3, W4, W5, W6, W7, W8, W9, W10, W11, W12, W13, W14, W15]
+	);
+	let _ = ecs.create_entities_from_archetype(archetype, 3);
+
+	let mut count = 0;
+	ecs.filter().include::<Wide>().for_each(|(w0, .., w15)| {
+		w0.0 += w15.0 + 1;
+		count += 1;
+	});
+	assert_eq!(count, 3);
+
+	let mut sum = 0;
+	ecs.filter().include::<&W0>().for_each(|w0| sum += w0.0);
+	assert_eq!(sum, 3);
+}
+
+#[test]
+pub fn runtime_id_lists_match_widest_tuple() {
+	let ids = [
+		ComponentId::of::<W0>(),
+		ComponentId::of::<W1>(),
+		ComponentId::of::<W2>(),
+		ComponentId::of::<W3>(),
+		ComponentId::of::<W4>(),
+		ComponentId::of::<W5>(),
+		ComponentId::of::<W6>(),
+		ComponentId::of::<W7>(),
+		ComponentId::of::<W8>(),
+		ComponentId::of::<W9>(),
+		ComponentId::of::<W10>(),
+		ComponentId::of::<W11>(),
+		ComponentId::of::<W12>(),
+		ComponentId::of::<W13>(),
+		ComponentId::of::<W14>(),
+		ComponentId::of::<W15>(),
+	];
+
+	let typed = EntityQuery::build().include::<Wide>().create();
+	let dynamic = DynQueryBuilder::new().include_ids(ids).build();
+	assert_eq!(typed, dynamic);
+
+	// Runtime lists are not bound by the tuple limit.
+	let wider = DynQueryBuilder::new().include_ids(ids).include_id(ComponentId::of::<Mass>()).build();
+	assert_ne!(typed, wider);
+
+	let mut ecs = EcsContext::new();
+	let narrow = create_archetype!(
+		ecs,
+		[W0, W1, W2, W3, W4, W5, W6, W7, W8, W9, W10, W11, W12, W13, W14, W15]
+	);
+	let wide = create_archetype!(
+		ecs,
+		[W0, W1, W2, W3, W4, W5, W6, W7, W8, W9, W10, W11, W12, W13, W14, W15, Mass]
+	);
+	let archetypes = |query| ecs.explain_query(query).archetypes.iter().map(|a| a.archetype).collect::<Vec<_>>();
+	assert_eq!(archetypes(typed), vec![narrow, wide]);
+	assert_eq!(archetypes(wider), vec![wide]);
+}
+
+#[derive(Default, Component)]
+struct Fire(u32);
+
+#[derive(Default, Component)]
+struct Ice;
+
+#[derive(Default, Component)]
+#[component(storage = "sparse")]
+struct Poison;
+
+#[test]
+pub fn include_any_component() {
+	let mut ecs = EcsContext::new();
+	let fire = spawn!(ecs, Mass, Fire(0));
+	let ice = spawn!(ecs, Mass, Ice);
+	let both = spawn!(ecs, Mass, Fire(0), Ice);
+	let poison = spawn!(ecs, Mass, Poison);
+	spawn!(ecs, Mass);
+	spawn!(ecs, Fire(0));
+
+	let mut matched = vec![];
+	ecs.filter()
+		.include::<&Mass>()
+		.include_any::<(&mut Fire, &Ice)>()
+		.entities_for_each(|entity, (fire, ice)| {
+			if let Some(fire) = fire {
+				fire.0 += 1;
+			}
+			matched.push((entity.instance, ice.is_some()));
+		});
+	let mut expected = vec![(fire.instance, false), (ice.instance, true), (both.instance, true)];
+	expected.sort();
+	matched.sort();
+	assert_eq!(matched, expected);
+	assert_eq!(ecs.get_component::<Fire>(&both).unwrap().0, 1);
+
+	let mut matched = vec![];
+	ecs.filter()
+		.include::<&Mass>()
+		.include_any::<(&Ice, &Poison)>()
+		.entities_for_each(|entity, _| matched.push(entity.instance));
+	let mut expected = vec![ice.instance, both.instance, poison.instance];
+	expected.sort();
+	matched.sort();
+	assert_eq!(matched, expected);
+
+	let mut visited = 0;
+	ecs.filter().take(2).include_any::<(&Fire, &Ice)>().for_each(|_| visited += 1);
+	assert_eq!(visited, 2);
+}
+
+#[test]
+pub fn query_macro_matches_chain() {
+	let query = query!(&mut Mass, &Charge; with Frozen; without Fire);
+	let expected = EntityQuery::build().include::<(&mut Mass, &Charge, Frozen)>().exclude::<Fire>().create();
+	assert_eq!(query, expected);
+	assert_eq!(query!(&Mass), EntityQuery::build().include::<&Mass>().create());
+
+	let mut ecs = EcsContext::new();
+	spawn!(ecs, Mass, Charge, Frozen);
+	spawn!(ecs, Mass, Charge, Frozen, Fire(0));
+	spawn!(ecs, Mass, Charge);
+
+	let mut from_macro = 0;
+	query!(&mut Mass, &Charge; with Frozen; without Fire; in ecs).for_each(|(_, _)| from_macro += 1);
+	let mut from_chain = 0;
+	ecs.filter()
+		.include::<Frozen>()
+		.exclude::<Fire>()
+		.include::<(&mut Mass, &Charge)>()
+		.for_each(|(_, _)| from_chain += 1);
+	assert_eq!(from_macro, 1);
+	assert_eq!(from_macro, from_chain);
+
+	let mut masses = 0;
+	query!(&Mass; in ecs).for_each(|_: &Mass| masses += 1);
+	assert_eq!(masses, 3);
+}
+
+#[test]
+pub fn query_macro_rejects_misordered_clauses() {
+	trybuild::TestCases::new().compile_fail("tests/ui/query_macro_order.rs");
+}
@@ -0,0 +1,1621 @@
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::Barrier;
+use crate::archetypes::Archetype;
+use crate::components::{Component, ComponentType, ErasedComponent};
+use crate::context::EcsContext;
+use crate::entities::{
+	ComponentAccessError, ComponentQuery, Disabled, Entity, EntityFilterForEach, EntityFilterParallelForEach,
+	EntityInstance, EntityQuery, Link, EntityRegistry, WeakEntity,
+};
+use crate::data_structures::BitField;
+use crate::{create_archetype, spawn};
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+pub fn shrink_after_mass_destroy() {
+	let mut ecs = EcsContext::new();
+	let survivor = ecs.create_entity();
+	let entities: Vec<_> = ecs.create_entities_from_archetype(Archetype::default(), 10000).collect();
+
+	assert_eq!(ecs.capacity(), 16 + 9985, "Capacity does not match expected capacity");
+
+	ecs.destroy_entities(&entities);
+	unsafe { ecs.shrink_to_fit() };
+
+	assert_eq!(ecs.capacity(), 16, "Unused instance buffers were not freed");
+
+	let entities: Vec<_> = ecs.create_entities_from_archetype(Archetype::default(), 15).collect();
+	assert_eq!(ecs.capacity(), 16, "Available instances were not reused");
+
+	ecs.destroy_entities(&entities);
+	ecs.destroy_entities(&[survivor]);
+	unsafe { ecs.shrink_to_fit() };
+
+	assert_eq!(ecs.capacity(), 0, "Unused instance buffers were not freed");
+}
+
+static LIVE_COMPONENTS: AtomicIsize = AtomicIsize::new(0);
+
+#[derive(Component)]
+struct Counted {
+	_value: u64,
+}
+
+#[derive(Default, Component)]
+struct Marker;
+
+impl Default for Counted {
+	fn default() -> Self {
+		LIVE_COMPONENTS.fetch_add(1, Ordering::Relaxed);
+		Self { _value: 0 }
+	}
+}
+
+impl Drop for Counted {
+	fn drop(&mut self) {
+		LIVE_COMPONENTS.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+#[test]
+pub fn drop_live_components_once() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Counted]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 100).collect();
+	let singles: Vec<_> = (0..100).map(|_| ecs.create_entity_from_archetype(archetype)).collect();
+
+	for entity in entities.iter().step_by(3) {
+		ecs.add_component(entity, Marker);
+	}
+	for entity in entities.iter().step_by(5) {
+		ecs.remove_component::<Counted>(entity);
+	}
+	for entity in singles.iter().step_by(2) {
+		ecs.add_component(entity, Counted::default());
+		ecs.add_component(entity, Marker);
+		ecs.remove_component::<Marker>(entity);
+	}
+
+	let destroyed: Vec<_> = entities.iter().chain(singles.iter()).step_by(7).cloned().collect();
+	ecs.destroy_entities(&destroyed);
+	assert_eq!(LIVE_COMPONENTS.load(Ordering::Relaxed), 200 - 20 - 26);
+
+	drop(ecs);
+	assert_eq!(
+		LIVE_COMPONENTS.load(Ordering::Relaxed),
+		0,
+		"Components were leaked or dropped more than once"
+	);
+}
+
+#[test]
+pub fn explain_unmatched_query() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Counted]);
+	let _ = ecs.create_entities_from_archetype(archetype, 4);
+
+	let query = EntityQuery::build().include::<(&Counted, &Marker)>().create();
+	let explain = ecs.explain_query(query);
+
+	assert!(explain.archetypes.is_empty(), "Query should not match any archetype");
+	assert_eq!(explain.include.len(), 2);
+	assert!(explain.include.iter().any(|n| n.ends_with("Marker")));
+	assert_eq!(
+		explain.exclude.len(),
+		1,
+		"Only disabled entities should be implicitly excluded"
+	);
+	assert!(explain.exclude[0].ends_with("Disabled"));
+
+	let explain = ecs.explain_query(EntityQuery::build().include::<&Counted>().create());
+	assert_eq!(explain.archetypes.len(), 1);
+	assert_eq!(explain.archetypes[0].archetype, archetype);
+	assert_eq!(explain.archetypes[0].entities, 4);
+}
+
+#[derive(Default, Component)]
+struct Position {
+	value: u32,
+}
+
+#[derive(Default, Component)]
+#[component(storage = "sparse")]
+struct Selected {
+	value: u32,
+}
+
+#[test]
+pub fn toggle_sparse_component() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 8).collect();
+	let archetypes = ecs.archetype_store.iter().count();
+
+	for i in 0..10 {
+		for entity in &entities {
+			assert!(ecs.add_component(entity, Selected { value: i }));
+			assert!(!ecs.add_component(entity, Selected { value: i }));
+			assert_eq!(ecs.get_component::<Selected>(entity).unwrap().value, i);
+			assert!(ecs.remove_component::<Selected>(entity));
+			assert!(!ecs.remove_component::<Selected>(entity));
+		}
+	}
+
+	assert_eq!(
+		ecs.archetype_store.iter().count(),
+		archetypes,
+		"Sparse components should not create archetypes"
+	);
+
+	for (i, entity) in entities.iter().enumerate().step_by(2) {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = i as u32;
+		ecs.add_component(entity, Selected { value: i as u32 });
+	}
+
+	let mut selected = 0;
+	ecs.filter().include::<(&Position, &mut Selected)>().for_each(|(position, selected_value)| {
+		assert_eq!(position.value, selected_value.value);
+		selected_value.value += 1;
+		selected += 1;
+	});
+	assert_eq!(selected, 4);
+	assert_eq!(ecs.get_component::<Selected>(&entities[2]).unwrap().value, 3);
+
+	let mut unselected = 0;
+	ecs.filter().include::<&Position>().exclude::<&Selected>().for_each(|_| unselected += 1);
+	assert_eq!(unselected, 4);
+
+	ecs.destroy_entities(&entities[..1]);
+	assert!(ecs.get_component::<Selected>(&entities[2]).is_some());
+}
+
+#[test]
+pub fn shared_parallel_queries() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 1000).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = i as u32;
+		if i % 4 == 0 {
+			ecs.add_component(entity, Selected { value: 1 });
+		}
+	}
+
+	let view = ecs.view();
+	let (sum, selected) = std::thread::scope(|scope| {
+		let sum = scope.spawn(|| {
+			let sum = AtomicIsize::new(0);
+			view.filter().include::<&Position>().par_for_each(|position| {
+				sum.fetch_add(position.value as isize, Ordering::Relaxed);
+			});
+			sum.into_inner()
+		});
+		let selected = scope.spawn(|| {
+			let selected = AtomicIsize::new(0);
+			view.filter().include::<(&Position, &Selected)>().par_for_each(|(_, selected_value)| {
+				selected.fetch_add(selected_value.value as isize, Ordering::Relaxed);
+			});
+			selected.into_inner()
+		});
+		(sum.join().unwrap(), selected.join().unwrap())
+	});
+
+	assert_eq!(sum, 999 * 1000 / 2);
+	assert_eq!(selected, 250);
+}
+
+#[test]
+pub fn concurrent_readers_share_query_cache() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let _ = ecs.create_entities_from_archetype(archetype, 100);
+	spawn!(ecs, Position { value: 1 }, Selected { value: 1 });
+
+	let query = <(&Position, ())>::get_query();
+	assert!(!ecs.archetype_store.is_query_initialized(query));
+
+	let view = ecs.view();
+	let barrier = Barrier::new(2);
+	let counts = std::thread::scope(|scope| {
+		let readers: Vec<_> = (0..2)
+			.map(|_| {
+				scope.spawn(|| {
+					barrier.wait();
+					let mut count = 0;
+					view.filter().include::<&Position>().for_each(|_| count += 1);
+					count
+				})
+			})
+			.collect();
+		readers.into_iter().map(|r| r.join().unwrap()).collect::<Vec<_>>()
+	});
+
+	assert_eq!(counts, [101, 101]);
+	assert!(
+		ecs.archetype_store.is_query_initialized(query),
+		"Query cache was not populated"
+	);
+}
+
+#[test]
+pub fn view_rejects_non_sync_components() {
+	trybuild::TestCases::new().compile_fail("tests/ui/view_non_sync_component.rs");
+}
+
+#[test]
+pub fn read_only_queries_through_shared_registry() {
+	fn sum_positions(registry: &EntityRegistry) -> u32 {
+		let mut sum = 0;
+		registry.filter_ref().include::<&Position>().for_each(|position| {
+			registry.filter_ref().include::<(&Position, &Selected)>().for_each(|(other, _)| {
+				sum += position.value * other.value;
+			});
+		});
+		sum
+	}
+
+	let mut ecs = EcsContext::new();
+	for value in 1..=4 {
+		spawn!(ecs, Position { value });
+	}
+	spawn!(ecs, Position { value: 10 }, Selected { value: 0 });
+
+	let query = <(&Position, ())>::get_query();
+	assert!(!ecs.archetype_store.is_query_initialized(query));
+	assert_eq!(sum_positions(&ecs), (1 + 2 + 3 + 4 + 10) * 10);
+	assert!(
+		ecs.archetype_store.is_query_initialized(query),
+		"Query cache was not initialized"
+	);
+
+	spawn!(ecs, Position { value: 5 });
+	assert_eq!(sum_positions(&ecs), (1 + 2 + 3 + 4 + 5 + 10) * 10);
+}
+
+#[test]
+pub fn chained_includes_accumulate() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	let _ = ecs.create_entities_from_archetype(positions, 3);
+	let _ = ecs.create_entities_from_archetype(markers, 5);
+
+	let mut count = 0;
+	ecs.filter().include::<&Marker>().include::<&Position>().for_each(|_| count += 1);
+	assert_eq!(count, 5, "Both includes should apply");
+
+	let mut count = 0;
+	ecs.filter().include::<&Position>().for_each(|_| count += 1);
+	assert_eq!(count, 8);
+}
+
+#[test]
+pub fn list_component_types() {
+	let mut ecs = EcsContext::new();
+	let entity = ecs.create_entity();
+	assert!(ecs.component_types(&entity).is_empty());
+
+	ecs.add_component(&entity, Position::default());
+	ecs.add_component(&entity, Marker);
+	ecs.add_component(&entity, Selected::default());
+
+	let types = ecs.component_types(&entity);
+	assert_eq!(types.len(), 2);
+	assert!(types.contains(&ComponentType::of::<Position>()));
+	assert!(types.contains(&ComponentType::of::<Marker>()));
+
+	let names: Vec<_> = types.iter().map(|t| t.id().name()).collect();
+	assert!(names.iter().any(|n| n.ends_with("Position")));
+}
+
+struct Targets;
+struct Follows;
+
+#[test]
+pub fn links_removed_on_target_destroy() {
+	let mut ecs = EcsContext::new();
+	let target = ecs.create_entity();
+	let other = ecs.create_entity();
+	let holders: Vec<_> = (0..4).map(|_| ecs.create_entity()).collect();
+
+	for holder in &holders {
+		ecs.add_component(holder, Position::default());
+		ecs.add_component(holder, Link::<Targets>::new(target.clone()));
+		ecs.add_component(holder, Link::<Follows>::new(other.clone()));
+	}
+
+	ecs.destroy_entities(&holders[..1]);
+	ecs.remove_component::<Link<Targets>>(&holders[1]);
+	ecs.add_component(&holders[1], Link::<Targets>::new(other.clone()));
+
+	ecs.destroy_entities(&[target]);
+	assert!(ecs.get_component::<Link<Targets>>(&holders[1]).is_some());
+	for holder in &holders[2..] {
+		assert!(
+			ecs.get_component::<Link<Targets>>(holder).is_none(),
+			"Link to destroyed entity was not removed"
+		);
+		assert!(ecs.get_component::<Link<Follows>>(holder).is_some());
+		assert!(ecs.get_component::<Position>(holder).is_some());
+	}
+
+	ecs.destroy_entities(&[other]);
+	for holder in &holders[1..] {
+		assert!(ecs.get_component::<Link<Targets>>(holder).is_none());
+		assert!(ecs.get_component::<Link<Follows>>(holder).is_none());
+	}
+}
+
+#[test]
+pub fn cloned_links_removed_on_target_destroy() {
+	let mut ecs = EcsContext::new();
+	let target = ecs.create_entity();
+	let holder = ecs.create_entity();
+	ecs.add_component(&holder, Link::<Targets>::new(target.clone()));
+
+	let clone = ecs.clone_entity(&holder);
+	assert!(ecs.get_component::<Link<Targets>>(&clone).unwrap().target().instance == target.instance);
+
+	ecs.destroy_entities(&[target]);
+	assert!(ecs.get_component::<Link<Targets>>(&holder).is_none());
+	assert!(
+		ecs.get_component::<Link<Targets>>(&clone).is_none(),
+		"Link of cloned entity was not removed"
+	);
+}
+
+#[test]
+pub fn component_access_errors() {
+	let mut ecs = EcsContext::new();
+	let mut other = EcsContext::new();
+	let entity = ecs.create_entity();
+	let foreign = other.create_entity();
+	let destroyed = ecs.create_entity();
+	ecs.add_component(&entity, Position { value: 7 });
+	ecs.destroy_entities(std::slice::from_ref(&destroyed));
+
+	assert_eq!(ecs.try_get_component::<Position>(&entity).map(|p| p.value), Ok(7));
+	assert_eq!(
+		ecs.try_get_component::<Marker>(&entity).err(),
+		Some(ComponentAccessError::MissingComponent)
+	);
+	assert_eq!(
+		ecs.try_get_component::<Position>(&destroyed).err(),
+		Some(ComponentAccessError::DeadEntity)
+	);
+	assert_eq!(
+		ecs.try_get_component::<Position>(&foreign).err(),
+		Some(ComponentAccessError::WrongContext)
+	);
+}
+
+#[test]
+pub fn filter_into_iterator() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 8).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = i as u32;
+		if i % 2 == 0 {
+			ecs.add_component(entity, Selected { value: i as u32 });
+		}
+	}
+
+	for (position, selected) in ecs.filter().include::<(&mut Position, &Selected)>() {
+		position.value += selected.value;
+	}
+
+	let mut iterated = Vec::new();
+	for position in ecs.filter().include::<&Position>().exclude::<&Selected>() {
+		iterated.push(position.value);
+	}
+	assert_eq!(iterated, vec![1, 3, 5, 7]);
+
+	let mut expected = Vec::new();
+	ecs.filter().include::<&Position>().for_each(|position| expected.push(position.value));
+	let iterated: Vec<_> = ecs.filter().include::<&Position>().into_iter().map(|p| p.value).collect();
+	assert_eq!(iterated, expected);
+	assert_eq!(iterated, vec![0, 1, 4, 3, 8, 5, 12, 7]);
+}
+
+#[test]
+pub fn preallocated_capacity() {
+	let mut ecs = EcsContext::with_capacity(1000);
+	assert_eq!(ecs.capacity(), 1000);
+
+	let archetype = create_archetype!(ecs, [Position]);
+	let single: Vec<_> = (0..500).map(|_| ecs.create_entity_from_archetype(archetype)).collect();
+	let batch: Vec<_> = ecs.create_entities_from_archetype(archetype, 500).collect();
+	assert_eq!(single.len() + batch.len(), 1000);
+	assert_eq!(
+		ecs.capacity(),
+		1000,
+		"Creating entities within capacity should not allocate"
+	);
+
+	ecs.create_entity();
+	assert!(ecs.capacity() > 1000);
+}
+
+#[test]
+pub fn iteration_is_slot_ascending() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 16).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = i as u32;
+	}
+
+	let destroyed: Vec<_> = entities.iter().skip(1).step_by(2).rev().cloned().collect();
+	ecs.destroy_entities(&destroyed);
+	let spawned: Vec<_> = ecs.create_entities_from_archetype(archetype, 4).collect();
+	for (i, entity) in spawned.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = 100 + i as u32;
+	}
+
+	let expected = vec![0, 100, 2, 101, 4, 102, 6, 103, 8, 10, 12, 14];
+	let mut visited = Vec::new();
+	ecs.filter().include::<&Position>().for_each(|position| visited.push(position.value));
+	assert_eq!(visited, expected);
+
+	let iterated: Vec<_> = ecs.filter().include::<&Position>().into_iter().map(|p| p.value).collect();
+	assert_eq!(iterated, expected);
+}
+
+#[test]
+pub fn batch_component_presence() {
+	let mut ecs = EcsContext::new();
+	let with_position = create_archetype!(ecs, [Position]);
+	let without_position = create_archetype!(ecs, [Marker]);
+
+	let mut entities = Vec::new();
+	for i in 0..40 {
+		let archetype = if i % 3 == 0 { without_position } else { with_position };
+		let entity = ecs.create_entity_from_archetype(archetype);
+		if i % 4 == 0 {
+			ecs.add_component(&entity, Selected { value: i });
+		}
+		entities.push(entity);
+	}
+
+	let mut out = BitField::new();
+	ecs.components_present::<Position>(&entities, &mut out);
+	for i in 0..entities.len() {
+		assert_eq!(out.get(i), i % 3 != 0, "Wrong table presence for entity {i}");
+	}
+
+	ecs.components_present::<Selected>(&entities, &mut out);
+	for i in 0..entities.len() {
+		assert_eq!(out.get(i), i % 4 == 0, "Wrong sparse presence for entity {i}");
+	}
+
+	ecs.components_present::<Counted>(&entities[..4], &mut out);
+	assert!(out.is_empty());
+}
+
+#[derive(Default, Clone, Component)]
+struct Inventory {
+	owner: u32,
+	items: Vec<u8>,
+}
+
+#[derive(Default, Clone, Component)]
+#[component(storage = "sparse")]
+struct Tags {
+	tags: Vec<u8>,
+}
+
+#[test]
+pub fn clone_entity_deep_copies() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Inventory]);
+	let original = ecs.create_entity_from_archetype(archetype);
+	*ecs.get_component_mut::<Inventory>(&original).unwrap() = Inventory {
+		owner: 3,
+		items: vec![1, 2, 3],
+	};
+	ecs.add_component(&original, Tags { tags: vec![4] });
+
+	let clone = ecs.clone_entity(&original);
+	ecs.get_component_mut::<Inventory>(&clone).unwrap().items.push(4);
+	ecs.get_component_mut::<Tags>(&clone).unwrap().tags.clear();
+
+	assert_eq!(ecs.get_component::<Inventory>(&clone).unwrap().owner, 3);
+	assert_eq!(ecs.get_component::<Inventory>(&clone).unwrap().items, [1, 2, 3, 4]);
+	assert_eq!(ecs.get_component::<Inventory>(&original).unwrap().items, [1, 2, 3]);
+	assert_eq!(ecs.get_component::<Tags>(&original).unwrap().tags, [4]);
+	assert!(ecs.get_component::<Tags>(&clone).unwrap().tags.is_empty());
+
+	ecs.destroy_entities(std::slice::from_ref(&original));
+	assert_eq!(ecs.get_component::<Inventory>(&clone).unwrap().items, [1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "Entity contains components that do not implement Clone")]
+pub fn clone_entity_requires_clone() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Marker]);
+	let entity = ecs.create_entity_from_archetype(archetype);
+	ecs.clone_entity(&entity);
+}
+
+#[test]
+pub fn empty_include_matches_all_archetypes() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	ecs.create_entities_from_archetype(positions, 3).for_each(drop);
+	ecs.create_entities_from_archetype(markers, 2).for_each(drop);
+	let empty = ecs.create_entity();
+	ecs.add_component(&empty, Selected { value: 0 });
+	ecs.create_entity();
+
+	let mut visited = 0;
+	ecs.filter().include::<()>().for_each(|()| visited += 1);
+	assert_eq!(visited, 7);
+
+	let mut visited = 0;
+	ecs.filter().include::<()>().entities_for_each(|_, ()| visited += 1);
+	assert_eq!(visited, 7);
+
+	let mut visited = 0;
+	ecs.filter().include::<()>().exclude::<&Marker>().for_each(|()| visited += 1);
+	assert_eq!(visited, 5);
+
+	let mut visited = 0;
+	ecs.filter().include::<&Selected>().for_each(|_| visited += 1);
+	assert_eq!(visited, 1);
+}
+
+#[test]
+pub fn memory_stats_match_capacities() {
+	let mut ecs = EcsContext::with_capacity(64);
+	let position = ComponentType::of::<Position>();
+	let inventory = ComponentType::of::<Inventory>();
+	ecs.create_archetype_with_capacity(std::slice::from_ref(&position), 100);
+	ecs.create_archetype_with_capacity(&[position, inventory], 10);
+
+	let stats = ecs.memory_stats();
+	let position_bytes = 110 * size_of::<Position>();
+	let inventory_bytes = 10 * size_of::<Inventory>();
+
+	assert_eq!(stats.instance_bytes, 64 * size_of::<EntityInstance>());
+	assert_eq!(stats.archetype_bytes, position_bytes + inventory_bytes);
+	assert_eq!(stats.components.len(), 2);
+	assert_eq!(stats.components[std::any::type_name::<Position>()], position_bytes);
+	assert_eq!(stats.components[std::any::type_name::<Inventory>()], inventory_bytes);
+}
+
+#[test]
+pub fn limited_iteration() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	ecs.create_entities_from_archetype(positions, 30).for_each(drop);
+	ecs.create_entities_from_archetype(markers, 30).for_each(drop);
+
+	for (limit, expected) in [(0, 0), (10, 10), (45, 45), (60, 60), (100, 60)] {
+		let mut visited = 0;
+		ecs.filter().include::<&mut Position>().take(limit).for_each(|_| visited += 1);
+		assert_eq!(visited, expected, "for_each with limit {limit}");
+
+		let mut visited = 0;
+		ecs.filter().include::<&Position>().take(limit).entities_for_each(|_, _| visited += 1);
+		assert_eq!(visited, expected, "entities_for_each with limit {limit}");
+
+		let visited = AtomicIsize::new(0);
+		ecs.filter().include::<&Position>().take(limit).par_for_each(|_| {
+			visited.fetch_add(1, Ordering::Relaxed);
+		});
+		assert_eq!(
+			visited.into_inner(),
+			expected as isize,
+			"par_for_each with limit {limit}"
+		);
+
+		let iterated = ecs.filter().include::<&Position>().take(limit).into_iter().count();
+		assert_eq!(iterated, expected, "into_iter with limit {limit}");
+	}
+
+	let mut visited = 0;
+	ecs.filter().include::<&Position>().take(50).exclude::<&Marker>().for_each(|_| visited += 1);
+	assert_eq!(visited, 30);
+}
+
+#[test]
+pub fn entities_outliving_registry() {
+	let entity = {
+		let mut ecs = EcsContext::new();
+		let entity = ecs.create_entity();
+		ecs.add_component(&entity, Position { value: 1 });
+		entity
+	};
+
+	let mut ecs = EcsContext::new();
+	ecs.create_entities_from_archetype(Default::default(), 16).for_each(drop);
+	assert_eq!(
+		ecs.try_get_component::<Position>(&entity).err(),
+		Some(ComponentAccessError::WrongContext)
+	);
+}
+
+#[test]
+#[should_panic(expected = "Entity does not belong to this context")]
+pub fn dangling_entity_panics() {
+	let entity = EcsContext::new().create_entity();
+	let mut ecs = EcsContext::new();
+	ecs.add_component(&entity, Position { value: 1 });
+}
+
+#[test]
+pub fn free_capacity_counts() {
+	let mut ecs = EcsContext::with_capacity(32);
+	let position = ComponentType::of::<Position>();
+	let archetype = ecs.create_archetype_with_capacity(&[position], 20);
+	assert_eq!(ecs.available_capacity(), 32);
+	assert_eq!(ecs.archetype_free_slots(archetype), 20);
+
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 12).collect();
+	assert_eq!(ecs.available_capacity(), 20);
+	assert_eq!(ecs.archetype_free_slots(archetype), 8);
+
+	ecs.destroy_entities(&entities[..4]);
+	assert_eq!(ecs.available_capacity(), 24);
+	assert_eq!(ecs.archetype_free_slots(archetype), 12);
+
+	ecs.create_entity();
+	assert_eq!(ecs.available_capacity(), 23);
+	assert_eq!(ecs.archetype_free_slots(archetype), 12);
+}
+
+#[test]
+pub fn single_component_mutation() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(positions, 10).collect();
+	entities.extend(ecs.create_entities_from_archetype(markers, 10));
+	ecs.create_entity();
+
+	ecs.for_each_component_mut::<Position>(|position| position.value += 1);
+	ecs.par_for_each_component_mut::<Position>(|position| position.value *= 3);
+
+	for entity in &entities {
+		assert_eq!(ecs.get_component::<Position>(entity).unwrap().value, 3);
+	}
+}
+
+#[derive(Default, Component)]
+struct Health {
+	value: i32,
+}
+
+#[test]
+pub fn despawn_below_health_threshold() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Health]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 10).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Health>(entity).unwrap().value = i as i32 - 4;
+	}
+
+	ecs.despawn_where::<&Health, ()>(|health| health.value <= 0);
+
+	let mut survivors = 0;
+	ecs.filter().include::<&Health>().for_each(|health| {
+		assert!(health.value > 0);
+		survivors += 1;
+	});
+	assert_eq!(survivors, 5);
+	for (i, entity) in entities[5..].iter().enumerate() {
+		assert_eq!(ecs.get_component::<Health>(entity).unwrap().value, i as i32 + 1);
+	}
+}
+
+#[derive(Default, Component)]
+struct Impulse {
+	value: u32,
+}
+
+#[test]
+pub fn consume_impulses() {
+	let mut ecs = EcsContext::new();
+	let impulses = create_archetype!(ecs, [Position, Impulse]);
+	let marked = create_archetype!(ecs, [Position, Impulse, Marker]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(impulses, 8).collect();
+	entities.extend(ecs.create_entities_from_archetype(marked, 5));
+	for entity in &entities {
+		ecs.get_component_mut::<Impulse>(entity).unwrap().value = 3;
+	}
+	ecs.destroy_entities(&entities[2..4]);
+	entities.drain(2..4);
+	let still = create_archetype!(ecs, [Position]);
+	let still = ecs.create_entity_from_archetype(still);
+
+	ecs.filter()
+		.include::<(&mut Position, &Impulse)>()
+		.for_each_then_remove::<Impulse>(|(position, impulse)| position.value += impulse.value);
+
+	for entity in &entities {
+		assert_eq!(ecs.get_component::<Position>(entity).unwrap().value, 3);
+		assert!(ecs.get_component::<Impulse>(entity).is_none());
+	}
+	assert_eq!(ecs.get_component::<Position>(&still).unwrap().value, 0);
+
+	let mut remaining = 0;
+	ecs.filter().include::<&Impulse>().for_each(|_| remaining += 1);
+	assert_eq!(remaining, 0);
+
+	assert!(ecs.archetype_store.get(impulses.index).is_empty());
+	assert!(ecs.archetype_store.get(marked.index).is_empty());
+	let mut markers = 0;
+	ecs.filter().include::<(&Position, &Marker)>().for_each(|(position, _)| {
+		assert_eq!(position.value, 3);
+		markers += 1;
+	});
+	assert_eq!(markers, 5);
+}
+
+#[test]
+pub fn null_entities() {
+	assert!(Entity::null().is_null());
+	assert!(Entity::default().is_null());
+
+	let mut ecs = EcsContext::new();
+	let entity = ecs.create_entity();
+	assert!(!entity.is_null());
+	ecs.destroy_entities(std::slice::from_ref(&entity));
+	assert!(!entity.is_null());
+}
+
+#[test]
+pub fn entity_bits_round_trip() {
+	let mut ecs = EcsContext::new();
+	let other = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let mut entities: Vec<_> = (0..10).map(|_| ecs.create_entity()).collect();
+	entities.extend(ecs.create_entities_from_archetype(archetype, 100));
+
+	for entity in &entities {
+		let bits = entity.to_bits(&ecs);
+		assert_ne!(bits, 0);
+		let unpacked = Entity::from_bits(bits, &ecs);
+		assert!(unpacked.instance == entity.instance && unpacked.version == entity.version);
+		assert!(Entity::from_bits(bits, &other).is_null());
+	}
+
+	let bits = entities[3].to_bits(&ecs);
+	ecs.destroy_entities(&entities[3..4]);
+	assert_eq!(ecs.validate_entities(&[Entity::from_bits(bits, &ecs)]), [false]);
+	let recreated = ecs.create_entity();
+	assert_ne!(recreated.to_bits(&ecs), bits);
+
+	ecs.destroy_entities(&[recreated]);
+	ecs.destroy_entities(&entities[..3]);
+	ecs.destroy_entities(&entities[4..]);
+	unsafe { ecs.shrink_to_fit() };
+	assert!(Entity::from_bits(bits, &ecs).is_null(), "Freed instance was unpacked");
+}
+
+#[test]
+pub fn null_entity_bits_round_trip() {
+	let ecs = EcsContext::new();
+	assert_eq!(Entity::null().to_bits(&ecs), 0);
+	assert!(Entity::from_bits(0, &ecs).is_null());
+	assert!(Entity::from_bits(Entity::null().to_bits(&ecs), &ecs).is_null());
+}
+
+#[test]
+pub fn parallel_fold_matches_sequential() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(positions, 60000).collect();
+	entities.extend(ecs.create_entities_from_archetype(markers, 40000));
+	ecs.destroy_entities(&entities[1000..2000]);
+	for (i, entity) in entities.iter().enumerate().filter(|(i, _)| !(1000..2000).contains(i)) {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = (i * 7 % 1013) as u32;
+	}
+
+	let (mut sum, mut min, mut max) = (0u64, u32::MAX, 0);
+	ecs.filter().include::<&Position>().for_each(|position| {
+		sum += position.value as u64;
+		min = min.min(position.value);
+		max = max.max(position.value);
+	});
+
+	let par_sum = ecs.filter().include::<&Position>().par_fold(|| 0u64, |acc, p| acc + p.value as u64, |a, b| a + b);
+	assert_eq!(par_sum, sum);
+
+	let bounds = ecs.filter().include::<&Position>().par_fold(
+		|| (u32::MAX, 0),
+		|(min, max), p| (min.min(p.value), max.max(p.value)),
+		|a, b| (a.0.min(b.0), a.1.max(b.1)),
+	);
+	assert_eq!(bounds, (min, max));
+
+	let count = ecs.filter().include::<&Position>().take(500).par_fold(|| 0, |acc, _| acc + 1, |a, b| a + b);
+	assert_eq!(count, 500);
+}
+
+#[test]
+pub fn sorted_iteration() {
+	let mut ecs = EcsContext::new();
+	let positions = create_archetype!(ecs, [Position]);
+	let markers = create_archetype!(ecs, [Position, Marker]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(positions, 20).collect();
+	entities.extend(ecs.create_entities_from_archetype(markers, 20));
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = (i * 17 % 40) as u32;
+	}
+
+	let mut order = vec![];
+	ecs.filter().include::<&mut Position>().for_each_sorted_by(
+		|position| std::cmp::Reverse(position.value),
+		|position| {
+			order.push(position.value);
+			position.value = 0;
+		},
+	);
+
+	assert_eq!(order, (0..40).rev().collect::<Vec<_>>());
+	ecs.filter().include::<&Position>().for_each(|position| assert_eq!(position.value, 0));
+}
+
+#[test]
+pub fn initialize_while_spawning() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	for (i, entity) in ecs.create_entities_from_archetype(archetype, 8).enumerate() {
+		ecs.get_component_mut::<Position>(&entity).unwrap().value = i as u32;
+	}
+
+	let mut values = vec![];
+	ecs.filter().include::<&Position>().for_each(|position| values.push(position.value));
+	assert_eq!(values, (0..8).collect::<Vec<_>>());
+}
+
+#[derive(Default, Component, PartialEq)]
+enum Faction {
+	#[default]
+	Neutral,
+	Enemy,
+}
+
+#[test]
+pub fn filter_by_component_value() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position, Faction]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 30).collect();
+	for entity in entities.iter().step_by(3) {
+		*ecs.get_component_mut::<Faction>(entity).unwrap() = Faction::Enemy;
+	}
+
+	let enemies = ecs.filter().include::<&Faction>().filter_value(|faction| **faction == Faction::Enemy).count();
+	assert_eq!(enemies, 10);
+
+	ecs.filter()
+		.include::<(&mut Position, &Faction)>()
+		.filter_value(|(_, faction)| **faction == Faction::Enemy)
+		.take(4)
+		.for_each(|(position, _)| position.value = 1);
+
+	let mut moved = 0;
+	ecs.filter().include::<&Position>().for_each(|position| moved += position.value);
+	assert_eq!(moved, 4);
+}
+
+#[derive(Default, Component)]
+struct Source {
+	value: u32,
+}
+
+#[test]
+pub fn disjoint_query_set() {
+	let mut ecs = EcsContext::new();
+	let sources = create_archetype!(ecs, [Source, Marker]);
+	let targets = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(sources, 5).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Source>(entity).unwrap().value = i as u32 + 1;
+	}
+	let _ = ecs.create_entities_from_archetype(targets, 5);
+
+	let mut set = ecs.query_set();
+	let sources = set.iter::<&Source, ()>();
+	let markers = set.iter::<&Marker, ()>();
+	let targets = set.iter::<&mut Position, &Marker>();
+	for ((source, _), target) in sources.zip(markers).zip(targets) {
+		target.value = source.value * 10;
+	}
+
+	let mut values = vec![];
+	ecs.filter().include::<&Position>().for_each(|position| values.push(position.value));
+	assert_eq!(values, [10, 20, 30, 40, 50]);
+}
+
+#[test]
+#[should_panic(expected = "Query conflicts with a previous query of the QuerySet")]
+pub fn conflicting_query_set() {
+	let mut ecs = EcsContext::new();
+	let mut set = ecs.query_set();
+	let _reader = set.iter::<&Position, ()>();
+	let _writer = set.iter::<&mut Position, &Marker>();
+}
+
+#[test]
+pub fn query_set_shares_reads() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Source, Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 4).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Source>(entity).unwrap().value = i as u32;
+		ecs.add_component(entity, Selected { value: i as u32 * 2 });
+	}
+
+	let mut set = ecs.query_set();
+	let sources = set.iter::<&Source, ()>();
+	let selected = set.iter::<(&Source, &Selected), ()>();
+	let targets = set.iter::<&mut Position, ()>();
+	for ((source, (again, selected)), target) in sources.zip(selected).zip(targets) {
+		target.value = source.value + again.value + selected.value;
+	}
+
+	let mut set = ecs.query_set();
+	let sources = set.iter::<&Source, ()>();
+	let selected = set.iter::<&mut Selected, ()>();
+	for (source, selected) in sources.zip(selected) {
+		selected.value += source.value;
+	}
+
+	let mut values = vec![];
+	ecs.filter().include::<(&Position, &Selected)>().for_each(|(p, s)| values.push((p.value, s.value)));
+	assert_eq!(values, [(0, 0), (4, 3), (8, 6), (12, 9)]);
+}
+
+#[test]
+pub fn query_set_requests_during_iteration() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Source, Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 4).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Source>(entity).unwrap().value = i as u32 + 1;
+	}
+
+	let mut set = ecs.query_set();
+	let mut targets = set.iter::<&mut Position, ()>();
+	let first = targets.next().unwrap();
+	let mut sources = set.iter::<&Source, ()>();
+	first.value = sources.next().unwrap().value * 10;
+	for (source, target) in sources.zip(targets) {
+		target.value = source.value * 10;
+	}
+
+	let mut values = vec![];
+	ecs.filter().include::<&Position>().for_each(|position| values.push(position.value));
+	assert_eq!(values, [10, 20, 30, 40]);
+}
+
+#[test]
+#[should_panic(expected = "Query conflicts with a previous query of the QuerySet")]
+pub fn query_set_excluded_sparse_conflict() {
+	let mut ecs = EcsContext::new();
+	let mut set = ecs.query_set();
+	let _writer = set.iter::<&mut Selected, ()>();
+	let _filtered = set.iter::<&Position, &Selected>();
+}
+
+#[test]
+pub fn observe_entity_lifecycle() {
+	let created = Rc::new(Cell::new(0));
+	let destroyed = Rc::new(Cell::new(0));
+
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let empty = ecs.create_entity();
+	{
+		let created = created.clone();
+		ecs.on_entity_created(move |_| created.set(created.get() + 1));
+		let destroyed = destroyed.clone();
+		ecs.on_entity_destroyed(move |entity| {
+			assert!(!entity.is_null());
+			destroyed.set(destroyed.get() + 1);
+		});
+	}
+
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 10).collect();
+	entities.push(ecs.create_entity_from_archetype(archetype));
+	entities.push(spawn!(ecs, Position { value: 1 }));
+	entities.push(ecs.clone_entity(&empty));
+	assert_eq!(created.get(), 13);
+
+	ecs.destroy_entities(&entities[..5]);
+	assert_eq!(destroyed.get(), 5);
+	ecs.destroy_entities(&entities[5..]);
+	assert_eq!(destroyed.get(), 13);
+}
+
+#[test]
+pub fn transfer_between_registries() {
+	let mut src = EcsContext::new();
+	let mut dst = EcsContext::new();
+	let _ = dst.create_entity();
+
+	let entity = spawn!(
+		src,
+		Position { value: 4 },
+		Selected { value: 2 },
+		Tags { tags: vec![1, 2, 3] }
+	);
+	let other = spawn!(src, Position { value: 8 });
+	let moved = src.transfer_entity(&entity, &mut dst);
+
+	assert_eq!(dst.get_component::<Position>(&moved).unwrap().value, 4);
+	assert_eq!(dst.get_component::<Selected>(&moved).unwrap().value, 2);
+	assert_eq!(dst.get_component::<Tags>(&moved).unwrap().tags, [1, 2, 3]);
+	assert!(src.get_component::<Position>(&other).is_some());
+
+	let query = EntityQuery::build().include::<&Position>().create();
+	assert!(!src.entity_matches(&entity, query));
+	assert!(dst.entity_matches(&moved, query));
+	assert_eq!(src.explain_query(query).archetypes[0].entities, 1);
+
+	let mut tagged = 0;
+	src.filter().include::<&Tags>().for_each(|_| tagged += 1);
+	assert_eq!(tagged, 0);
+}
+
+#[derive(Default, Component)]
+struct Velocity {
+	value: u32,
+}
+
+#[derive(Default, Component)]
+struct Mass {
+	value: u32,
+}
+
+#[test]
+pub fn add_bundle_in_one_transition() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position { value: 1 });
+	let everything = EntityQuery::build().create();
+	let archetypes = ecs.explain_query(everything).archetypes.len();
+
+	let bundle = (Velocity { value: 2 }, Mass { value: 3 }, Selected { value: 4 });
+	assert!(ecs.add_bundle(&entity, bundle));
+	assert_eq!(ecs.explain_query(everything).archetypes.len(), archetypes + 1);
+
+	assert_eq!(ecs.get_component::<Position>(&entity).unwrap().value, 1);
+	assert_eq!(ecs.get_component::<Velocity>(&entity).unwrap().value, 2);
+	assert_eq!(ecs.get_component::<Mass>(&entity).unwrap().value, 3);
+	assert_eq!(ecs.get_component::<Selected>(&entity).unwrap().value, 4);
+
+	assert!(!ecs.add_bundle(&entity, (Tags::default(), Mass { value: 5 })));
+	assert!(ecs.get_component::<Tags>(&entity).is_none());
+	assert_eq!(ecs.get_component::<Mass>(&entity).unwrap().value, 3);
+}
+
+#[test]
+pub fn collected_entities_reuse_storage() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let _ = ecs.create_entities_from_archetype(archetype, 100);
+
+	let storage = {
+		let entities = ecs.filter().include::<&Position>().collect_entities();
+		assert_eq!(entities.len(), 100);
+		entities.as_ptr()
+	};
+
+	for _ in 0..10 {
+		let entities = ecs.filter().include::<&Position>().collect_entities();
+		assert_eq!(entities.len(), 100);
+		assert_eq!(entities.as_ptr(), storage, "Collected entities were not reused");
+	}
+}
+
+#[test]
+pub fn sorted_iteration_reuses_storage() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 100).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = (i % 10) as u32;
+	}
+
+	let mut sorted = vec![];
+	ecs.filter().include::<&Position>().for_each_sorted_by(|p| p.value, |p| sorted.push(p.value));
+	assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+	let storage = ecs.usize_vec_pool.take_one().as_ptr();
+	let items = ecs.scratch_pool.take_one().as_ptr();
+
+	for _ in 0..10 {
+		ecs.filter().include::<&Position>().for_each_sorted_by(|p| p.value, |_| {});
+		let order = ecs.usize_vec_pool.take_one();
+		assert_eq!(order.as_ptr(), storage, "Sort order storage was not reused");
+		assert_eq!(
+			ecs.scratch_pool.take_one().as_ptr(),
+			items,
+			"Sorted item storage was not reused"
+		);
+	}
+}
+
+#[test]
+pub fn visit_entity_components() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position { value: 3 }, Selected { value: 5 }, Marker);
+	let _ = spawn!(ecs, Position { value: 7 }, Tags::default());
+
+	let mut visited = vec![];
+	let mut sum = 0;
+	ecs.visit_components(&entity, &mut |component: ErasedComponent| {
+		visited.push(component.id());
+		if let Some(position) = component.downcast_ref::<Position>() {
+			sum += position.value;
+		}
+		if let Some(selected) = component.downcast_ref::<Selected>() {
+			sum += selected.value;
+		}
+		assert!(component.downcast_ref::<Tags>().is_none());
+	});
+
+	assert_eq!(sum, 8);
+	assert_eq!(visited.len(), 3);
+	for id in [
+		Position::component_id(),
+		Selected::component_id(),
+		Marker::component_id(),
+	] {
+		assert!(visited.contains(&id));
+	}
+}
+
+#[test]
+pub fn upgrade_weak_entities() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position { value: 9 });
+	let weak = entity.downgrade();
+
+	let upgraded = weak.upgrade(&ecs).unwrap();
+	assert_eq!(ecs.get_component::<Position>(&upgraded).unwrap().value, 9);
+	assert!(weak.upgrade(&EcsContext::new()).is_none());
+	assert!(WeakEntity::default().upgrade(&ecs).is_none());
+
+	ecs.destroy_entities(&[entity]);
+	assert!(weak.upgrade(&ecs).is_none());
+
+	unsafe { ecs.shrink_to_fit() };
+	let _ = ecs.create_entities_from_archetype(Archetype::default(), 64);
+	assert!(
+		weak.upgrade(&ecs).is_none(),
+		"Reused instance memory resurrected a weak entity"
+	);
+}
+
+#[test]
+pub fn spawn_empty_in_one_transition() {
+	let mut ecs = EcsContext::new();
+	let archetypes = ecs.archetype_store.iter().count();
+
+	let entity = ecs
+		.spawn_empty()
+		.insert(Position { value: 1 })
+		.insert(Velocity { value: 2 })
+		.insert(Selected { value: 3 })
+		.insert(Mass { value: 4 })
+		.insert(Position { value: 5 })
+		.id();
+
+	assert_eq!(
+		ecs.archetype_store.iter().count(),
+		archetypes + 1,
+		"Intermediate archetypes were created"
+	);
+	let archetype = ecs.create_archetype(&[
+		ComponentType::of::<Position>(),
+		ComponentType::of::<Velocity>(),
+		ComponentType::of::<Mass>(),
+	]);
+	assert_eq!(unsafe { (*entity.instance).archetype }, archetype.index);
+
+	assert_eq!(ecs.get_component::<Position>(&entity).unwrap().value, 5);
+	assert_eq!(ecs.get_component::<Velocity>(&entity).unwrap().value, 2);
+	assert_eq!(ecs.get_component::<Selected>(&entity).unwrap().value, 3);
+	assert_eq!(ecs.get_component::<Mass>(&entity).unwrap().value, 4);
+
+	let empty = ecs.spawn_empty().id();
+	assert_eq!(unsafe { (*empty.instance).archetype }, Archetype::default().index);
+}
+
+#[test]
+pub fn disabled_entities_are_skipped() {
+	let mut ecs = EcsContext::new();
+	let enabled = spawn!(ecs, Position { value: 1 });
+	let disabled = spawn!(ecs, Position { value: 2 });
+	spawn!(ecs, Position { value: 4 }, Selected { value: 0 });
+	ecs.add_component(&disabled, Disabled);
+
+	let mut sum = 0;
+	ecs.filter().include::<&Position>().for_each(|p| sum += p.value);
+	assert_eq!(sum, 1 + 4, "Disabled entity was visited");
+
+	let mut sum = 0;
+	ecs.filter().include::<&Position>().include_disabled().for_each(|p| sum += p.value);
+	assert_eq!(sum, 1 + 2 + 4);
+
+	let mut sum = 0;
+	ecs.filter().include::<&Position>().exclude::<&Selected>().for_each(|p| sum += p.value);
+	assert_eq!(sum, 1);
+
+	let mut sum = 0;
+	ecs.filter().include::<(&Position, &Disabled)>().for_each(|(p, _)| sum += p.value);
+	assert_eq!(sum, 2, "Explicitly included disabled entities were skipped");
+
+	let mut sum = 0;
+	ecs.filter_ref().include::<&Position>().include_disabled().for_each(|p| sum += p.value);
+	assert_eq!(sum, 1 + 2 + 4);
+
+	let query = EntityQuery::build().include::<&Position>().include_disabled().create();
+	assert_eq!(
+		ecs.explain_query(query).archetypes.iter().map(|a| a.entities).sum::<usize>(),
+		3
+	);
+
+	ecs.remove_component::<Disabled>(&disabled);
+	let mut sum = 0;
+	ecs.filter().include::<&Position>().for_each(|p| sum += p.value);
+	assert_eq!(sum, 1 + 2 + 4, "Re-enabled entity was skipped");
+	assert!(ecs.get_component::<Position>(&enabled).is_some());
+}
+
+#[test]
+pub fn entity_handles() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position { value: 1 });
+
+	let mut handle = ecs.entity_mut(&entity);
+	handle.get_mut::<Position>().unwrap().value += 1;
+	handle.insert(Velocity { value: 3 }).insert(Selected { value: 4 }).remove::<Position>();
+	assert!(!handle.contains::<Position>());
+	assert_eq!(handle.get::<Velocity>().unwrap().value, 3);
+	let archetype = handle.archetype();
+
+	let handle = ecs.entity(&entity);
+	assert_eq!(handle.archetype(), archetype);
+	assert!(handle.contains::<Selected>());
+	assert_eq!(handle.get::<Selected>().unwrap().value, 4);
+	assert!(handle.get::<Position>().is_none());
+	assert!(ecs.archetype_contains::<Velocity>(archetype));
+
+	let mut handle = ecs.entity_mut(&entity);
+	handle.insert(Position { value: 5 });
+	assert_eq!(ecs.get_component::<Position>(&entity).unwrap().value, 5);
+}
+
+#[test]
+pub fn spawn_initialized_entities() {
+	let mut ecs = EcsContext::with_capacity(16);
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities = ecs.spawn_initialized(archetype, 50, |i, mut entity| {
+		entity.get_mut::<Position>().unwrap().value = i as u32;
+		if i % 5 == 0 {
+			entity.insert(Velocity { value: i as u32 * 2 });
+		}
+	});
+
+	assert_eq!(entities.len(), 50);
+	for (i, entity) in entities.iter().enumerate() {
+		assert_eq!(ecs.get_component::<Position>(entity).unwrap().value, i as u32);
+		let velocity = ecs.get_component::<Velocity>(entity).map(|v| v.value);
+		assert_eq!(velocity, (i % 5 == 0).then_some(i as u32 * 2));
+	}
+}
+
+#[test]
+pub fn validate_entity_batches() {
+	let mut ecs = EcsContext::with_capacity(4);
+	let archetype = create_archetype!(ecs, [Position]);
+	let mut entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 8).collect();
+	ecs.destroy_entities(&entities[2..4]);
+
+	let mut other = EcsContext::new();
+	entities.push(other.create_entity());
+	entities.push(Entity::default());
+
+	let expected = [true, true, false, false, true, true, true, true, false, false];
+	assert_eq!(ecs.validate_entities(&entities), expected);
+	assert!(ecs.validate_entities(&[]).is_empty());
+}
+
+#[test]
+pub fn watch_single_entity_component() {
+	let mut ecs = EcsContext::new();
+	let watched = spawn!(ecs, Health { value: 10 });
+	let other = spawn!(ecs, Health { value: 10 });
+
+	let seen = Rc::new(Cell::new(0));
+	let calls = Rc::new(Cell::new(0));
+	let (seen_clone, calls_clone) = (seen.clone(), calls.clone());
+	ecs.watch_component::<Health>(&watched, move |health| {
+		seen_clone.set(health.value);
+		calls_clone.set(calls_clone.get() + 1);
+	});
+
+	ecs.get_component_mut::<Health>(&other).unwrap().value = 1;
+	ecs.add_component(&watched, Position { value: 1 });
+	ecs.get_component_mut::<Position>(&watched).unwrap().value = 2;
+	ecs.advance_tick();
+	assert_eq!(calls.get(), 0, "Unrelated writes invoked the callback");
+
+	ecs.get_component_mut::<Health>(&watched).unwrap().value = 7;
+	ecs.get_component_mut::<Health>(&watched).unwrap().value -= 2;
+	ecs.advance_tick();
+	assert_eq!((calls.get(), seen.get()), (1, 5));
+
+	ecs.advance_tick();
+	assert_eq!(calls.get(), 1);
+
+	ecs.remove_component::<Health>(&watched);
+	ecs.add_component(&watched, Health { value: 3 });
+	ecs.advance_tick();
+	assert_eq!((calls.get(), seen.get()), (2, 3));
+
+	ecs.get_component_mut::<Health>(&watched).unwrap().value = 4;
+	ecs.destroy_entities(&[watched]);
+	let reused = spawn!(ecs, Health { value: 8 });
+	ecs.get_component_mut::<Health>(&reused).unwrap().value = 9;
+	ecs.advance_tick();
+	assert_eq!(calls.get(), 2, "Watch outlived its entity");
+}
+
+#[test]
+pub fn yielded_entities_are_valid_handles() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 8).collect();
+	for (i, entity) in entities.iter().enumerate() {
+		ecs.get_component_mut::<Position>(entity).unwrap().value = i as u32;
+	}
+
+	// Moved entities must be yielded through the handle stored by their new archetype
+	ecs.destroy_entities(&entities[6..]);
+	ecs.add_component(&entities[1], Velocity { value: 1 });
+	let respawned = spawn!(ecs, Position { value: 6 });
+
+	let same =
+		|a: &Entity, b: &Entity| (a.instance, a.version, a.registry_id) == (b.instance, b.version, b.registry_id);
+	let check = |ecs: &EcsContext, yielded: Vec<(Entity, u32)>| {
+		assert_eq!(yielded.len(), 7);
+		for (entity, value) in yielded {
+			assert_eq!(ecs.get_component::<Position>(&entity).unwrap().value, value);
+			let original = entities[..6].iter().chain([&respawned]).find(|e| same(e, &entity));
+			assert!(original.is_some(), "Yielded handle does not match any live entity");
+		}
+	};
+
+	let mut yielded = vec![];
+	ecs.filter().include::<&mut Position>().entities_for_each(|entity, position| {
+		position.value += 10;
+		yielded.push((entity, position.value));
+	});
+	check(&ecs, yielded);
+
+	let yielded = ecs.filter().include::<&mut Position>().into_iter().with_entities();
+	let yielded = yielded.map(|(entity, position)| (entity, position.value)).collect();
+	check(&ecs, yielded);
+
+	// Entities are not Send, so their parts are compared instead
+	let yielded = std::sync::Mutex::new(vec![]);
+	ecs.filter().include::<&Position>().par_entities_for_each(|entity, position| {
+		yielded.lock().unwrap().push((entity.instance as usize, entity.version, position.value));
+	});
+	let mut yielded = yielded.into_inner().unwrap();
+	let live = entities[..6].iter().chain([&respawned]);
+	let value = |e: &Entity| ecs.get_component::<Position>(e).unwrap().value;
+	let mut expected: Vec<_> = live.map(|e| (e.instance as usize, e.version, value(e))).collect();
+	expected.sort();
+	yielded.sort();
+	assert_eq!(yielded, expected);
+}
+
+#[test]
+pub fn lenient_entity_checks() {
+	let mut ecs = EcsContext::new();
+	let entity = spawn!(ecs, Position { value: 1 });
+	let foreign = EcsContext::new().create_entity();
+	ecs.destroy_entities(std::slice::from_ref(&entity));
+
+	ecs.set_strict_entity_checks(false);
+	assert!(ecs.get_component::<Position>(&entity).is_none());
+	assert!(ecs.get_component_mut::<Position>(&foreign).is_none());
+	assert!(!ecs.add_component(&entity, Selected { value: 1 }));
+	assert!(!ecs.add_component(&foreign, Position { value: 1 }));
+	assert!(!ecs.remove_component::<Position>(&entity));
+
+	let alive = spawn!(ecs, Position { value: 2 });
+	assert_eq!(ecs.get_component::<Position>(&alive).unwrap().value, 2);
+
+	ecs.set_strict_entity_checks(true);
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+		ecs.get_component::<Position>(&entity);
+	}));
+	assert!(result.is_err(), "Strict checks did not panic");
+}
+
+#[test]
+pub fn empty_archetypes_are_skipped() {
+	let mut ecs = EcsContext::new();
+	let emptied = create_archetype!(ecs, [Position]);
+	let populated = create_archetype!(ecs, [Position, Disabled]);
+
+	let entities: Vec<_> = ecs.create_entities_from_archetype(emptied, 64).collect();
+	ecs.destroy_entities(&entities);
+	let _ = ecs.create_entities_from_archetype(populated, 2);
+
+	let query = EntityQuery::build().include::<&Position>().create();
+	let matched: Vec<_> = ecs.explain_query(query).archetypes.iter().map(|a| a.archetype).collect();
+	assert_eq!(matched, vec![emptied], "Empty archetypes should still be matched");
+
+	let mut visited = 0;
+	ecs.filter().include::<&mut Position>().for_each(|_| visited += 1);
+	ecs.filter().include::<&mut Position>().entities_for_each(|_, _| visited += 1);
+	visited += ecs.filter().include::<&Position>().into_iter().count();
+	ecs.filter_ref().include::<&Position>().for_each(|_| visited += 1);
+	assert_eq!(visited, 0);
+
+	let visited = AtomicIsize::new(0);
+	ecs.filter().include::<&Position>().par_for_each(|_| {
+		visited.fetch_add(1, Ordering::Relaxed);
+	});
+	assert_eq!(visited.load(Ordering::Relaxed), 0);
+
+	let mut visited = 0;
+	ecs.filter().include::<&Position>().include_disabled().for_each(|_| visited += 1);
+	assert_eq!(visited, 2, "Populated archetypes should still be iterated");
+}
+
+#[test]
+pub fn component_added_ticks() {
+	let mut ecs = EcsContext::new();
+	ecs.track_added_ticks::<Position>();
+	ecs.track_added_ticks::<Selected>();
+
+	let archetype = create_archetype!(ecs, [Position]);
+	let spawned: Vec<_> = ecs.create_entities_from_archetype(archetype, 2).collect();
+	let late = ecs.create_entity();
+	assert_eq!(ecs.component_added_tick::<Position>(&spawned[0]), Some(0));
+	assert_eq!(ecs.component_added_tick::<Position>(&late), None);
+
+	ecs.advance_tick();
+	ecs.advance_tick();
+	assert_eq!(ecs.tick(), 2);
+
+	ecs.add_component(&late, Position::default());
+	ecs.add_component(&late, Selected::default());
+	ecs.get_component_mut::<Position>(&spawned[0]).unwrap().value = 1;
+	assert_eq!(ecs.component_added_tick::<Position>(&late), Some(2));
+	assert_eq!(ecs.component_added_tick::<Selected>(&late), Some(2));
+	assert_eq!(
+		ecs.component_added_tick::<Position>(&spawned[0]),
+		Some(0),
+		"Mutations should not affect the added tick"
+	);
+
+	ecs.advance_tick();
+	ecs.remove_component::<Position>(&spawned[1]);
+	assert_eq!(ecs.component_added_tick::<Position>(&spawned[1]), None);
+	ecs.add_component(&spawned[1], Position::default());
+	assert_eq!(ecs.component_added_tick::<Position>(&spawned[1]), Some(3));
+
+	ecs.destroy_entities(std::slice::from_ref(&late));
+	let reused = ecs.create_entity();
+	assert_eq!(ecs.component_added_tick::<Selected>(&reused), None);
+}
+
+#[test]
+pub fn par_map_collect_is_ordered() {
+	let mut ecs = EcsContext::new();
+	let plain = create_archetype!(ecs, [Position]);
+	let disabled = create_archetype!(ecs, [Position, Disabled]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(plain, 5000).collect();
+	let _ = ecs.create_entities_from_archetype(disabled, 100);
+
+	let destroyed: Vec<_> = entities.iter().step_by(7).cloned().collect();
+	ecs.destroy_entities(&destroyed);
+	for entity in entities.iter().enumerate().filter(|(i, _)| i % 3 == 0 && i % 7 != 0).map(|(_, e)| e) {
+		ecs.add_component(entity, Selected::default());
+	}
+
+	let mut value = 0;
+	ecs.filter().include::<&mut Position>().include_disabled().for_each(|p| {
+		p.value = value;
+		value += 1;
+	});
+
+	let key = |entity: Entity, p: &Position| (entity.instance as usize, entity.version, p.value);
+	let mut sequential = vec![];
+	ecs.filter()
+		.include::<&Position>()
+		.include_disabled()
+		.entities_for_each(|e, p| sequential.push(key(e, p)));
+	let parallel = ecs.filter().include::<&Position>().include_disabled().par_map_collect(key);
+	assert_eq!(parallel, sequential);
+
+	let mut sequential = vec![];
+	ecs.filter()
+		.include::<(&Position, &Selected)>()
+		.entities_for_each(|e, (p, _)| sequential.push(key(e, p)));
+	let parallel = ecs.filter().include::<(&Position, &Selected)>().par_map_collect(|e, (p, _)| key(e, p));
+	assert_eq!(parallel, sequential);
+
+	let mut sequential = vec![];
+	ecs.filter().include::<&Position>().take(10).entities_for_each(|e, p| sequential.push(key(e, p)));
+	let parallel = ecs.filter().include::<&Position>().take(10).par_map_collect(key);
+	assert_eq!(parallel.len(), 10);
+	assert_eq!(parallel, sequential);
+}
+
+#[test]
+pub fn prefetched_iteration_matches_for_each() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Position]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(archetype, 2000).collect();
+
+	// Fragments the slots, so that tiles cross used range boundaries.
+	let destroyed: Vec<_> = entities.iter().skip(250).step_by(11).cloned().collect();
+	ecs.destroy_entities(&destroyed);
+	for (i, entity) in entities.iter().enumerate().filter(|(i, _)| i % 5 == 0 && (*i < 250 || (i - 250) % 11 != 0)) {
+		ecs.add_component(entity, Selected { value: i as u32 });
+	}
+
+	let mut value = 0;
+	ecs.filter().include::<&mut Position>().for_each_prefetched(|p| {
+		p.value = value;
+		value += 1;
+	});
+
+	let mut expected = vec![];
+	ecs.filter().include::<&Position>().for_each(|p| expected.push(p.value));
+	assert_eq!(expected, (0..value).collect::<Vec<_>>());
+
+	let mut expected = vec![];
+	ecs.filter()
+		.include::<(&Position, &Selected)>()
+		.for_each(|(p, s)| expected.push((p.value, s.value)));
+	let mut prefetched = vec![];
+	ecs.filter()
+		.include::<(&Position, &Selected)>()
+		.for_each_prefetched(|(p, s)| prefetched.push((p.value, s.value)));
+	assert_eq!(prefetched, expected);
+	assert!(!prefetched.is_empty());
+}
+
+#[test]
+pub fn len_hint_matches_collected() {
+	let mut ecs = EcsContext::new();
+	let plain = create_archetype!(ecs, [Position]);
+	let disabled = create_archetype!(ecs, [Position, Disabled]);
+	let entities: Vec<_> = ecs.create_entities_from_archetype(plain, 40).collect();
+	let _ = ecs.create_entities_from_archetype(disabled, 7);
+	let _ = ecs.create_entities_from_archetype(Archetype::default(), 12);
+	ecs.destroy_entities(&entities[..5]);
+
+	let hint = ecs.filter().include::<&Position>().len_hint();
+	let collected = ecs.filter().include::<&Position>().collect_entities().len();
+	assert_eq!(hint, 35);
+	assert_eq!(hint, collected);
+
+	let filter = ecs.filter().include::<&Position>().include_disabled();
+	assert_eq!(filter.len_hint(), 42);
+	assert_eq!(filter.par_map_collect(|_, p| p.value).len(), 42);
+
+	assert_eq!(ecs.filter().len_hint(), ecs.filter().collect_entities().len());
+	assert_eq!(ecs.filter().include::<&Position>().take(3).len_hint(), 3);
+
+	ecs.add_component(&entities[10], Selected::default());
+	let hint = ecs.filter().include::<(&Position, &Selected)>().len_hint();
+	assert!(hint >= ecs.filter().include::<(&Position, &Selected)>().collect_entities().len());
+}
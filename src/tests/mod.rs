@@ -1,3 +1,9 @@
 mod range_allocator_tests;
-
-pub use range_allocator_tests::*;
+mod entity_registry_tests;
+mod archetype_macros_tests;
+mod archetype_tests;
+mod bit_field_tests;
+mod entity_query_tests;
+mod system_tests;
+mod component_tests;
+mod pool_tests;
@@ -0,0 +1,39 @@
+use crate::data_structures::{Pool, SyncPool};
+use rayon::prelude::*;
+
+#[test]
+pub fn pool_reuses_values() {
+	let mut pool = Pool::<Vec<u32>>::default();
+	let capacity = {
+		let mut values = pool.take_one();
+		values.extend(0..100);
+		values.capacity()
+	};
+
+	let mut values = pool.take_one();
+	values.clear();
+	assert_eq!(values.capacity(), capacity);
+}
+
+#[test]
+pub fn sync_pool_from_parallel_tasks() {
+	let pool = SyncPool::<Vec<usize>>::default();
+	let sums: Vec<usize> = (0..256usize)
+		.into_par_iter()
+		.map(|i| {
+			let mut scratch = pool.take_one();
+			scratch.clear();
+			scratch.extend(0..i);
+			scratch.iter().sum()
+		})
+		.collect();
+
+	for (i, sum) in sums.iter().enumerate() {
+		assert_eq!(*sum, (0..i).sum::<usize>());
+	}
+
+	let available = pool.available();
+	assert!(available >= 1 && available <= rayon::current_num_threads() * 2);
+	let _borrowed = pool.take_one();
+	assert_eq!(pool.available(), available - 1);
+}
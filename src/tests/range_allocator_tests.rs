@@ -1,4 +1,6 @@
-use crate::data_structures::RangeAllocator;
+#![allow(clippy::single_range_in_vec_init)]
+
+use crate::data_structures::{RangeAllocator, RangeFreeError};
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 
@@ -86,3 +88,128 @@ pub fn fragmented_deallocation() {
 		"Available space does not match expected space"
 	);
 }
+
+#[test]
+pub fn used_ranges_are_ascending() {
+	let count = 256;
+
+	let mut allocator = RangeAllocator::new();
+	allocator.allocate(4 * count);
+
+	let mut ranges = (0..count).map(|i| i * 4..(i + 1) * 4).collect::<Vec<_>>();
+	ranges.shuffle(&mut thread_rng());
+	for range in &ranges[..count / 2] {
+		allocator.free(range.clone());
+	}
+
+	let mut fragments = vec![];
+	allocator.allocate_fragmented(count, &mut fragments);
+
+	let used = allocator.used_ranges().collect::<Vec<_>>();
+	for pair in used.windows(2) {
+		assert!(
+			pair[0].end < pair[1].start,
+			"Used ranges are not ascending and disjoint"
+		);
+	}
+
+	let mut slots = vec![false; allocator.capacity()];
+	for range in used.iter().chain(allocator.free_ranges().collect::<Vec<_>>().iter()) {
+		for slot in range.clone() {
+			assert!(!slots[slot], "Used and free ranges overlap");
+			slots[slot] = true;
+		}
+	}
+	assert!(
+		slots.iter().all(|s| *s),
+		"Used and free ranges do not cover the allocator"
+	);
+	assert_eq!(used.iter().map(|r| r.len()).sum::<usize>(), allocator.used());
+}
+
+#[test]
+pub fn double_free_is_detected() {
+	let mut allocator = RangeAllocator::new();
+	allocator.allocate(64);
+
+	assert_eq!(allocator.try_free(16..32), Ok(()));
+	assert_eq!(allocator.try_free(16..32), Err(RangeFreeError::NotAllocated));
+	assert_eq!(allocator.try_free(24..40), Err(RangeFreeError::NotAllocated));
+	assert_eq!(allocator.try_free(60..80), Err(RangeFreeError::OutOfBounds));
+	assert_eq!(allocator.used(), 48, "Failed frees should not change the used space");
+
+	assert_eq!(allocator.try_free(32..64), Ok(()));
+	assert_eq!(allocator.try_free(0..16), Ok(()));
+	assert_eq!(allocator.used(), 0);
+	assert_eq!(allocator.free_ranges().collect::<Vec<_>>(), [0..64]);
+}
+
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "Range is not allocated"))]
+pub fn double_free_panics_in_debug() {
+	let mut allocator = RangeAllocator::new();
+	allocator.allocate(16);
+	allocator.free(0..8);
+	allocator.free(4..8);
+}
+
+#[test]
+pub fn aligned_allocation() {
+	let mut allocator = RangeAllocator::with_capacity(64);
+	allocator.allocate(3);
+
+	for align in [1, 4, 8, 16] {
+		let range = allocator.try_allocate_aligned(5, align).unwrap();
+		assert_eq!(range.start % align, 0, "Range is not aligned to {align}");
+		assert_eq!(range.len(), 5);
+	}
+	assert_eq!(allocator.used(), 23);
+
+	let used = allocator.used_ranges().map(|r| r.len()).sum::<usize>();
+	assert_eq!(used, allocator.used(), "Skipped space should remain free");
+
+	let needed = allocator.try_allocate_aligned(32, 32).unwrap_err();
+	allocator.reserve(needed);
+	let range = allocator.try_allocate_aligned(32, 32).unwrap();
+	assert_eq!(range, 64..96);
+	assert_eq!(allocator.try_allocate_aligned(4, 4), Ok(24..28));
+}
+
+#[test]
+pub fn reset_to_single_range() {
+	let mut allocator = RangeAllocator::with_capacity(64);
+	for _ in 0..8 {
+		allocator.allocate(10);
+	}
+	allocator.free(10..20);
+	allocator.free(40..45);
+
+	allocator.reset();
+	assert_eq!(allocator.free_ranges().collect::<Vec<_>>(), [0..allocator.capacity()]);
+	assert_eq!(allocator.available(), allocator.capacity());
+	assert_eq!(allocator.used_ranges().count(), 0);
+
+	let mut empty = RangeAllocator::new();
+	empty.reset();
+	assert_eq!(empty.free_ranges().count(), 0);
+}
+
+#[test]
+pub fn reset_to_mark() {
+	let mut allocator = RangeAllocator::with_capacity(32);
+	allocator.allocate(8);
+	allocator.allocate(8);
+	allocator.free(0..8);
+	let mark = allocator.mark();
+
+	allocator.allocate(16);
+	allocator.allocate(16);
+	allocator.free(8..16);
+	assert_eq!(allocator.capacity(), 48);
+
+	allocator.reset_to(&mark);
+	assert_eq!(allocator.free_ranges().collect::<Vec<_>>(), [0..8, 16..48]);
+	assert_eq!(allocator.used_ranges().collect::<Vec<_>>(), [8..16]);
+	assert_eq!(allocator.used(), 8);
+	assert_eq!(allocator.available(), 40);
+}
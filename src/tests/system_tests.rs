@@ -0,0 +1,167 @@
+use crate::entities::{CommandBuffer, EntityFilterForEach, EntityQuery, EntityRegistry};
+use crate::components::Component;
+use crate::archetypes::Archetype;
+use crate::context::EcsContext;
+use crate::systems::{System, SystemSetup};
+use crate::create_archetype;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[derive(Default, Component)]
+struct Bullet {
+	speed: u32,
+}
+
+fn count_bullets(entities: &EntityRegistry) -> usize {
+	let query = EntityQuery::build().include::<&Bullet>().create();
+	entities.explain_query(query).archetypes.iter().map(|a| a.entities).sum()
+}
+
+struct Spawner {
+	archetype: Archetype,
+}
+
+impl System for Spawner {
+	fn run(&mut self, entities: &mut EntityRegistry, commands: &mut CommandBuffer) {
+		for speed in 1..=3 {
+			commands.spawn(self.archetype, move |registry, entity| {
+				registry.get_component_mut::<Bullet>(&entity).unwrap().speed = speed;
+			});
+		}
+		assert_eq!(count_bullets(entities), 0, "Spawns were not deferred");
+	}
+}
+
+struct Counter;
+
+impl System for Counter {
+	fn run(&mut self, entities: &mut EntityRegistry, _: &mut CommandBuffer) {
+		assert_eq!(
+			count_bullets(entities),
+			3,
+			"Commands were not flushed after the previous system"
+		);
+	}
+}
+
+#[test]
+pub fn system_commands_flush_after_run() {
+	let mut ecs = EcsContext::new();
+	let archetype = create_archetype!(ecs, [Bullet]);
+	ecs.register_system(Spawner { archetype });
+	ecs.register_system(Counter);
+	ecs.setup_systems();
+	ecs.run_systems();
+
+	let mut speeds = vec![];
+	ecs.filter().include::<&Bullet>().for_each(|bullet| speeds.push(bullet.speed));
+	speeds.sort();
+	assert_eq!(speeds, [1, 2, 3]);
+}
+
+struct Physics {
+	steps: Rc<Cell<u32>>,
+}
+
+impl System for Physics {
+	fn run(&mut self, _: &mut EntityRegistry, _: &mut CommandBuffer) {
+		self.steps.set(self.steps.get() + 1);
+	}
+}
+
+#[test]
+pub fn run_criteria() {
+	let paused = Rc::new(Cell::new(false));
+	let steps = Rc::new(Cell::new(0));
+
+	let mut ecs = EcsContext::new();
+	let criteria = paused.clone();
+	ecs.register_system_with(Physics { steps: steps.clone() }, move |_| !criteria.get());
+	ecs.setup_systems();
+
+	ecs.run_systems();
+	paused.set(true);
+	ecs.run_systems();
+	ecs.run_systems();
+	assert_eq!(steps.get(), 1);
+
+	paused.set(false);
+	ecs.run_systems();
+	assert_eq!(steps.get(), 2);
+}
+
+#[derive(Default, Component)]
+struct Renderable;
+
+struct Renderer {
+	entered: Rc<Cell<usize>>,
+}
+
+impl System for Renderer {
+	fn run(&mut self, entities: &mut EntityRegistry, _: &mut CommandBuffer) {
+		let entered = self.entered.get() + entities.entities_entered::<Renderable>().len();
+		self.entered.set(entered);
+	}
+}
+
+#[test]
+pub fn entities_entered_once() {
+	let entered = Rc::new(Cell::new(0));
+	let mut ecs = EcsContext::new();
+	ecs.track_structural_changes::<Renderable>();
+	ecs.register_system(Renderer {
+		entered: entered.clone(),
+	});
+	ecs.setup_systems();
+
+	let entity = ecs.create_entity();
+	ecs.add_component(&entity, Renderable);
+	assert!(ecs.entities_entered::<Renderable>().is_empty());
+
+	for _ in 0..3 {
+		ecs.run_systems();
+	}
+	assert_eq!(entered.get(), 1);
+
+	ecs.remove_component::<Renderable>(&entity);
+	ecs.run_systems();
+	assert_eq!(ecs.entities_exited::<Renderable>().len(), 1);
+	assert!(ecs.entities_entered::<Renderable>().is_empty());
+	assert_eq!(entered.get(), 1);
+}
+
+#[derive(Default)]
+struct Armory {
+	archetype: Option<Archetype>,
+}
+
+impl System for Armory {
+	fn setup(&mut self, context: &mut SystemSetup) {
+		let archetype = create_archetype!(context, [Bullet]);
+		context.warm_query(EntityQuery::build().include::<&Bullet>().create());
+		self.archetype = Some(archetype);
+	}
+
+	fn run(&mut self, entities: &mut EntityRegistry, _: &mut CommandBuffer) {
+		let archetype = self.archetype.expect("Archetype was not created during setup");
+		let _ = entities.create_entities_from_archetype(archetype, 2);
+	}
+}
+
+#[test]
+pub fn archetypes_created_during_setup() {
+	let mut ecs = EcsContext::new();
+	ecs.register_system(Armory::default());
+	ecs.setup_systems();
+
+	let query = EntityQuery::build().include::<&Bullet>().create();
+	assert!(
+		ecs.archetype_store.is_query_initialized(query),
+		"Query was not warmed during setup"
+	);
+	assert_eq!(count_bullets(&ecs), 0);
+
+	ecs.run_systems();
+	ecs.run_systems();
+	assert_eq!(count_bullets(&ecs), 4);
+}
@@ -0,0 +1,14 @@
+use turbo_ecs::prelude::*;
+
+#[derive(Default, Component)]
+#[component(immutable)]
+struct Id(u64);
+
+fn main() {
+    let mut ecs = EcsContext::new();
+    let entity = ecs.create_entity();
+    ecs.add_component(&entity, Id(7));
+
+    ecs.get_component_mut::<Id>(&entity).unwrap().0 = 8;
+    ecs.filter().include::<&mut Id>().for_each(|id| id.0 += 1);
+}
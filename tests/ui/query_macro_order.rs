@@ -0,0 +1,14 @@
+use turbo_ecs::prelude::*;
+
+#[derive(Default, Component)]
+struct A;
+
+#[derive(Default, Component)]
+struct B;
+
+#[derive(Default, Component)]
+struct C;
+
+fn main() {
+    let _ = query!(&A; without B; with C);
+}
@@ -0,0 +1,14 @@
+use turbo_ecs::prelude::*;
+use std::cell::Cell;
+
+#[derive(Default, Component)]
+struct Counter(Cell<u32>);
+
+fn main() {
+    let ecs = EcsContext::new();
+    let view = ecs.view();
+    std::thread::scope(|scope| {
+        scope.spawn(|| view.filter().include::<&Counter>().for_each(|c| c.0.set(c.0.get() + 1)));
+        scope.spawn(|| view.filter().include::<&Counter>().par_for_each(|c| c.0.set(c.0.get() + 1)));
+    });
+}
@@ -1,35 +1,144 @@
 use quote::{format_ident, quote};
 use proc_macro::TokenStream;
-use syn::DeriveInput;
+use syn::{parse_quote, DeriveInput, Lit, Meta, NestedMeta};
+use crate::crate_path;
 
 pub fn impl_component(ast: &DeriveInput) -> TokenStream {
+    let attributes = match ComponentAttributes::parse(ast) {
+        Ok(attributes) => attributes,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let name = &ast.ident;
+    let storage = format_ident!("{}", attributes.storage);
+    let version = attributes.version;
+    let krate = attributes.krate.unwrap_or_else(crate_path::resolve);
 
-    let name_str = name.to_string().to_uppercase();
-    let id_name = format_ident!("__COMPONENT_ID_OF_{}", name_str);
+    // Every instantiation of a generic component needs its own id,
+    // so generic types are looked up by TypeId instead of holding a static.
+    let mut generics = ast.generics.clone();
+    let is_generic = generics.type_params().next().is_some();
+    let type_params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    for param in type_params {
+        generics.make_where_clause().predicates.push(parse_quote!(#param: 'static));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mutable = match attributes.immutable {
+        true => quote! {},
+        false => quote! {
+            impl #impl_generics #krate::components::MutableComponent for #name #ty_generics #where_clause {}
+        },
+    };
+
+    let id_body = match is_generic {
+        true => quote! { #krate::components::component_id::get_generic::<Self>() },
+        false => quote! {
+            static ID: ::std::sync::OnceLock<#krate::components::component_id::ComponentId> =
+                ::std::sync::OnceLock::new();
+            *ID.get_or_init(|| unsafe { #krate::components::component_id::get_next_of::<Self>() })
+        },
+    };
 
     let gen = quote! {
-        turbo_ecs::lazy_static! {
-            static ref #id_name: turbo_ecs::components::component_id::ComponentId = unsafe {
-                turbo_ecs::components::component_id::get_next()
-            };
-        }
+        impl #impl_generics #krate::components::Component for #name #ty_generics #where_clause {
+            const STORAGE: #krate::components::ComponentStorage =
+                #krate::components::ComponentStorage::#storage;
+
+            const VERSION: u32 = #version;
+
+            #[inline(always)]
+            fn component_id() -> #krate::components::component_id::ComponentId {
+                #id_body
+            }
 
-        impl turbo_ecs::components::Component for #name {
             #[inline(always)]
-            fn component_id() -> turbo_ecs::components::component_id::ComponentId {
-                *#id_name
+            fn clone_fn() -> ::core::option::Option<#krate::components::CloneFn> {
+                use #krate::components::{CloneProbeClone as _, CloneProbeFallback as _};
+                (&#krate::components::CloneProbe::<Self>::new()).clone_fn()
+            }
+
+            #[inline(always)]
+            fn default_fn() -> ::core::option::Option<#krate::components::DefaultFn> {
+                use #krate::components::{DefaultProbeDefault as _, DefaultProbeFallback as _};
+                (&#krate::components::DefaultProbe::<Self>::new()).default_fn()
             }
         }
 
-        impl turbo_ecs::components::ComponentTypeInfo for #name {
-            type ComponentType = #name;
+        impl #impl_generics #krate::components::ComponentTypeInfo for #name #ty_generics #where_clause {
+            type ComponentType = Self;
+
+            const STORAGE: #krate::components::ComponentStorage =
+                <Self as #krate::components::Component>::STORAGE;
 
             #[inline(always)]
-            fn component_id() -> turbo_ecs::components::component_id::ComponentId {
-                turbo_ecs::components::component_id::ComponentId::of::<#name>()
+            fn component_id() -> #krate::components::component_id::ComponentId {
+                #krate::components::component_id::ComponentId::of::<Self>()
             }
         }
+
+        #mutable
     };
     gen.into()
-}
\ No newline at end of file
+}
+
+/// The options specified through `#[component(...)]`.
+struct ComponentAttributes {
+    storage: &'static str,
+    version: u32,
+    immutable: bool,
+    krate: Option<syn::Path>,
+}
+
+impl ComponentAttributes {
+    fn parse(ast: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = Self {
+            storage: "Table",
+            version: 1,
+            immutable: false,
+            krate: None,
+        };
+
+        for attr in ast.attrs.iter().filter(|a| a.path.is_ident("component")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(syn::Error::new_spanned(meta, "expected #[component(...)]")),
+            };
+
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("storage") => {
+                        attributes.storage = match &value.lit {
+                            Lit::Str(s) if s.value() == "table" => "Table",
+                            Lit::Str(s) if s.value() == "sparse" => "Sparse",
+                            lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "expected \"table\" or \"sparse\"",
+                                ))
+                            }
+                        };
+                    }
+                    NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("version") => {
+                        attributes.version = match &value.lit {
+                            Lit::Int(i) => i.base10_parse()?,
+                            lit => return Err(syn::Error::new_spanned(lit, "expected an integer version")),
+                        };
+                    }
+                    NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("crate") => {
+                        attributes.krate = match &value.lit {
+                            Lit::Str(s) => Some(s.parse()?),
+                            lit => return Err(syn::Error::new_spanned(lit, "expected a crate path")),
+                        };
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("immutable") => {
+                        attributes.immutable = true;
+                    }
+                    nested => return Err(syn::Error::new_spanned(nested, "unknown component option")),
+                }
+            }
+        }
+
+        Ok(attributes)
+    }
+}
@@ -0,0 +1,18 @@
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::format_ident;
+use syn::parse_quote;
+
+const CRATE_NAME: &str = "turbo_ecs";
+
+/// Resolves the path through which the deriving crate refers to `turbo_ecs`.
+///
+/// Falls back to `::turbo_ecs` when the crate is not renamed or the manifest cannot be read.
+/// Inside `turbo_ecs` itself, `::turbo_ecs` is made available through `extern crate self`.
+pub fn resolve() -> syn::Path {
+    let name = match crate_name(CRATE_NAME) {
+        Ok(FoundCrate::Name(name)) => name,
+        Ok(FoundCrate::Itself) | Err(_) => CRATE_NAME.to_string(),
+    };
+    let ident = format_ident!("{}", name);
+    parse_quote!(::#ident)
+}
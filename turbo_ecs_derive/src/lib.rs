@@ -1,9 +1,10 @@
 mod component;
+mod crate_path;
 
 use proc_macro::TokenStream;
 use syn;
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
     component::impl_component(&ast)